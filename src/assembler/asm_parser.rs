@@ -73,7 +73,10 @@ fn parse_op(lexer: &mut Lexer) -> Opr {
             }
         }
         TokenType::OBracket => parse_mem(lexer, 0),
-        TokenType::Int(i) => i.into(),
+        TokenType::Int(i) => match i32::try_from(i) {
+            Ok(i) => i.into(),
+            Err(_) => i.into(),
+        },
         _ => panic!("Unsupported ASM operation!"),
     }
 }
@@ -87,9 +90,10 @@ fn parse_mem(lexer: &mut Lexer, size: u8) -> Opr {
         TokenType::Plus | TokenType::Minus => {
             let sign = lexer.get_token_type();
             lexer.next_token();
-            let TokenType::Int(mut val) = lexer.get_token_type() else {
+            let TokenType::Int(val) = lexer.get_token_type() else {
                 panic!("expected an integer found {}", lexer.get_token_type());
             };
+            let mut val = val as i32;
             if sign == TokenType::Minus {
                 val = -val;
             }
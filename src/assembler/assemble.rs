@@ -4,13 +4,13 @@ use super::{
     instructions::{Instr, ModrmType, Opr, Oprs},
     memory::{MemAddr, MemAddrType},
     mnemonic::Mnemonic,
-    opcodes::opcode,
+    opcodes::{opcode, sse_prefix},
     register::Reg,
 };
 
 macro_rules! Register {
     ($a:ident) => {
-        Opr::R64($a) | Opr::R32($a) | Opr::R16($a) | Opr::R8($a)
+        Opr::R64($a) | Opr::R32($a) | Opr::R16($a) | Opr::R8($a) | Opr::Xmm($a)
     };
 }
 
@@ -24,6 +24,9 @@ pub fn assemble_instr(instr: &Instr) -> IBytes {
     let mut bytes = vec![];
     validate_opr_sizes(instr);
     let instr = align_imm_oprs_to_reg(instr);
+    if let Some(prefix) = sse_prefix(&instr) {
+        bytes.push(prefix);
+    }
     bytes.extend(rex(&instr));
     let (mut opcode, modrmtype) = opcode(&instr);
     let mut modrm_val = Vec::<u8>::new();
@@ -59,17 +62,23 @@ fn include_imm_values(bytes: &mut IBytes, instr: &Instr) {
                 1 => {
                     bytes.push(val.to_le_bytes()[0]);
                 }
-                2 | 4 | 8 => {
+                2 => {
+                    bytes.extend(val.to_le_bytes().iter().take(2));
+                }
+                4 | 8 => {
                     bytes.extend(val.to_le_bytes().iter().take(4));
                 }
                 _ => unreachable!(),
             },
             Oprs::Two(Opr::Mem(m), r_8_64!())
             | Oprs::Two(r_8_64!(), Opr::Mem(m))
-            | Oprs::One(Opr::Mem(m)) => {
-                if m.is_rela() {
-                    bytes.extend(0u32.to_le_bytes());
-                }
+            | Oprs::One(Opr::Mem(m))
+                if m.is_rela() =>
+            {
+                bytes.extend(0u32.to_le_bytes());
+            }
+            Oprs::Two(Opr::R64(_), Opr::Imm32(val)) => {
+                bytes.extend(val.to_le_bytes().iter().take(4));
             }
             Oprs::Two(
                 Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r),
@@ -80,6 +89,14 @@ fn include_imm_values(bytes: &mut IBytes, instr: &Instr) {
             _ => (),
         }
     } else {
+        match &instr.oprs {
+            Oprs::Two(Opr::Mem(m), _) | Oprs::Two(_, Opr::Mem(m)) | Oprs::One(Opr::Mem(m))
+                if m.is_rela() =>
+            {
+                bytes.extend(0u32.to_le_bytes());
+            }
+            _ => (),
+        }
         match instr.oprs {
             Oprs::Two(_, Opr::Imm8(val)) | Oprs::One(Opr::Imm8(val)) => {
                 bytes.extend(val.to_le_bytes().iter().take(1));
@@ -115,17 +132,19 @@ fn rm_rex(r1: &Reg, mem: &MemAddr) -> IBytes {
     if r1.size() == 16 {
         bytes.push(0x66);
     }
-    if rex != 0x40 {
+    if rex != 0x40 || r1.is_new_8bit_reg() {
         bytes.push(rex);
     }
     bytes
 }
 
-fn r_rex(r: &Reg) -> IBytes {
+/// Rex prefix for a single register operand, extended registers in the reg field of
+/// modrm use rex.r and extended registers in rm or opcode field use rex.b
+fn r_rex(r: &Reg, in_reg_field: bool) -> IBytes {
     let mut bytes = vec![];
     let mut rex: u8 = 0x40;
     if r.is_extended() {
-        rex |= 0b0100;
+        rex |= if in_reg_field { 0b0100 } else { 0b0001 };
     }
     if r.size() == 64 {
         rex |= 0b1000;
@@ -139,15 +158,76 @@ fn r_rex(r: &Reg) -> IBytes {
     bytes
 }
 
+/// Sse instructions only use general purpose registers as a source or
+/// destination of a conversion so W is decided by that operand
+fn sse_rex(instr: &Instr) -> IBytes {
+    let Oprs::Two(op1, op2) = &instr.oprs else {
+        unreachable!("{instr}: sse instructions expect two operands!");
+    };
+    let mut rex: u8 = 0x40;
+    match (op1, op2) {
+        (Opr::Xmm(r1), Opr::Xmm(r2)) => {
+            if r1.is_extended() {
+                rex |= 0b0100;
+            }
+            if r2.is_extended() {
+                rex |= 0b0001;
+            }
+        }
+        (Opr::Mem(mem), _) | (_, Opr::Mem(mem)) => {
+            if !mem.is_rela() {
+                if mem.get_register().is_extended() {
+                    rex |= 0b0001;
+                }
+                if let Some(s_reg) = mem.get_s_register() {
+                    if s_reg.is_extended() {
+                        rex |= 0b0010;
+                    }
+                }
+            }
+            let is_gpr_mem = matches!(instr.mnem, Mnemonic::Cvtsi2sd | Mnemonic::Movq);
+            if is_gpr_mem && mem.size == 8 {
+                rex |= 0b1000;
+            }
+        }
+        (Opr::R64(r) | Opr::R32(r), _) | (_, Opr::R64(r) | Opr::R32(r)) => {
+            if r.is_extended() {
+                if instr.mnem == Mnemonic::Cvttsd2si {
+                    rex |= 0b0100;
+                } else {
+                    rex |= 0b0001;
+                }
+            }
+            if r.size() == 64 {
+                rex |= 0b1000;
+            }
+        }
+        _ => unreachable!("{instr}: invalid operands for sse instruction!"),
+    }
+    if rex != 0x40 {
+        vec![rex]
+    } else {
+        vec![]
+    }
+}
+
 fn rex(instr: &Instr) -> IBytes {
+    if instr.mnem.is_sse() {
+        return sse_rex(instr);
+    }
     match &instr.oprs {
         Oprs::Two(Register!(r1), Register!(r2)) => {
             let mut bytes = vec![];
             let mut rex: u8 = 0x40;
-            if r1.is_extended() {
+            let (reg, rm) = if instr.mnem.reverse_modrm() {
+                (r1, r2)
+            } else {
+                (r2, r1)
+            };
+            if reg.is_extended() {
                 rex |= 0b0100;
             }
-            if r2.is_extended() {
+            if rm.is_extended() {
                 rex |= 0b0001;
             }
             if r1.size() == 64 {
@@ -163,11 +243,11 @@ fn rex(instr: &Instr) -> IBytes {
         }
         Oprs::Two(Register!(r1), Opr::Mem(mem)) | Oprs::Two(Opr::Mem(mem), Register!(r1)) => {
             if mem.is_rela() {
-                return r_rex(r1);
+                return r_rex(r1, true);
             }
             rm_rex(r1, mem)
         }
-        Oprs::Two(Register!(r), _) => r_rex(r),
+        Oprs::Two(Register!(r), _) => r_rex(r, false),
         Oprs::Two(Opr::Mem(mem), _) | Oprs::One(Opr::Mem(mem)) => {
             if mem.is_rela() {
                 todo!()
@@ -175,7 +255,7 @@ fn rex(instr: &Instr) -> IBytes {
             let mut bytes = vec![];
             let mut rex: u8 = 0x40;
             if mem.get_register().is_extended() {
-                rex |= 0b0100;
+                rex |= 0b0001;
             }
             if let Some(s_reg) = mem.get_s_register() {
                 if s_reg.is_extended() {
@@ -196,7 +276,7 @@ fn rex(instr: &Instr) -> IBytes {
         Oprs::One(Register!(r)) => {
             let mut rex: u8 = 0x40;
             if r.is_extended() {
-                rex |= 0b0100;
+                rex |= 0b0001;
             }
             if instr.mnem != Mnemonic::Push && instr.mnem != Mnemonic::Pop && r.size() == 64 {
                 rex |= 0b1000;
@@ -217,7 +297,7 @@ fn validate_opr_sizes(instr: &Instr) -> usize {
         let mut lhs_size;
         let rhs_size;
         match op1 {
-            Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r) => {
+            Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r) | Opr::Xmm(r) => {
                 lhs_size = r.size();
             }
             Opr::Mem(mem) => {
@@ -229,7 +309,7 @@ fn validate_opr_sizes(instr: &Instr) -> usize {
             Opr::Rela(_) | Opr::Loc(_) => unreachable!(),
         }
         match op2 {
-            Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r) => {
+            Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r) | Opr::Xmm(r) => {
                 rhs_size = r.size();
                 if lhs_size == 0 {
                     lhs_size = rhs_size;
@@ -262,9 +342,25 @@ fn validate_opr_sizes(instr: &Instr) -> usize {
 
 fn align_imm_oprs_to_reg(instr: &Instr) -> Instr {
     match (&instr.mnem, &instr.oprs) {
-        (Mnemonic::Mov, Oprs::Two(Opr::R64(r), Opr::Imm32(val) | Opr::Imm8(val))) => {
+        // Moving to the 32-bit register clears the upper half of the register
+        (
+            Mnemonic::Mov,
+            Oprs::Two(Opr::R64(r), Opr::Imm8(val) | Opr::Imm32(val) | Opr::Imm64(val)),
+        ) if u32::try_from(*val).is_ok() => {
             Instr::new2(Mnemonic::Mov, Opr::R32(r.convert(4)), Opr::Imm32(*val))
         }
+        // Negative values that fit in 32 bits are sign extended
+        (Mnemonic::Mov, Oprs::Two(Opr::R64(r), Opr::Imm8(val) | Opr::Imm64(val)))
+            if i32::try_from(*val).is_ok() =>
+        {
+            Instr::new2(Mnemonic::Mov, Opr::R64(*r), Opr::Imm32(*val))
+        }
+        (Mnemonic::Mov, Oprs::Two(Opr::R64(r), Opr::Imm32(val))) => {
+            Instr::new2(Mnemonic::Mov, Opr::R64(*r), Opr::Imm64(*val))
+        }
+        (Mnemonic::Mov, Oprs::Two(Opr::Mem(m), Opr::Imm64(val))) => {
+            Instr::new2(Mnemonic::Mov, Opr::Mem(m.clone()), Opr::Imm32(*val))
+        }
         _ => instr.clone(),
     }
 }
@@ -272,7 +368,12 @@ fn align_imm_oprs_to_reg(instr: &Instr) -> Instr {
 fn modrm(instr: &Instr) -> IBytes {
     match &instr.oprs {
         Oprs::Two(Register!(r1), Register!(r2)) => {
-            if !instr.mnem.reverse_modrm() {
+            // movq r/m64, xmm is the only sse form with xmm in the reg field as the source
+            let is_movq_store = matches!(
+                (&instr.mnem, &instr.oprs),
+                (Mnemonic::Movq, Oprs::Two(Opr::R64(_), _))
+            );
+            if !instr.mnem.reverse_modrm() || is_movq_store {
                 vec![_modrm(0b11, r1.opcode(), r2.opcode())]
             } else {
                 vec![_modrm(0b11, r2.opcode(), r1.opcode())]
@@ -325,6 +426,7 @@ fn _mem_modrm(r: u8, mem: &MemAddr) -> IBytes {
             let mut bytes = vec![];
             if disp == 0 {
                 bytes.push(_modrm(0b00, 0x04, r));
+                bytes.push(sib(mem));
                 bytes
             } else if disp >= i8::MIN as i32 && disp <= i8::MAX as i32 {
                 bytes.push(_modrm(0b01, 0x04, r));
@@ -338,7 +440,8 @@ fn _mem_modrm(r: u8, mem: &MemAddr) -> IBytes {
                 bytes
             }
         }
-        MemAddrType::AddrRela(_) => vec![0x04, 0x25],
+        // Addresses are relative to the next instruction so they work in shared objects
+        MemAddrType::AddrRela(_) => vec![_modrm(0b00, 0b101, r)],
     }
 }
 
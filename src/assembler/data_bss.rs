@@ -2,16 +2,6 @@ use std::fmt::Display;
 
 use crate::parser::types::VariableType;
 
-fn data_type(dt: &VariableType) -> &'static str {
-    match dt {
-        VariableType::String | VariableType::Char => "db",
-        VariableType::Int | VariableType::UInt => "dd",
-        VariableType::Long | VariableType::ULong | VariableType::Pointer => "dq",
-        VariableType::Array(t, _) => data_type(t.as_ref()),
-        _ => unreachable!(),
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct BssItem {
     pub index: usize,
@@ -36,7 +26,10 @@ pub struct DataItem {
     pub index: usize,
     pub name: String,
     pub data: Vec<u8>,
+    #[allow(dead_code)]
     pub dtype: VariableType,
+    /// Data item whose address is stored in this item
+    pub reloc: Option<String>,
 }
 impl DataItem {
     pub fn new(name: String, index: usize, data: Vec<u8>, dtype: VariableType) -> Self {
@@ -45,6 +38,7 @@ impl DataItem {
             name,
             data,
             dtype,
+            reloc: None,
         }
     }
 
@@ -82,12 +76,10 @@ impl DataItem {
 
 impl Display for DataItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {} {}",
-            self.name,
-            data_type(&self.dtype),
-            self.asmblized_data()
-        )
+        if let Some(target) = &self.reloc {
+            return write!(f, "{} dq {}", self.name, target);
+        }
+        // Values of every type are stored as raw bytes
+        write!(f, "{} db {}", self.name, self.asmblized_data())
     }
 }
@@ -25,6 +25,7 @@ pub enum Opr {
     R32(Reg),
     R16(Reg),
     R8(Reg),
+    Xmm(Reg),
     Mem(MemAddr),
     Imm8(i64),
     Imm32(i64),
@@ -58,7 +59,14 @@ impl Opr {
     }
 
     pub fn is_register(&self) -> bool {
-        matches!(self, Opr::R8(_) | Opr::R16(_) | Opr::R32(_) | Opr::R64(_))
+        matches!(
+            self,
+            Opr::R8(_) | Opr::R16(_) | Opr::R32(_) | Opr::R64(_) | Opr::Xmm(_)
+        )
+    }
+
+    pub fn is_xmm(&self) -> bool {
+        matches!(self, Opr::Xmm(_))
     }
 
     pub fn is_literal(&self) -> bool {
@@ -93,6 +101,7 @@ impl From<Reg> for Opr {
             32 => Self::R32(val),
             16 => Self::R16(val),
             8 => Self::R8(val),
+            128 => Self::Xmm(val),
             _ => unreachable!(),
         }
     }
@@ -100,7 +109,8 @@ impl From<Reg> for Opr {
 
 impl From<usize> for Opr {
     fn from(val: usize) -> Opr {
-        if val as u64 <= u8::MAX as u64 {
+        // 8 bit immediates are sign extended
+        if val as u64 <= i8::MAX as u64 {
             Self::Imm8(val as i64)
         } else if val as u64 <= u32::MAX as u64 {
             Self::Imm32(val as i64)
@@ -111,7 +121,7 @@ impl From<usize> for Opr {
 }
 impl From<i32> for Opr {
     fn from(val: i32) -> Opr {
-        if val <= u8::MAX as i32 && val >= i8::MIN as i32 {
+        if val <= i8::MAX as i32 && val >= i8::MIN as i32 {
             Self::Imm8(val as i64)
         } else {
             Self::Imm32(val as i64)
@@ -127,7 +137,7 @@ impl From<i64> for Opr {
 impl Display for Opr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::R64(r) | Self::R32(r) | Self::R16(r) | Self::R8(r) => r.fmt(f),
+            Self::R64(r) | Self::R32(r) | Self::R16(r) | Self::R8(r) | Self::Xmm(r) => r.fmt(f),
             Self::Mem(m) => m.fmt(f),
             Self::Imm8(val) | Self::Imm32(val) | Self::Imm64(val) => val.fmt(f),
             Self::Rela(refer) => refer.fmt(f),
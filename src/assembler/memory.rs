@@ -190,6 +190,20 @@ impl MemAddr {
         res
     }
 
+    /// Returns memory displaced from the current address
+    pub fn with_offset(&self, offset: i32, size: u8) -> Self {
+        if !Self::validate_size(&size) {
+            panic!("unexpected value for memory size");
+        }
+        let addr_type = match self.addr_type {
+            MemAddrType::Addr(r) => MemAddrType::Disp(r, offset),
+            MemAddrType::Disp(r, disp) => MemAddrType::Disp(r, disp + offset),
+            MemAddrType::Sib(r, disp, r2, scale) => MemAddrType::Sib(r, disp + offset, r2, scale),
+            MemAddrType::AddrRela(_) => unreachable!("relocatable memory can not be displaced"),
+        };
+        Self { addr_type, size }
+    }
+
     pub fn get_register(&self) -> Reg {
         match self.addr_type {
             MemAddrType::Addr(r) => r,
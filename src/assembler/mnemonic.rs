@@ -11,21 +11,32 @@ pub enum Mnemonic {
     Cmovl,
     Cmovge,
     Cmovle,
+    Cmova,
+    Cmovae,
+    Cmovb,
+    Cmovbe,
+    Cmovp,
     Push,
     Pop,
     Add,
     Sub,
     Imul,
     Idiv,
+    Div,
     Mul,
     Or,
     And,
+    Xor,
     Sal,
     Sar,
     Shr,
     Cmp,
     Test,
     Cqo,
+    Cdq,
+    Movsx,
+    Movsxd,
+    Movzx,
     Neg,
     Not,
     Nop,
@@ -34,11 +45,30 @@ pub enum Mnemonic {
     Jz,
     Jnz,
     Jne,
+    Jae,
+    Ja,
+    Jb,
+    Jbe,
+    Jg,
+    Jge,
+    Jl,
+    Jle,
+    Jp,
     Syscall,
     Leave,
     Ret,
     Lable,
     Inc,
+    Movsd,
+    Movq,
+    Addsd,
+    Subsd,
+    Mulsd,
+    Divsd,
+    Ucomisd,
+    Xorpd,
+    Cvtsi2sd,
+    Cvttsd2si,
 }
 
 impl Mnemonic {
@@ -55,7 +85,32 @@ impl Mnemonic {
                 | Self::Cmovge
                 | Self::Cmovl
                 | Self::Cmovle
+                | Self::Cmova
+                | Self::Cmovae
+                | Self::Cmovb
+                | Self::Cmovbe
+                | Self::Cmovp
                 | Self::Imul
+                | Self::Movsx
+                | Self::Movsxd
+                | Self::Movzx
+        ) || self.is_sse()
+    }
+
+    /// Sse instructions that are encoded with a mandatory prefix
+    pub fn is_sse(&self) -> bool {
+        matches!(
+            self,
+            Self::Movsd
+                | Self::Movq
+                | Self::Addsd
+                | Self::Subsd
+                | Self::Mulsd
+                | Self::Divsd
+                | Self::Ucomisd
+                | Self::Xorpd
+                | Self::Cvtsi2sd
+                | Self::Cvttsd2si
         )
     }
 }
@@ -71,21 +126,32 @@ impl Display for Mnemonic {
             Self::Cmovl => write!(f, "cmovl"),
             Self::Cmovge => write!(f, "cmovge"),
             Self::Cmovle => write!(f, "cmovle"),
+            Self::Cmova => write!(f, "cmova"),
+            Self::Cmovae => write!(f, "cmovae"),
+            Self::Cmovb => write!(f, "cmovb"),
+            Self::Cmovbe => write!(f, "cmovbe"),
+            Self::Cmovp => write!(f, "cmovp"),
             Self::Push => write!(f, "push"),
             Self::Pop => write!(f, "pop"),
             Self::Add => write!(f, "add"),
             Self::Sub => write!(f, "sub"),
             Self::Imul => write!(f, "imul"),
             Self::Idiv => write!(f, "idiv"),
+            Self::Div => write!(f, "div"),
             Self::Mul => write!(f, "mul"),
             Self::Or => write!(f, "or"),
             Self::And => write!(f, "and"),
+            Self::Xor => write!(f, "xor"),
             Self::Sal => write!(f, "sal"),
             Self::Sar => write!(f, "sar"),
             Self::Shr => write!(f, "shr"),
             Self::Cmp => write!(f, "cmp"),
             Self::Test => write!(f, "test"),
             Self::Cqo => write!(f, "cqo"),
+            Self::Cdq => write!(f, "cdq"),
+            Self::Movsx => write!(f, "movsx"),
+            Self::Movsxd => write!(f, "movsxd"),
+            Self::Movzx => write!(f, "movzx"),
             Self::Neg => write!(f, "neg"),
             Self::Not => write!(f, "not"),
             Self::Call => write!(f, "call"),
@@ -93,12 +159,31 @@ impl Display for Mnemonic {
             Self::Jz => write!(f, "jz"),
             Self::Jnz => write!(f, "jnz"),
             Self::Jne => write!(f, "jne"),
+            Self::Jae => write!(f, "jae"),
+            Self::Ja => write!(f, "ja"),
+            Self::Jb => write!(f, "jb"),
+            Self::Jbe => write!(f, "jbe"),
+            Self::Jg => write!(f, "jg"),
+            Self::Jge => write!(f, "jge"),
+            Self::Jl => write!(f, "jl"),
+            Self::Jle => write!(f, "jle"),
+            Self::Jp => write!(f, "jp"),
             Self::Syscall => write!(f, "syscall"),
             Self::Leave => write!(f, "leave"),
             Self::Ret => write!(f, "ret"),
             Self::Nop => write!(f, "nop"),
             Self::Lable => write!(f, ""),
             Self::Inc => write!(f, "inc"),
+            Self::Movsd => write!(f, "movsd"),
+            Self::Movq => write!(f, "movq"),
+            Self::Addsd => write!(f, "addsd"),
+            Self::Subsd => write!(f, "subsd"),
+            Self::Mulsd => write!(f, "mulsd"),
+            Self::Divsd => write!(f, "divsd"),
+            Self::Ucomisd => write!(f, "ucomisd"),
+            Self::Xorpd => write!(f, "xorpd"),
+            Self::Cvtsi2sd => write!(f, "cvtsi2sd"),
+            Self::Cvttsd2si => write!(f, "cvttsd2si"),
         }
     }
 }
@@ -117,32 +202,62 @@ impl FromStr for Mnemonic {
             "cmovl" => Ok(Self::Cmovl),
             "cmovge" => Ok(Self::Cmovge),
             "cmovle" => Ok(Self::Cmovle),
+            "cmova" => Ok(Self::Cmova),
+            "cmovae" => Ok(Self::Cmovae),
+            "cmovb" => Ok(Self::Cmovb),
+            "cmovbe" => Ok(Self::Cmovbe),
+            "cmovp" => Ok(Self::Cmovp),
             "push" => Ok(Self::Push),
             "pop" => Ok(Self::Pop),
             "add" => Ok(Self::Add),
             "sub" => Ok(Self::Sub),
             "imul" => Ok(Self::Imul),
             "idiv" => Ok(Self::Idiv),
+            "div" => Ok(Self::Div),
             "mul" => Ok(Self::Mul),
             "or" => Ok(Self::Or),
             "and" => Ok(Self::And),
+            "xor" => Ok(Self::Xor),
             "sal" => Ok(Self::Sal),
             "sar" => Ok(Self::Sar),
             "shr" => Ok(Self::Shr),
             "cmp" => Ok(Self::Cmp),
             "test" => Ok(Self::Test),
             "cqo" => Ok(Self::Cqo),
+            "cdq" => Ok(Self::Cdq),
+            "movsx" => Ok(Self::Movsx),
+            "movsxd" => Ok(Self::Movsxd),
+            "movzx" => Ok(Self::Movzx),
             "neg" => Ok(Self::Neg),
             "not" => Ok(Self::Not),
             "jmp" => Ok(Self::Jmp),
             "jz" => Ok(Self::Jz),
             "jnz" => Ok(Self::Jnz),
             "jne" => Ok(Self::Jne),
+            "jae" => Ok(Self::Jae),
+            "ja" => Ok(Self::Ja),
+            "jb" => Ok(Self::Jb),
+            "jbe" => Ok(Self::Jbe),
+            "jg" => Ok(Self::Jg),
+            "jge" => Ok(Self::Jge),
+            "jl" => Ok(Self::Jl),
+            "jle" => Ok(Self::Jle),
+            "jp" => Ok(Self::Jp),
             "syscall" => Ok(Self::Syscall),
             "call" => Ok(Self::Call),
             "inc" => Ok(Self::Inc),
             "leave" => Ok(Self::Leave),
             "ret" => Ok(Self::Ret),
+            "movsd" => Ok(Self::Movsd),
+            "movq" => Ok(Self::Movq),
+            "addsd" => Ok(Self::Addsd),
+            "subsd" => Ok(Self::Subsd),
+            "mulsd" => Ok(Self::Mulsd),
+            "divsd" => Ok(Self::Divsd),
+            "ucomisd" => Ok(Self::Ucomisd),
+            "xorpd" => Ok(Self::Xorpd),
+            "cvtsi2sd" => Ok(Self::Cvtsi2sd),
+            "cvttsd2si" => Ok(Self::Cvttsd2si),
             _ => Err(format!("Undifiend Mnemonic {clean_s}!")),
         }
     }
@@ -20,6 +20,7 @@ use self::{
     assemble::assemble_instr,
     data_bss::{BssItem, DataItem},
     instructions::{Instr, Opr, Oprs},
+    memory::MemAddr,
     mnemonic::Mnemonic,
 };
 
@@ -89,21 +90,26 @@ impl Codegen {
                 let Some(key) = item.instr.get_rela_key() else {
                     unreachable!();
                 };
-                let rela_offset = item
+                let rela_pos = item
                     .bytes
                     .windows(4)
                     .position(|x| x == [0, 0, 0, 0])
-                    .unwrap()
-                    + bytes_sum;
+                    .unwrap();
+                let rela_offset = rela_pos + bytes_sum;
+                // Memory operands are relative to the end of the instruction
+                let (r_type, pc) = match item.instr.uses_rela_memory() {
+                    true => (0x2, (item.bytes.len() - rela_pos) as i64),
+                    false => (0xb, 0),
+                };
                 match self.symbols_map.get(&key).unwrap() {
                     (_, SymbolType::BssSec) => {
                         let addend = self.bss_buf.iter().find(|x| x.name == key).unwrap().index;
                         self.rela_map.push(RelaItem::new(
                             ".bss",
                             SymbolType::BssSec,
-                            0xb,
+                            r_type,
                             rela_offset as u64,
-                            addend as i64,
+                            addend as i64 - pc,
                         ));
                     }
                     (_, SymbolType::DataSec) => {
@@ -116,9 +122,9 @@ impl Codegen {
                         self.rela_map.push(RelaItem::new(
                             ".data",
                             SymbolType::DataSec,
-                            0xb,
+                            r_type,
                             rela_offset as u64,
-                            addend as i64,
+                            addend as i64 - pc,
                         ));
                     }
                     (_, SymbolType::Ffi) => {
@@ -130,6 +136,15 @@ impl Codegen {
                             -4,
                         ));
                     }
+                    (offset, SymbolType::TextSec) => {
+                        self.rela_map.push(RelaItem::new(
+                            ".text",
+                            SymbolType::TextSec,
+                            r_type,
+                            rela_offset as u64,
+                            *offset as i64 - pc,
+                        ));
+                    }
                     _ => unreachable!("{:?}", item.instr),
                 }
                 bytes_sum += item.bytes.len();
@@ -185,22 +200,25 @@ impl Codegen {
 
     pub fn add_data(&mut self, data: Vec<u8>, dtype: VariableType) -> String {
         let name = format!("data{}", self.data_buf.len());
-        let index = match self.data_buf.values().last() {
-            Some(dt) => dt.index + dt.data.len(),
-            None => 0,
-        };
-        self.symbols_map.insert(
-            name.clone(),
-            (
-                self.data_buf.values().map(|x| x.data.len()).sum(),
-                SymbolType::DataSec,
-            ),
-        );
+        self.add_named_data(&name, data, dtype);
+        name
+    }
+
+    pub fn add_named_data(&mut self, name: &str, data: Vec<u8>, dtype: VariableType) {
+        // data_buf is ordered by name so the last item is not always the latest one
+        let index = self.data_buf.values().map(|x| x.data.len()).sum();
+        self.symbols_map
+            .insert(name.to_string(), (index, SymbolType::DataSec));
         self.data_buf.insert(
-            name.clone(),
-            DataItem::new(name.clone(), index, data, dtype),
+            name.to_string(),
+            DataItem::new(name.to_string(), index, data, dtype),
         );
-        name
+    }
+
+    /// Adds a pointer to another data item, the address is set by the linker
+    pub fn add_data_pointer(&mut self, name: &str, target: &str, dtype: VariableType) {
+        self.add_named_data(name, vec![0; 8], dtype);
+        self.data_buf.get_mut(name).unwrap().reloc = Some(target.to_string());
     }
 
     pub fn add_bss_seg(&mut self, size: usize) -> String {
@@ -221,7 +239,25 @@ impl Codegen {
         bss_tag
     }
 
+    /// Number of instructions and labels generated so far
+    pub fn instructs_len(&self) -> usize {
+        self.instructs.len()
+    }
+
+    /// Drops the instructions generated after the given length
+    pub fn truncate(&mut self, len: usize) {
+        self.instructs.truncate(len);
+    }
+
     pub fn instr2(&mut self, mnemonic: Mnemonic, opr1: impl Into<Opr>, opr2: impl Into<Opr>) {
+        let (opr1, opr2) = (opr1.into(), opr2.into());
+        // Addresses of data and functions are loaded relative to the instruction
+        if let (Mnemonic::Mov, Opr::R64(_), Opr::Rela(key)) = (&mnemonic, &opr1, &opr2) {
+            let mem = MemAddr::new_rela_s(8, key.clone());
+            self.instructs
+                .push(InstrData::new(Instr::new2(Mnemonic::Lea, opr1, mem)));
+            return;
+        }
         self.instructs
             .push(InstrData::new(Instr::new2(mnemonic, opr1, opr2)));
     }
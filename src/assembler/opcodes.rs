@@ -33,6 +33,7 @@ macro_rules! imm {
 
 pub fn opcode(instr: &Instr) -> (u16, ModrmType) {
     match (&instr.mnem, &instr.oprs) {
+        (Mnemonic::Add, Two(rm_8!(), R8(_))) => (0x00, Modrm),
         (Mnemonic::Add, Two(rm_16_64!(), r_16_64!())) => (0x01, Modrm),
         (Mnemonic::Add, Two(rm_16_64!(), Imm32(_))) => (0x81, Ext(0)),
         (Mnemonic::Add, Two(rm_16_64!(), Imm8(_))) => (0x83, Ext(0)),
@@ -40,6 +41,7 @@ pub fn opcode(instr: &Instr) -> (u16, ModrmType) {
         (Mov, Two(R8(_), Mem(_))) => (0x8A, Modrm),
         (Mov, Two(rm_16_64!(), r_16_64!())) => (0x89, Modrm),
         (Mov, Two(r_16_64!(), Mem(_))) => (0x8B, Modrm),
+        (Mov, Two(R64(_), Imm32(_))) => (0xC7, Ext(0)),
         (Mov, Two(r_16_64!(), imm!())) => (0xB8, ModrmType::Add),
         (Mov, Two(Mem(m), imm!())) => {
             if m.size == 1 {
@@ -51,13 +53,16 @@ pub fn opcode(instr: &Instr) -> (u16, ModrmType) {
         (Push, One(Imm8(_))) => (0x6A, ModrmType::None),
         (Push, One(Imm32(_) | Imm64(_))) => (0x68, ModrmType::None),
         (Push, One(R64(_))) => (0x50, ModrmType::Add),
+        (Push, One(Mem(_))) => (0xff, Ext(6)),
         (Pop, One(R64(_))) => (0x58, ModrmType::Add),
+        (Sub, Two(rm_8!(), R8(_))) => (0x28, Modrm),
         (Sub, Two(rm_16_64!(), r_16_64!())) => (0x29, Modrm),
         (Sub, Two(r_16_64!(), Mem(_))) => (0x2B, Modrm),
         (Sub, Two(R64(Reg::RAX), Imm32(_))) => (0x2D, ModrmType::None),
         (Sub, Two(rm_16_64!(), Imm32(_))) => (0x81, Ext(5)),
         (Sub, Two(rm_16_64!(), Imm8(_))) => (0x83, Ext(5)),
-        (Idiv, One(R64(_))) => (0xf7, Ext(7)),
+        (Idiv, One(R64(_) | R32(_))) => (0xf7, Ext(7)),
+        (Div, One(R64(_) | R32(_))) => (0xf7, Ext(6)),
         (Syscall, Oprs::None) => (0x0f05, ModrmType::None),
         (Leave, Oprs::None) => (0xc9, ModrmType::None),
         (Nop, Oprs::None) => (0x90, ModrmType::None),
@@ -65,6 +70,11 @@ pub fn opcode(instr: &Instr) -> (u16, ModrmType) {
         (Imul, Two(r_16_64!(), rm_16_64!())) => (0x0faf, Modrm),
         (Or, Two(rm_16_64!(), r_16_64!())) => (0x09, Modrm),
         (And, Two(rm_16_64!(), r_16_64!())) => (0x21, Modrm),
+        (And, Two(rm_16_64!(), Imm32(_))) => (0x81, Ext(4)),
+        (And, Two(rm_16_64!(), Imm8(_))) => (0x83, Ext(4)),
+        (Xor, Two(rm_16_64!(), r_16_64!())) => (0x31, Modrm),
+        (Xor, Two(rm_16_64!(), Imm32(_))) => (0x81, Ext(6)),
+        (Xor, Two(rm_16_64!(), Imm8(_))) => (0x83, Ext(6)),
         (Sar, Two(rm_16_64!(), R8(Reg::CL))) => (0xd3, Ext(7)),
         (Shr, Two(rm_16_64!(), R8(Reg::CL))) => (0xd3, Ext(5)),
         (Shr, Two(rm_16_64!(), Imm8(_))) => (0xc1, Ext(5)),
@@ -79,21 +89,75 @@ pub fn opcode(instr: &Instr) -> (u16, ModrmType) {
         (Not, One(rm_16_64!())) => (0xf7, Ext(2)),
         (Jne, One(Imm8(_))) => (0x75, ModrmType::None),
         (Jne, One(Imm32(_))) => (0x0f85, ModrmType::None),
+        (Jae, One(Imm8(_))) => (0x73, ModrmType::None),
+        (Jae, One(Imm32(_))) => (0x0f83, ModrmType::None),
+        (Ja, One(Imm8(_))) => (0x77, ModrmType::None),
+        (Ja, One(Imm32(_))) => (0x0f87, ModrmType::None),
+        (Jb, One(Imm8(_))) => (0x72, ModrmType::None),
+        (Jb, One(Imm32(_))) => (0x0f82, ModrmType::None),
+        (Jbe, One(Imm8(_))) => (0x76, ModrmType::None),
+        (Jbe, One(Imm32(_))) => (0x0f86, ModrmType::None),
+        (Jg, One(Imm8(_))) => (0x7f, ModrmType::None),
+        (Jg, One(Imm32(_))) => (0x0f8f, ModrmType::None),
+        (Jge, One(Imm8(_))) => (0x7d, ModrmType::None),
+        (Jge, One(Imm32(_))) => (0x0f8d, ModrmType::None),
+        (Jl, One(Imm8(_))) => (0x7c, ModrmType::None),
+        (Jl, One(Imm32(_))) => (0x0f8c, ModrmType::None),
+        (Jle, One(Imm8(_))) => (0x7e, ModrmType::None),
+        (Jle, One(Imm32(_))) => (0x0f8e, ModrmType::None),
+        (Jp, One(Imm8(_))) => (0x7a, ModrmType::None),
+        (Jp, One(Imm32(_))) => (0x0f8a, ModrmType::None),
         (Cmove, Two(r_16_64!(), rm_16_64!())) => (0x0f44, Modrm),
         (Cmovne, Two(r_16_64!(), rm_16_64!())) => (0x0f45, Modrm),
         (Cmovg, Two(r_16_64!(), rm_16_64!())) => (0x0f4f, Modrm),
         (Cmovge, Two(r_16_64!(), rm_16_64!())) => (0x0f4d, Modrm),
         (Cmovl, Two(r_16_64!(), rm_16_64!())) => (0x0f4c, Modrm),
         (Cmovle, Two(r_16_64!(), rm_16_64!())) => (0x0f4e, Modrm),
+        (Cmova, Two(r_16_64!(), rm_16_64!())) => (0x0f47, Modrm),
+        (Cmovae, Two(r_16_64!(), rm_16_64!())) => (0x0f43, Modrm),
+        (Cmovb, Two(r_16_64!(), rm_16_64!())) => (0x0f42, Modrm),
+        (Cmovbe, Two(r_16_64!(), rm_16_64!())) => (0x0f46, Modrm),
+        (Cmovp, Two(r_16_64!(), rm_16_64!())) => (0x0f4a, Modrm),
         (Call, One(imm!())) => (0xe8, ModrmType::None),
+        (Call, One(rm_16_64!())) => (0xff, Ext(2)),
         (Jmp, One(Imm8(_))) => (0xeb, ModrmType::None),
         (Jmp, One(Imm32(_))) => (0xe9, ModrmType::None),
         (Jz, One(Imm32(_))) => (0x0f84, ModrmType::None),
         (Jz, One(Imm8(_))) => (0x74, ModrmType::None),
+        (Test, Two(rm_8!(), R8(_))) => (0x84, Modrm),
         (Test, Two(rm_16_64!(), r_16_64!())) => (0x85, Modrm),
         (Cqo, Oprs::None) => (0x4899, ModrmType::None),
+        (Cdq, Oprs::None) => (0x99, ModrmType::None),
+        (Movsxd, Two(R64(_), R32(_) | Mem(_))) => (0x63, Modrm),
+        (Movsx, Two(r_16_64!(), Mem(m))) if m.size == 2 => (0x0fbf, Modrm),
+        (Movsx, Two(r_16_64!(), R16(_))) => (0x0fbf, Modrm),
+        (Movsx, Two(r_16_64!(), rm_8!())) => (0x0fbe, Modrm),
+        (Movzx, Two(r_16_64!(), Mem(m))) if m.size == 2 => (0x0fb7, Modrm),
+        (Movzx, Two(r_16_64!(), R16(_))) => (0x0fb7, Modrm),
+        (Movzx, Two(r_16_64!(), rm_8!())) => (0x0fb6, Modrm),
         (Ret, Oprs::None) => (0xc3, ModrmType::None),
         (Inc, One(rm_16_64!())) => (0xff, Ext(0)),
+        (Movsd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f10, Modrm),
+        (Movsd, Two(Mem(_), Xmm(_))) => (0x0f11, Modrm),
+        (Movq, Two(Xmm(_), R64(_) | Mem(_))) => (0x0f6e, Modrm),
+        (Movq, Two(R64(_) | Mem(_), Xmm(_))) => (0x0f7e, Modrm),
+        (Addsd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f58, Modrm),
+        (Subsd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f5c, Modrm),
+        (Mulsd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f59, Modrm),
+        (Divsd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f5e, Modrm),
+        (Ucomisd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f2e, Modrm),
+        (Xorpd, Two(Xmm(_), Xmm(_) | Mem(_))) => (0x0f57, Modrm),
+        (Cvtsi2sd, Two(Xmm(_), R64(_) | R32(_) | Mem(_))) => (0x0f2a, Modrm),
+        (Cvttsd2si, Two(R64(_) | R32(_), Xmm(_) | Mem(_))) => (0x0f2c, Modrm),
         _ => unimplemented!("{instr}"),
     }
 }
+
+/// Mandatory prefix of sse instructions which comes before rex
+pub fn sse_prefix(instr: &Instr) -> Option<u8> {
+    match instr.mnem {
+        Movsd | Addsd | Subsd | Mulsd | Divsd | Cvtsi2sd | Cvttsd2si => Some(0xf2),
+        Movq | Ucomisd | Xorpd => Some(0x66),
+        _ => Option::None,
+    }
+}
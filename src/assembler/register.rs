@@ -49,6 +49,14 @@ pub enum Reg {
     DIL = 0x17,
     R8B = 0x18,
     R9B = 0x19,
+    XMM0 = 0xC0,
+    XMM1 = 0xC1,
+    XMM2 = 0xC2,
+    XMM3 = 0xC3,
+    XMM4 = 0xC4,
+    XMM5 = 0xC5,
+    XMM6 = 0xC6,
+    XMM7 = 0xC7,
 }
 
 #[allow(non_snake_case)]
@@ -57,6 +65,20 @@ impl Reg {
         matches!(self, Self::SPL | Self::BPL | Self::SIL | Self::DIL)
     }
 
+    pub fn is_xmm(&self) -> bool {
+        matches!(
+            self,
+            Self::XMM0
+                | Self::XMM1
+                | Self::XMM2
+                | Self::XMM3
+                | Self::XMM4
+                | Self::XMM5
+                | Self::XMM6
+                | Self::XMM7
+        )
+    }
+
     pub fn is_extended(&self) -> bool {
         matches!(
             self,
@@ -114,6 +136,14 @@ impl Reg {
             | Self::R8B
             | Self::R9B => 8u8,
             Self::AH | Self::CH | Self::DH | Self::BH => 8u8,
+            Self::XMM0
+            | Self::XMM1
+            | Self::XMM2
+            | Self::XMM3
+            | Self::XMM4
+            | Self::XMM5
+            | Self::XMM6
+            | Self::XMM7 => 128u8,
         }
     }
 
@@ -129,6 +159,14 @@ impl Reg {
             Self::RDI | Self::EDI | Self::DI | Self::DIL | Self::BH => 7u8,
             Self::R8 | Self::R8D | Self::R8W | Self::R8B => 0u8,
             Self::R9 | Self::R9D | Self::R9W | Self::R9B => 1u8,
+            Self::XMM0 => 0u8,
+            Self::XMM1 => 1u8,
+            Self::XMM2 => 2u8,
+            Self::XMM3 => 3u8,
+            Self::XMM4 => 4u8,
+            Self::XMM5 => 5u8,
+            Self::XMM6 => 6u8,
+            Self::XMM7 => 7u8,
         }
     }
 
@@ -204,6 +242,15 @@ impl Reg {
                 8 => Self::R9,
                 _ => unreachable!(),
             },
+            // sse registers do not have sized variants
+            Self::XMM0
+            | Self::XMM1
+            | Self::XMM2
+            | Self::XMM3
+            | Self::XMM4
+            | Self::XMM5
+            | Self::XMM6
+            | Self::XMM7 => *self,
         }
     }
 }
@@ -259,6 +306,14 @@ impl FromStr for Reg {
             "dil" => Ok(Self::DIL),
             "r8b" => Ok(Self::R8B),
             "r9b" => Ok(Self::R9B),
+            "xmm0" => Ok(Self::XMM0),
+            "xmm1" => Ok(Self::XMM1),
+            "xmm2" => Ok(Self::XMM2),
+            "xmm3" => Ok(Self::XMM3),
+            "xmm4" => Ok(Self::XMM4),
+            "xmm5" => Ok(Self::XMM5),
+            "xmm6" => Ok(Self::XMM6),
+            "xmm7" => Ok(Self::XMM7),
             _ => Err(format!("Unsupported Register \"{lowercase_s}\"!")),
         }
     }
@@ -311,6 +366,14 @@ impl Display for Reg {
             Self::DIL => write!(f, "dil"),
             Self::R8B => write!(f, "r8b"),
             Self::R9B => write!(f, "r9b"),
+            Self::XMM0 => write!(f, "xmm0"),
+            Self::XMM1 => write!(f, "xmm1"),
+            Self::XMM2 => write!(f, "xmm2"),
+            Self::XMM3 => write!(f, "xmm3"),
+            Self::XMM4 => write!(f, "xmm4"),
+            Self::XMM5 => write!(f, "xmm5"),
+            Self::XMM6 => write!(f, "xmm6"),
+            Self::XMM7 => write!(f, "xmm7"),
         }
     }
 }
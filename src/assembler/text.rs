@@ -11,17 +11,22 @@ pub fn x86_64_nasm_generator(output: &Path, cc: &CompilerContext) -> Result<(),
     file.write_all(b";; This File is Automatically Created Using The Nmet Compiler\n")?;
     file.write_all(b";; Under MIT License Copyright Mahan Farzaneh 2023-2024\n\n")?;
 
+    file.write_all(b"default rel\n")?;
     file.write_all(b"section .text\n")?;
 
     for mod_name in cc.namespace_map.values() {
-        let NSType::Ffi(_, ff) = mod_name else {
-            continue;
-        };
-        let exten = format!("extern {ff}\n");
-        file.write_all(exten.as_bytes())?;
+        match mod_name {
+            NSType::Ffi(_, ff) => file.write_all(format!("extern {ff}\n").as_bytes())?,
+            NSType::Function(f) if f.is_extern => {
+                file.write_all(format!("global {}\n", f.ident).as_bytes())?
+            }
+            _ => (),
+        }
     }
 
-    file.write_all(b"global _start\n")?;
+    if !cc.is_lib() {
+        file.write_all(b"global _start\n")?;
+    }
     file.write_all(cc.codegen.text_section_asm().as_bytes())?;
     // for instruct in &codegen.instruct_buf {
     //     file.write_all(instruct.to_string().as_bytes())?;
@@ -1,6 +1,11 @@
-use crate::compiler::CompilerContext;
+use crate::{compiler::CompilerContext, parser::types::VariableType};
 
-use super::{instructions::Opr, mnemonic::Mnemonic, register::Reg};
+use super::{
+    instructions::Opr,
+    memory::{MemAddr, MemAddrType},
+    mnemonic::Mnemonic,
+    register::Reg,
+};
 
 pub fn mov_unknown_to_register(cc: &mut CompilerContext, r: Reg, opr: Opr) {
     match &opr {
@@ -16,21 +21,159 @@ pub fn mov_unknown_to_register(cc: &mut CompilerContext, r: Reg, opr: Opr) {
                 cc.codegen.instr2(Mnemonic::Mov, r, *r2);
             }
         }
+        Opr::Xmm(x) => {
+            cc.codegen.instr2(Mnemonic::Movq, r.convert(8), *x);
+        }
         _ => {
             cc.codegen.instr2(Mnemonic::Mov, r, opr);
         }
     }
 }
 
+/// Moves a value to an sse register, integer values are converted to float
+/// while float values in general purpose registers are moved as raw bits
+pub fn mov_unknown_to_xmm(cc: &mut CompilerContext, x: Reg, opr: Opr, vtype: &VariableType) {
+    if *vtype == VariableType::Float {
+        match opr {
+            Opr::Mem(m) => {
+                let mut m = m;
+                m.size = 8;
+                cc.codegen.instr2(Mnemonic::Movsd, x, m);
+            }
+            Opr::Xmm(x2) => {
+                if x != x2 {
+                    cc.codegen.instr2(Mnemonic::Movsd, x, x2);
+                }
+            }
+            Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r) => {
+                cc.codegen.instr2(Mnemonic::Movq, x, r.convert(8));
+            }
+            _ => unreachable!("float values can not be an immidiate value"),
+        }
+        return;
+    }
+    if *vtype.underlying() == VariableType::ULong {
+        if opr != Opr::R64(Reg::RAX) {
+            mov_unknown_to_register(cc, Reg::RAX, opr);
+        }
+        mov_ulong_to_xmm(cc, x);
+        return;
+    }
+    match opr {
+        Opr::Mem(m) if m.size == 4 && *vtype.underlying() == VariableType::Int => {
+            cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, m);
+        }
+        Opr::Mem(m) if m.size == 8 => {
+            cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, m);
+        }
+        Opr::Mem(m) => {
+            // Smaller values do not clear the upper bits of the register
+            mov_unknown_to_register(cc, Reg::RAX, MemAddr::into(m));
+            extend_integer(cc, Reg::RAX, vtype, 8);
+            cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, Reg::RAX);
+        }
+        Opr::R64(r) | Opr::R32(r) | Opr::R16(r) | Opr::R8(r) => {
+            if *vtype.underlying() == VariableType::Int {
+                cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, r.convert(4));
+            } else {
+                cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, r.convert(8));
+            }
+        }
+        _ => {
+            cc.codegen.instr2(Mnemonic::Mov, Reg::RAX, opr);
+            cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, Reg::RAX);
+        }
+    }
+}
+
+/// Converts the unsigned value in RAX to a float, values that do not fit a signed conversion
+/// are halved with their lowest bit kept for rounding and doubled back, uses RCX and RDX
+pub fn mov_ulong_to_xmm(cc: &mut CompilerContext, x: Reg) {
+    // RCX is one for values with the sign bit set
+    cc.codegen.instr2(Mnemonic::Mov, Reg::RCX, Reg::RAX);
+    cc.codegen.instr2(Mnemonic::Shr, Reg::RCX, 63);
+    cc.codegen.instr2(Mnemonic::Mov, Reg::RDX, Reg::RAX);
+    cc.codegen.instr2(Mnemonic::And, Reg::RDX, Reg::RCX);
+    cc.codegen.instr2(Mnemonic::Shr, Reg::RAX, Reg::CL);
+    cc.codegen.instr2(Mnemonic::Or, Reg::RAX, Reg::RDX);
+    cc.codegen.instr2(Mnemonic::Cvtsi2sd, x, Reg::RAX);
+    // Doubled by adding one to the exponent
+    cc.codegen.instr2(Mnemonic::Sal, Reg::RCX, 52);
+    cc.codegen.instr2(Mnemonic::Movq, Reg::RAX, x);
+    cc.codegen.instr2(Mnemonic::Add, Reg::RAX, Reg::RCX);
+    cc.codegen.instr2(Mnemonic::Movq, x, Reg::RAX);
+}
+
+/// Extends an integer in a register to the size of an operation based on the sign of its type
+pub fn extend_integer(cc: &mut CompilerContext, r: Reg, vtype: &VariableType, size: u8) {
+    match vtype.underlying() {
+        VariableType::Int if size == 8 => cc.codegen.instr2(Mnemonic::Movsxd, r, r.convert(4)),
+        VariableType::Short => cc
+            .codegen
+            .instr2(Mnemonic::Movsx, r.convert(size), r.convert(2)),
+        VariableType::UShort => cc
+            .codegen
+            .instr2(Mnemonic::Movzx, r.convert(4), r.convert(2)),
+        VariableType::Char => cc
+            .codegen
+            .instr2(Mnemonic::Movzx, r.convert(4), r.convert(1)),
+        _ => (),
+    }
+}
+
+/// Truncates a float value to an integer in a general purpose register
+pub fn mov_float_to_register(cc: &mut CompilerContext, r: Reg, opr: Opr) {
+    mov_unknown_to_xmm(cc, Reg::XMM0, opr, &VariableType::Float);
+    cc.codegen
+        .instr2(Mnemonic::Cvttsd2si, r.convert(8), Reg::XMM0);
+}
+
 pub fn restore_last_temp_value(cc: &mut CompilerContext, to: Reg) {
-    cc.codegen.instr1(Mnemonic::Pop, to);
+    if to.is_xmm() {
+        cc.codegen.instr1(Mnemonic::Pop, Reg::RAX);
+        cc.codegen.instr2(Mnemonic::Movq, to, Reg::RAX);
+    } else {
+        cc.codegen.instr1(Mnemonic::Pop, to);
+    }
 }
 
 pub fn save_temp_value(cc: &mut CompilerContext, opr: Opr) {
-    if opr.is_mem() {
+    if opr.is_mem() || opr.is_xmm() {
         mov_unknown_to_register(cc, Reg::RAX, opr);
         cc.codegen.instr1(Mnemonic::Push, Reg::RAX);
     } else {
         cc.codegen.instr1(Mnemonic::Push, opr);
     }
 }
+
+/// Loads address of relocatable memory to a register so it can be displaced
+pub fn displaceable_mem(cc: &mut CompilerContext, mem: MemAddr, r: Reg) -> MemAddr {
+    if let MemAddrType::AddrRela(tag) = &mem.addr_type {
+        cc.codegen.instr2(Mnemonic::Mov, r, Opr::Rela(tag.clone()));
+        let mut res = MemAddr::new(r);
+        res.size = mem.size;
+        return res;
+    }
+    mem
+}
+
+/// Copies a block of memory through RAX using the largest possible moves
+pub fn copy_memory(cc: &mut CompilerContext, dst: MemAddr, src: MemAddr, size: usize) {
+    let dst = displaceable_mem(cc, dst, Reg::RDI);
+    let src = displaceable_mem(cc, src, Reg::RSI);
+    let mut offset = 0;
+    while offset < size {
+        let chunk = match size - offset {
+            8.. => 8,
+            4..=7 => 4,
+            2..=3 => 2,
+            _ => 1,
+        };
+        let reg = Reg::RAX.convert(chunk);
+        cc.codegen
+            .instr2(Mnemonic::Mov, reg, src.with_offset(offset as i32, chunk));
+        cc.codegen
+            .instr2(Mnemonic::Mov, dst.with_offset(offset as i32, chunk), reg);
+        offset += chunk as usize;
+    }
+}
@@ -27,17 +27,26 @@ use crate::{
         instructions::Opr, memory::MemAddr, mnemonic::Mnemonic::*, register::Reg::*, Codegen,
     },
     mem, memb, memq,
+    parser::types::VariableType,
 };
 
+use super::CompilerContext;
+
 #[derive(Hash, PartialEq, Eq)]
 pub enum Bif {
     Print,
+    PrintSigned,
+    PrintFloat,
+    Panic,
 }
 
 impl Bif {
     pub fn implement(&self, codegen: &mut Codegen) {
         match self {
             Self::Print => Self::print_impl(codegen),
+            Self::PrintSigned => Self::print_signed_impl(codegen),
+            Self::PrintFloat => Self::print_float_impl(codegen),
+            Self::Panic => Self::panic_impl(codegen),
         }
     }
 
@@ -90,4 +99,138 @@ impl Bif {
         codegen.instr0(Leave);
         codegen.instr0(Ret);
     }
+
+    /// Prints the minus sign of a negative value in rdi and then its absolute value
+    /// Depends on print
+    fn print_signed_impl(codegen: &mut Codegen) {
+        codegen.set_lable("print_signed");
+        codegen.instr2(Mov, RAX, RDI);
+        codegen.instr2(Shr, RAX, 63);
+        codegen.instr2(Cmp, RAX, 0);
+        codegen.instr1(Jz, Opr::rel("print"));
+        codegen.instr1(Push, RDI);
+        codegen.instr1(Push, '-' as i32);
+        codegen.instr2(Mov, RSI, RSP);
+        codegen.instr2(Mov, RDX, 1);
+        codegen.instr2(Mov, RDI, 1);
+        codegen.instr2(Mov, RAX, 1);
+        codegen.instr0(Syscall);
+        codegen.instr1(Pop, RDI);
+        codegen.instr1(Pop, RDI);
+        codegen.instr1(Neg, RDI);
+        codegen.instr1(Jmp, Opr::rel("print"));
+    }
+
+    /// Prints the float in xmm0 with up to 6 fractional digits
+    fn print_float_impl(codegen: &mut Codegen) {
+        codegen.set_lable("print_float");
+        codegen.instr1(Push, RBP);
+        codegen.instr2(Mov, RBP, RSP);
+        codegen.instr2(Sub, RSP, 80);
+        // Seperate the sign bit and the absolute value
+        codegen.instr2(Movq, RAX, XMM0);
+        codegen.instr2(Mov, R8, RAX);
+        codegen.instr2(Shr, R8, 63);
+        codegen.instr2(Sal, RAX, 1);
+        codegen.instr2(Shr, RAX, 1);
+        codegen.instr2(Movq, XMM0, RAX);
+        // Integer part
+        codegen.instr2(Cvttsd2si, RAX, XMM0);
+        codegen.instr2(Mov, memq!(RBP, -72), RAX);
+        // Rounded fraction part
+        codegen.instr2(Cvtsi2sd, XMM1, RAX);
+        codegen.instr2(Subsd, XMM0, XMM1);
+        codegen.instr2(Mov, RAX, 1000000);
+        codegen.instr2(Cvtsi2sd, XMM1, RAX);
+        codegen.instr2(Mulsd, XMM0, XMM1);
+        codegen.instr2(Mov, RAX, 0.5f64.to_bits() as i64);
+        codegen.instr2(Movq, XMM1, RAX);
+        codegen.instr2(Addsd, XMM0, XMM1);
+        codegen.instr2(Cvttsd2si, RAX, XMM0);
+        // Carry when fraction is rounded up to a whole number
+        codegen.instr2(Mov, RCX, memq!(RBP, -72));
+        codegen.instr2(Mov, RDX, RCX);
+        codegen.instr1(Inc, RDX);
+        codegen.instr2(Mov, R9, 0);
+        codegen.instr2(Cmp, RAX, 1000000);
+        codegen.instr2(Cmove, RCX, RDX);
+        codegen.instr2(Cmove, RAX, R9);
+        codegen.instr2(Mov, memq!(RBP, -72), RCX);
+        codegen.instr2(Mov, memq!(RBP, -80), RAX);
+        // Fill the buffer from the end
+        codegen.instr2(Mov, RCX, 64);
+        codegen.instr2(Mov, memb!(RBP, -65, RCX), 10);
+        codegen.instr2(Sub, RCX, 1);
+        codegen.instr2(Mov, RBX, 10);
+        codegen.instr2(Mov, RAX, memq!(RBP, -80));
+        codegen.instr2(Mov, R9, 0);
+        codegen.instr2(Mov, RDI, 6);
+        codegen.set_lable("print_float.L1");
+        codegen.instr0(Cqo);
+        codegen.instr1(Idiv, RBX);
+        // Skip trailing zeros of the fraction
+        codegen.instr2(Mov, RSI, RDX);
+        codegen.instr2(Or, RSI, R9);
+        codegen.instr2(Test, RSI, RSI);
+        codegen.instr1(Jz, Opr::rel("print_float.L2"));
+        codegen.instr2(Add, RDX, 48);
+        codegen.instr2(Mov, memb!(RBP, -65, RCX), DL);
+        codegen.instr2(Sub, RCX, 1);
+        codegen.instr2(Add, R9, 1);
+        codegen.set_lable("print_float.L2");
+        codegen.instr2(Sub, RDI, 1);
+        codegen.instr2(Test, RDI, RDI);
+        codegen.instr1(Jne, Opr::rel("print_float.L1"));
+        codegen.instr2(Test, R9, R9);
+        codegen.instr1(Jne, Opr::rel("print_float.L3"));
+        codegen.instr2(Mov, memb!(RBP, -65, RCX), 48);
+        codegen.instr2(Sub, RCX, 1);
+        codegen.set_lable("print_float.L3");
+        codegen.instr2(Mov, memb!(RBP, -65, RCX), 46);
+        codegen.instr2(Sub, RCX, 1);
+        codegen.instr2(Mov, RAX, memq!(RBP, -72));
+        codegen.set_lable("print_float.L4");
+        codegen.instr0(Cqo);
+        codegen.instr1(Idiv, RBX);
+        codegen.instr2(Add, RDX, 48);
+        codegen.instr2(Mov, memb!(RBP, -65, RCX), DL);
+        codegen.instr2(Sub, RCX, 1);
+        codegen.instr2(Test, RAX, RAX);
+        codegen.instr1(Jne, Opr::rel("print_float.L4"));
+        codegen.instr2(Test, R8, R8);
+        codegen.instr1(Jz, Opr::rel("print_float.L5"));
+        codegen.instr2(Mov, memb!(RBP, -65, RCX), 45);
+        codegen.instr2(Sub, RCX, 1);
+        codegen.set_lable("print_float.L5");
+        codegen.instr2(Lea, RSI, mem!(RBP, -64, RCX));
+        codegen.instr2(Mov, RDX, 64);
+        codegen.instr2(Sub, RDX, RCX);
+        codegen.instr2(Mov, RDI, 1);
+        codegen.instr2(Mov, RAX, 1);
+        codegen.instr0(Syscall);
+        codegen.instr0(Leave);
+        codegen.instr0(Ret);
+    }
+
+    /// Prints the message in rdi to stderr and exits with status 1
+    /// The message starts with its 8 byte length
+    fn panic_impl(codegen: &mut Codegen) {
+        codegen.set_lable("runtime_panic");
+        codegen.instr2(Mov, RDX, memq!(RDI));
+        codegen.instr2(Lea, RSI, mem!(RDI, 8));
+        codegen.instr2(Mov, RDI, 2);
+        codegen.instr2(Mov, RAX, 1);
+        codegen.instr0(Syscall);
+        codegen.instr2(Mov, RDI, 1);
+        codegen.instr2(Mov, RAX, 60);
+        codegen.instr0(Syscall);
+    }
+}
+
+/// Adds a message for the runtime panic routine to the data section
+pub fn panic_message(cc: &mut CompilerContext, msg: &str) -> Opr {
+    let mut data = msg.len().to_le_bytes().to_vec();
+    data.extend(msg.as_bytes());
+    cc.bif_set.insert(Bif::Panic);
+    Opr::Rela(cc.codegen.add_data(data, VariableType::String))
 }
@@ -1,27 +1,123 @@
+use crate::assembler::{memory::MemAddr, mnemonic::Mnemonic::*, register::Reg::*};
+use crate::error_handeling::CompilationError;
 use crate::log_cerror;
 use crate::parser::block::Block;
+use crate::parser::stmt::Stmt;
 
 use super::stmts::compile_stmt;
 use super::CompilerContext;
 
+/// Defers registered in a block that is being compiled
+/// * id - id of the block
+/// * defers - deferred stmts in the order they are reached
+/// * is_defer - block is the body of a defer and can not be left early
+#[derive(Debug, Clone)]
+pub struct DeferScope {
+    id: String,
+    defers: Vec<Vec<Stmt>>,
+    is_defer: bool,
+}
+
 /*
  *  keep in mind there could be a problem when a variable wants to access
  *  somthing that added after in code but it could be a feature too :)
  */
 pub fn compile_block(cc: &mut CompilerContext, block: &Block) {
-    for stmt in &block.stmts {
-        compile_stmt(cc, stmt, block).unwrap_or_else(|e| {
-            cc.error();
-            log_cerror!(stmt.loc, "{e}");
-        });
-    }
+    compile_scope(cc, block, false);
 }
 
 pub fn compile_function_block_alrady_scoped(cc: &mut CompilerContext, block: &Block) {
+    compile_scope(cc, block, false);
+}
+
+fn compile_scope(cc: &mut CompilerContext, block: &Block, is_defer: bool) {
+    cc.defer_scopes.push(DeferScope {
+        id: block.id.clone(),
+        defers: Vec::new(),
+        is_defer,
+    });
     for stmt in &block.stmts {
         compile_stmt(cc, stmt, block).unwrap_or_else(|e| {
             cc.error();
             log_cerror!(stmt.loc, "{e}");
         });
     }
+    let scope = cc.defer_scopes.pop().unwrap();
+    compile_scope_defers(cc, &scope);
+}
+
+/// Registers deferred stmts in the innermost block
+pub fn insert_defer(cc: &mut CompilerContext, stmts: &[Stmt]) {
+    cc.defer_scopes
+        .last_mut()
+        .unwrap()
+        .defers
+        .push(stmts.to_vec());
+}
+
+/// Runs the defers of every block inside the loop before break or continue
+pub fn compile_loop_defers(
+    cc: &mut CompilerContext,
+    loop_id: &str,
+) -> Result<(), CompilationError> {
+    let count = cc
+        .defer_scopes
+        .iter()
+        .rev()
+        .take_while(|s| s.id.starts_with(loop_id))
+        .count();
+    compile_exit_defers(cc, count)
+}
+
+/// Runs the defers of every block in the function before returning,
+/// the returned value is kept while they run
+pub fn compile_return_defers(cc: &mut CompilerContext) -> Result<(), CompilationError> {
+    if cc.defer_scopes.iter().all(|s| s.defers.is_empty()) {
+        return compile_exit_defers(cc, cc.defer_scopes.len());
+    }
+    // Variables of other branches may be below the stack pointer,
+    // so it is moved below all of them before the registers are saved
+    let mem_offset = cc.mem_offset;
+    cc.mem_offset += 24;
+    cc.codegen.instr2(Mov, RSP, RBP);
+    cc.codegen.instr2(Sub, RSP, cc.mem_offset);
+    let mem = MemAddr::new_disp_s(8, RBP, -(cc.mem_offset as i32));
+    cc.codegen.instr2(Mov, mem.clone(), RAX);
+    cc.codegen.instr2(Mov, mem.with_offset(8, 8), RDX);
+    cc.codegen.instr2(Movsd, mem.with_offset(16, 8), XMM0);
+    compile_exit_defers(cc, cc.defer_scopes.len())?;
+    cc.codegen.instr2(Mov, RAX, mem.clone());
+    cc.codegen.instr2(Mov, RDX, mem.with_offset(8, 8));
+    cc.codegen.instr2(Movsd, XMM0, mem.with_offset(16, 8));
+    cc.mem_offset = mem_offset;
+    Ok(())
+}
+
+/// Runs the defers of the innermost blocks that are left
+/// Code after the exit does not see the variables declared in the defers
+fn compile_exit_defers(cc: &mut CompilerContext, count: usize) -> Result<(), CompilationError> {
+    let start = cc.defer_scopes.len() - count;
+    let scopes = cc.defer_scopes[start..].to_vec();
+    if scopes.iter().any(|s| s.is_defer) {
+        return Err(CompilationError::DeferExit);
+    }
+    let mem_offset = cc.mem_offset;
+    for scope in scopes.iter().rev() {
+        compile_scope_defers(cc, scope);
+    }
+    cc.mem_offset = mem_offset;
+    Ok(())
+}
+
+/// Defers run in reverse order, each copy is compiled in its own block
+/// so labels stay unique when a defer runs at more than one exit
+fn compile_scope_defers(cc: &mut CompilerContext, scope: &DeferScope) {
+    for stmts in scope.defers.iter().rev() {
+        let id = format!("{}.D{}", scope.id, cc.defer_count);
+        cc.defer_count += 1;
+        let mut block = Block::new_unscoped(scope.id.clone());
+        block.stmts = stmts.clone();
+        block.rename_parent(&scope.id, &id);
+        compile_scope(cc, &block, true);
+    }
 }
@@ -0,0 +1,211 @@
+/**********************************************************************************************
+*
+*   compiler/conditions: short circuit evaluation and branching on conditions
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use crate::{
+    assembler::{
+        instructions::Opr,
+        mnemonic::Mnemonic::{self, *},
+        register::Reg::*,
+        utils::{extend_integer, mov_unknown_to_register, mov_unknown_to_xmm},
+    },
+    error_handeling::{CompilationError, Loc},
+    optim::ExprOpr,
+    parser::{
+        block::Block,
+        constants::{eval_const, ConstValue},
+        expr::{Expr, ExprType, IfExpr, Op},
+        types::VariableType,
+    },
+};
+
+use super::{
+    expr::{compile_compare_flags, compile_expr, CompareFlags},
+    CompilerContext,
+};
+
+/// Compiles a logical and/or to a bool in RAX
+/// The right side is only evaluated if the left side does not decide the result
+pub fn compile_logical_expr(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+) -> Result<ExprOpr, CompilationError> {
+    if let Ok(ConstValue::Num(v, VariableType::Bool)) = eval_const(expr) {
+        return Ok(ExprOpr::new(v, VariableType::Bool));
+    }
+    let loc = &expr.loc;
+    let false_label = block.name_with_prefix(&format!("LF{}_{}", loc.line, loc.col));
+    let end_label = block.name_with_prefix(&format!("LE{}_{}", loc.line, loc.col));
+    compile_condition_jump(cc, block, expr, false, &false_label)?;
+    cc.codegen.instr2(Mov, RAX, 1);
+    cc.codegen.instr1(Jmp, Opr::Loc(end_label.clone()));
+    cc.codegen.set_lable(false_label);
+    cc.codegen.instr2(Mov, RAX, 0);
+    cc.codegen.set_lable(end_label);
+    Ok(ExprOpr::new(RAX, VariableType::Bool))
+}
+
+/// Compiles the value of the branch chosen by the condition to RAX or XMM0
+/// Types of the branches are unified like the two sides of an assignment
+pub fn compile_if_expr(
+    cc: &mut CompilerContext,
+    block: &Block,
+    ie: &IfExpr,
+    loc: &Loc,
+) -> Result<ExprOpr, CompilationError> {
+    let else_label = block.name_with_prefix(&format!("IE{}_{}", loc.line, loc.col));
+    let then_label = block.name_with_prefix(&format!("IT{}_{}", loc.line, loc.col));
+    let end_label = block.name_with_prefix(&format!("IX{}_{}", loc.line, loc.col));
+    compile_condition_jump(cc, block, &ie.condition, false, &else_label)?;
+    // Each branch is compiled once and moved to the common type after both are known
+    let then_eo = compile_expr(cc, block, &ie.then)?;
+    cc.codegen.instr1(Jmp, Opr::Loc(then_label.clone()));
+    cc.codegen.set_lable(else_label);
+    let else_eo = compile_expr(cc, block, &ie.otherwise)?;
+    let vtype = then_eo.vtype.cast(&else_eo.vtype)?;
+    if vtype.is_aggregate() || matches!(vtype, VariableType::Array(..)) {
+        return Err(CompilationError::Err(format!(
+            "If expressions can not choose between values of type ({vtype})"
+        )));
+    }
+    move_branch_value(cc, else_eo, &vtype);
+    cc.codegen.instr1(Jmp, Opr::Loc(end_label.clone()));
+    cc.codegen.set_lable(then_label);
+    move_branch_value(cc, then_eo, &vtype);
+    cc.codegen.set_lable(end_label);
+    match vtype {
+        VariableType::Float => Ok(ExprOpr::new(XMM0, vtype)),
+        _ => Ok(ExprOpr::new(RAX, vtype)),
+    }
+}
+
+/// Moves the value of a branch to the register of the if expression
+fn move_branch_value(cc: &mut CompilerContext, eo: ExprOpr, vtype: &VariableType) {
+    if *vtype == VariableType::Float {
+        mov_unknown_to_xmm(cc, XMM0, eo.value, &eo.vtype);
+        return;
+    }
+    mov_unknown_to_register(cc, RAX, eo.value);
+    // Narrow values are extended when the other branch is wider
+    extend_integer(cc, RAX, &eo.vtype, vtype.size().max(4) as u8);
+}
+
+/// Jumps to target if the condition evaluates to `when` and falls through otherwise
+pub fn compile_condition_jump(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+    when: bool,
+    target: &str,
+) -> Result<(), CompilationError> {
+    match &expr.etype {
+        ExprType::Binary(b) if matches!(b.op, Op::LogicalAnd | Op::LogicalOr) => {
+            // false && x is false and true || x is true without evaluating x
+            if (b.op == Op::LogicalAnd) != when {
+                compile_condition_jump(cc, block, &b.left, when, target)?;
+                return compile_condition_jump(cc, block, &b.right, when, target);
+            }
+            let loc = &expr.loc;
+            let skip = block.name_with_prefix(&format!("SC{}_{}", loc.line, loc.col));
+            compile_condition_jump(cc, block, &b.left, !when, &skip)?;
+            compile_condition_jump(cc, block, &b.right, when, target)?;
+            cc.codegen.set_lable(skip);
+            Ok(())
+        }
+        ExprType::Unary(u) if u.op == Op::Not => {
+            compile_condition_jump(cc, block, &u.right, !when, target)
+        }
+        ExprType::Compare(c) => match compile_compare_flags(cc, block, c)? {
+            CompareFlags::Folded(eo) => compile_const_jump(cc, &eo, when, target),
+            CompareFlags::Flags(cmov) => {
+                cc.codegen
+                    .instr1(jump_mnemonic(cmov, when), Opr::Loc(target.to_owned()));
+                Ok(())
+            }
+            // NaN operands set the parity flag and are never equal
+            CompareFlags::FloatEq(eq) if eq == when => {
+                let loc = &expr.loc;
+                let skip = block.name_with_prefix(&format!("FE{}_{}", loc.line, loc.col));
+                cc.codegen.instr1(Jp, Opr::Loc(skip.clone()));
+                cc.codegen.instr1(Jz, Opr::Loc(target.to_owned()));
+                cc.codegen.set_lable(skip);
+                Ok(())
+            }
+            CompareFlags::FloatEq(_) => {
+                cc.codegen.instr1(Jne, Opr::Loc(target.to_owned()));
+                cc.codegen.instr1(Jp, Opr::Loc(target.to_owned()));
+                Ok(())
+            }
+        },
+        _ => {
+            let eo = compile_expr(cc, block, expr)?;
+            VariableType::Bool.cast(&eo.vtype)?;
+            if eo.value.is_literal() {
+                return compile_const_jump(cc, &eo, when, target);
+            }
+            // Only the lowest byte of a bool is set
+            let reg = match eo.vtype {
+                VariableType::Bool => AL,
+                _ => RAX,
+            };
+            mov_unknown_to_register(cc, RAX, eo.value);
+            cc.codegen.instr2(Test, reg, reg);
+            let mnem = if when { Jne } else { Jz };
+            cc.codegen.instr1(mnem, Opr::Loc(target.to_owned()));
+            Ok(())
+        }
+    }
+}
+
+/// Conditions known at compile time either always jump or never do
+fn compile_const_jump(
+    cc: &mut CompilerContext,
+    eo: &ExprOpr,
+    when: bool,
+    target: &str,
+) -> Result<(), CompilationError> {
+    VariableType::Bool.cast(&eo.vtype)?;
+    if (eo.value.get_literal_value() != 0) == when {
+        cc.codegen.instr1(Jmp, Opr::Loc(target.to_owned()));
+    }
+    Ok(())
+}
+
+/// Conditional jump that is taken when the flags match the cmov, or do not match it
+/// Float compares only produce above and above or equal, so negated jumps are taken for NaN too
+fn jump_mnemonic(cmov: Mnemonic, when: bool) -> Mnemonic {
+    match (cmov, when) {
+        (Cmove, true) | (Cmovne, false) => Jz,
+        (Cmovne, true) | (Cmove, false) => Jne,
+        (Cmovg, true) | (Cmovle, false) => Jg,
+        (Cmovle, true) | (Cmovg, false) => Jle,
+        (Cmovl, true) | (Cmovge, false) => Jl,
+        (Cmovge, true) | (Cmovl, false) => Jge,
+        (Cmova, true) | (Cmovbe, false) => Ja,
+        (Cmovbe, true) | (Cmova, false) => Jbe,
+        (Cmovb, true) | (Cmovae, false) => Jb,
+        (Cmovae, true) | (Cmovb, false) => Jae,
+        _ => unreachable!("{cmov} is not a compare"),
+    }
+}
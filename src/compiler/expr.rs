@@ -25,25 +25,38 @@
 use crate::{
     assembler::{
         instructions::Opr,
-        memory::MemAddr,
-        mnemonic::Mnemonic::*,
-        register::Reg::{self, *},
-        utils::{mov_unknown_to_register, restore_last_temp_value, save_temp_value},
+        memory::{MemAddr, MemAddrType},
+        mnemonic::Mnemonic::{self, *},
+        register::{Reg, Reg::*},
+        utils::{
+            copy_memory, displaceable_mem, extend_integer, mov_float_to_register,
+            mov_unknown_to_register, mov_unknown_to_xmm, restore_last_temp_value, save_temp_value,
+        },
     },
-    error_handeling::CompilationError,
-    mem, memq,
-    optim::{fold_binary_expr, fold_compare_expr, fold_unary_expr, ExprOpr},
+    error_handeling::{CompilationError, Loc},
+    memq,
+    optim::{fold_binary_expr, fold_cast, fold_compare_expr, fold_unary_expr, ExprOpr},
     parser::{
         block::Block,
         expr::{
-            ArrayIndex, BinaryExpr, CompareExpr, CompareOp, Expr, ExprType, FunctionCall, Op,
-            UnaryExpr,
+            ArrayIndex, BinaryExpr, CompareExpr, CompareOp, EnumVariantExpr, Expr, ExprType,
+            FunctionCall, Op, UnaryExpr,
         },
-        types::VariableType,
+        function::{method_ident, FunctionDecl},
+        types::{EnumType, FunctionType, VariableType},
     },
 };
 
-use super::{function_args_register, CompilerContext, NSType};
+use super::{
+    bif::panic_message,
+    conditions::{compile_if_expr, compile_logical_expr},
+    function_args_register, function_float_args_register,
+    generics::instantiate_function,
+    resolve_type,
+    results::{compile_try, compile_unwrap},
+    tuples::returns_in_memory,
+    CompilerContext, NSType,
+};
 
 /// This function is part of the Nmet compiler and programming language.
 /// It takes expression (Expr) and a compiler context (CompilerContext)
@@ -55,13 +68,54 @@ pub fn compile_expr(
 ) -> Result<ExprOpr, CompilationError> {
     match &expr.etype {
         ExprType::Compare(c) => compile_compare_expr(cc, block, c),
+        ExprType::Binary(b) if matches!(b.op, Op::LogicalAnd | Op::LogicalOr) => {
+            compile_logical_expr(cc, block, expr)
+        }
         ExprType::Binary(b) => compile_binary_expr(cc, block, b),
-        ExprType::Access(ident, ac) => compile_access(cc, block, ident, ac),
+        ExprType::Access(base, item) => compile_access(cc, block, base, item),
         ExprType::Unary(u) => compile_unaray_expr(cc, block, u),
-        ExprType::FunctionCall(fc) => compile_function_call(cc, block, fc),
+        ExprType::FunctionCall(fc)
+            if fc.ident == "len"
+                && fc.receiver.is_none()
+                && !cc.namespace_map.contains_key("len") =>
+        {
+            compile_len(cc, block, fc)
+        }
+        ExprType::FunctionCall(fc)
+            if fc.ident == "unwrap"
+                && fc.receiver.is_none()
+                && !cc.namespace_map.contains_key("unwrap") =>
+        {
+            compile_unwrap(cc, block, fc, &expr.loc)
+        }
+        ExprType::FunctionCall(fc) => compile_function_call(cc, block, fc, &expr.loc),
+        ExprType::Try(e) => compile_try(cc, block, e, &expr.loc),
+        ExprType::Tuple(_) => Err(CompilationError::Err(
+            "Tuple values can only be stored in variables, assigned or returned".to_string(),
+        )),
+        ExprType::ArrayLit(_) | ExprType::ArrayRepeat(..) | ExprType::StructLit(_) => {
+            Err(CompilationError::Err(
+                "Array and struct literals can only be stored in variables or assigned".to_string(),
+            ))
+        }
+        ExprType::IndirectCall(callee, args) => compile_indirect_call(cc, block, callee, args),
         ExprType::Ptr(e) => compile_ptr(cc, block, e),
         ExprType::DeRef(e) => compile_deref(cc, block, e),
-        ExprType::ArrayIndex(ai) => compile_array_index(cc, block, ai),
+        ExprType::ArrayIndex(ai) => compile_array_index(cc, block, ai, &expr.loc),
+        ExprType::EnumVariant(ev) => compile_enum_variant(cc, ev),
+        ExprType::Cast(e, vtype) => compile_cast(cc, block, e, vtype),
+        ExprType::If(ie) => compile_if_expr(cc, block, ie, &expr.loc),
+        ExprType::SizeOf(vtype) => Ok(ExprOpr::new(
+            resolve_type(cc, vtype)?.size() as i64,
+            VariableType::Long,
+        )),
+        ExprType::AlignOf(vtype) => Ok(ExprOpr::new(
+            resolve_type(cc, vtype)?.align() as i64,
+            VariableType::Long,
+        )),
+        ExprType::Variable(v) if cc.variables_map.get(v, block).is_err() => {
+            compile_function_value(cc, v)
+        }
         ExprType::Variable(v) => {
             let v_map = cc.variables_map.get(v, block)?;
             let mem_acss = v_map.mem();
@@ -69,14 +123,27 @@ pub fn compile_expr(
         }
         ExprType::Bool(b) => Ok(ExprOpr::new(*b as i32, VariableType::Bool)),
         ExprType::Char(x) => Ok(ExprOpr::new(*x as i32, VariableType::Char)),
-        ExprType::Int(x) => Ok(ExprOpr::new(*x, VariableType::Int)),
+        // Literals are never negative, a negative value is a literal above i64::MAX
+        ExprType::Int(x) if *x < 0 => Ok(ExprOpr::new(*x, VariableType::ULong)),
+        ExprType::Int(x) => match i32::try_from(*x) {
+            Ok(x) => Ok(ExprOpr::new(x, VariableType::Int)),
+            Err(_) => Ok(ExprOpr::new(*x, VariableType::Long)),
+        },
         ExprType::String(str) => {
             let id = cc
                 .codegen
                 .add_data(str.as_bytes().to_vec(), VariableType::String);
             Ok(ExprOpr::new(Opr::Rela(id.to_owned()), VariableType::String))
         }
-        ExprType::Float(_) => todo!(),
+        ExprType::Float(f) => {
+            let id = cc
+                .codegen
+                .add_data(f.to_le_bytes().to_vec(), VariableType::Float);
+            Ok(ExprOpr::new(
+                MemAddr::new_rela_s(8, id),
+                VariableType::Float,
+            ))
+        }
     }
 }
 
@@ -85,6 +152,43 @@ pub fn compile_compare_expr(
     block: &Block,
     cexpr: &CompareExpr,
 ) -> Result<ExprOpr, CompilationError> {
+    let flags = compile_compare_flags(cc, block, cexpr)?;
+    // set the result based on flag register
+    match flags {
+        CompareFlags::Folded(eo) => return Ok(eo),
+        CompareFlags::Flags(cmov) => {
+            cc.codegen.instr2(Mov, RCX, 0);
+            cc.codegen.instr2(Mov, RDX, 1);
+            cc.codegen.instr2(cmov, RCX, RDX);
+        }
+        CompareFlags::FloatEq(eq) => {
+            cc.codegen.instr2(Mov, RCX, 0);
+            cc.codegen.instr2(Mov, RDX, 1);
+            cc.codegen.instr2(if eq { Cmove } else { Cmovne }, RCX, RDX);
+            // NaN operands are never equal
+            cc.codegen.instr2(Mov, RDX, if eq { 0 } else { 1 });
+            cc.codegen.instr2(Cmovp, RCX, RDX);
+        }
+    }
+    Ok(ExprOpr::new(RCX, VariableType::Bool))
+}
+
+/// Result of a compare
+/// * Folded - both sides were literals
+/// * Flags - compare is done in the flag register, holds the cmov that matches it
+/// * FloatEq - float `==` (true) or `!=` (false), parity flag is set for NaN operands
+pub enum CompareFlags {
+    Folded(ExprOpr),
+    Flags(Mnemonic),
+    FloatEq(bool),
+}
+
+/// Compares two values without storing the result
+pub fn compile_compare_flags(
+    cc: &mut CompilerContext,
+    block: &Block,
+    cexpr: &CompareExpr,
+) -> Result<CompareFlags, CompilationError> {
     // Compile the left Exprssion
     let left = compile_expr(cc, block, cexpr.left.as_ref())?;
     // Store in memory if register
@@ -93,36 +197,135 @@ pub fn compile_compare_expr(
     }
     // Compile the right Exprssion
     let right = compile_expr(cc, block, cexpr.right.as_ref())?;
+    left.vtype.check_distinct(&right.vtype)?;
 
     // Check for possiblity of optimization
     // NOTE: If valuse where literal noting has been added to the codegen
     if left.value.is_literal() && right.value.is_literal() {
-        return fold_compare_expr(&left, &right, &cexpr.op);
+        return Ok(CompareFlags::Folded(fold_compare_expr(
+            &left, &right, &cexpr.op,
+        )?));
+    }
+    if left.vtype == VariableType::Float || right.vtype == VariableType::Float {
+        return compile_float_compare_flags(cc, &left, &right, &cexpr.op);
+    }
+
+    let (size, unsigned) = operation_width(&left.vtype, &right.vtype);
+    load_integer_operands(cc, &left, &right, size);
+    cc.codegen.instr2(Cmp, RAX.convert(size), RBX.convert(size));
+    let mnem = match (&cexpr.op, unsigned) {
+        (CompareOp::Eq, _) => Cmove,
+        (CompareOp::NotEq, _) => Cmovne,
+        (CompareOp::Bigger, false) => Cmovg,
+        (CompareOp::Smaller, false) => Cmovl,
+        (CompareOp::BiggerEq, false) => Cmovge,
+        (CompareOp::SmallerEq, false) => Cmovle,
+        (CompareOp::Bigger, true) => Cmova,
+        (CompareOp::Smaller, true) => Cmovb,
+        (CompareOp::BiggerEq, true) => Cmovae,
+        (CompareOp::SmallerEq, true) => Cmovbe,
+    };
+    Ok(CompareFlags::Flags(mnem))
+}
+
+/// Size in bytes and signedness of an integer operation
+/// 32-bit values are operated on in 32-bit registers
+fn operation_width(left: &VariableType, right: &VariableType) -> (u8, bool) {
+    match left.promote(right) {
+        Ok(t) if t.is_integer() && t.size() < 8 => (4, t.is_unsigned()),
+        Ok(t) => (8, t.is_unsigned()),
+        Err(_) => (8, false),
     }
+}
 
-    // Move Result to RBX Register
+/// Loads left and right side of an integer expression to RAX and RBX,
+/// values narrower than the operation are extended based on their sign
+fn load_integer_operands(cc: &mut CompilerContext, left: &ExprOpr, right: &ExprOpr, size: u8) {
+    // Move Result of right to RBX Register
     mov_unknown_to_register(cc, RBX, right.value.clone());
-    // Retrive the first instr values to RAX
+    // Retrive the left expr result to RAX
     if left.is_temp() {
         restore_last_temp_value(cc, RAX);
     } else {
         mov_unknown_to_register(cc, RAX, left.value.clone());
     }
-    // Result of Compare instruction
-    cc.codegen.instr2(Mov, RCX, 0);
-    cc.codegen.instr2(Mov, RDX, 1);
-    cc.codegen.instr2(Cmp, RAX, RBX);
-    // set the result based on flag register
-    let mnem = match cexpr.op {
-        CompareOp::Eq => Cmove,
-        CompareOp::NotEq => Cmovne,
-        CompareOp::Bigger => Cmovg,
-        CompareOp::Smaller => Cmovl,
-        CompareOp::BiggerEq => Cmovge,
-        CompareOp::SmallerEq => Cmovle,
+    for (reg, vtype) in [(RAX, &left.vtype), (RBX, &right.vtype)] {
+        extend_integer(cc, reg, vtype, size);
+    }
+}
+
+/// Divides RAX by RBX in the given size, quotient is stored in RAX and remainder in RDX
+pub fn compile_division(cc: &mut CompilerContext, size: u8, unsigned: bool) {
+    if unsigned {
+        cc.codegen.instr2(Mov, RDX, 0);
+        cc.codegen.instr1(Div, RBX.convert(size));
+    } else if size == 8 {
+        cc.codegen.instr0(Cqo);
+        cc.codegen.instr1(Idiv, RBX);
+    } else {
+        cc.codegen.instr0(Cdq);
+        cc.codegen.instr1(Idiv, EBX);
+    }
+}
+
+/// Loads left and right side of a float expression to XMM0 and XMM1
+fn load_float_operands(cc: &mut CompilerContext, left: &ExprOpr, right: &ExprOpr) {
+    mov_unknown_to_xmm(cc, XMM1, right.value.clone(), &right.vtype);
+    if left.is_temp() {
+        restore_last_temp_value(cc, RAX);
+        mov_unknown_to_xmm(cc, XMM0, RAX.into(), &left.vtype);
+    } else {
+        mov_unknown_to_xmm(cc, XMM0, left.value.clone(), &left.vtype);
+    }
+}
+
+fn compile_float_compare_flags(
+    cc: &mut CompilerContext,
+    left: &ExprOpr,
+    right: &ExprOpr,
+    op: &CompareOp,
+) -> Result<CompareFlags, CompilationError> {
+    left.vtype.cast(&right.vtype)?;
+    load_float_operands(cc, left, right);
+    // ucomisd sets the flags like an unsigned compare, unordered (NaN) operands
+    // set ZF, PF and CF so only above and above or equal are false for them.
+    // smaller compares are done with swapped operands
+    match op {
+        CompareOp::Smaller | CompareOp::SmallerEq => cc.codegen.instr2(Ucomisd, XMM1, XMM0),
+        _ => cc.codegen.instr2(Ucomisd, XMM0, XMM1),
+    }
+    let mnem = match op {
+        CompareOp::Eq => return Ok(CompareFlags::FloatEq(true)),
+        CompareOp::NotEq => return Ok(CompareFlags::FloatEq(false)),
+        CompareOp::Bigger | CompareOp::Smaller => Cmova,
+        CompareOp::BiggerEq | CompareOp::SmallerEq => Cmovae,
     };
-    cc.codegen.instr2(mnem, RCX, RDX);
-    Ok(ExprOpr::new(RCX, VariableType::Bool))
+    Ok(CompareFlags::Flags(mnem))
+}
+
+fn compile_float_binary_expr(
+    cc: &mut CompilerContext,
+    left: &ExprOpr,
+    right: &ExprOpr,
+    op: &Op,
+) -> Result<ExprOpr, CompilationError> {
+    let res_type = left.vtype.cast(&right.vtype)?;
+    let mnem = match op {
+        Op::Plus => Addsd,
+        Op::Sub => Subsd,
+        Op::Multi => Mulsd,
+        Op::Devide => Divsd,
+        _ => {
+            return Err(CompilationError::InValidBinaryOperation(
+                op.to_owned(),
+                left.vtype.to_string(),
+                right.vtype.to_string(),
+            ));
+        }
+    };
+    load_float_operands(cc, left, right);
+    cc.codegen.instr2(mnem, XMM0, XMM1);
+    Ok(ExprOpr::new(XMM0, res_type))
 }
 
 fn compile_binary_expr(
@@ -144,58 +347,85 @@ fn compile_binary_expr(
     if left.value.is_literal() && right.value.is_literal() {
         return fold_binary_expr(&left, &right, &bexpr.op);
     }
-
-    // Move Result of right to RBX Register
-    mov_unknown_to_register(cc, RBX, right.value.clone());
-    // Retrive the left expr result to RAX
-    if left.is_temp() {
-        restore_last_temp_value(cc, RAX);
-    } else {
-        mov_unknown_to_register(cc, RAX, left.value.clone());
+    if left.vtype == VariableType::Float || right.vtype == VariableType::Float {
+        return compile_float_binary_expr(cc, &left, &right, &bexpr.op);
     }
+
+    // Typed pointers are moved by integer offsets without being casted to them
+    let res_type = match (&bexpr.op, &left.vtype, &right.vtype) {
+        (Op::Plus | Op::Sub, VariableType::TypedPointer(_), r) if r.is_integer() => {
+            left.vtype.clone()
+        }
+        (Op::Plus, l, VariableType::TypedPointer(_)) if l.is_integer() => right.vtype.clone(),
+        _ => left.vtype.promote(&right.vtype)?,
+    };
+    let (size, unsigned) = operation_width(&left.vtype, &right.vtype);
+    load_integer_operands(cc, &left, &right, size);
+    let (a, b) = (RAX.convert(size), RBX.convert(size));
     match bexpr.op {
         Op::Plus => {
-            cc.codegen.instr2(Add, RAX, RBX);
+            // Offsets of typed pointers are counted in items
+            match (&left.vtype, &right.vtype) {
+                (VariableType::TypedPointer(t), r) if r.is_integer() => {
+                    scale_pointer_offset(cc, RBX, t)?;
+                }
+                (l, VariableType::TypedPointer(t)) if l.is_integer() => {
+                    scale_pointer_offset(cc, RAX, t)?;
+                }
+                _ => (),
+            }
+            cc.codegen.instr2(Add, a, b);
         }
         Op::Sub => {
-            cc.codegen.instr2(Sub, RAX, RBX);
+            match (&left.vtype, &right.vtype) {
+                (VariableType::TypedPointer(t), r) if r.is_integer() => {
+                    scale_pointer_offset(cc, RBX, t)?;
+                }
+                (VariableType::TypedPointer(t), VariableType::TypedPointer(_)) => {
+                    // Distance between two pointers in items
+                    left.vtype.cast(&right.vtype)?;
+                    let stride = resolve_type(cc, t)?.stride();
+                    cc.codegen.instr2(Sub, RAX, RBX);
+                    cc.codegen.instr0(Cqo);
+                    cc.codegen.instr2(Mov, RBX, stride);
+                    cc.codegen.instr1(Idiv, RBX);
+                    return Ok(ExprOpr::new(RAX, VariableType::Long));
+                }
+                _ => (),
+            }
+            cc.codegen.instr2(Sub, a, b);
         }
         Op::Multi => {
-            cc.codegen.instr2(Imul, RAX, RBX);
+            cc.codegen.instr2(Imul, a, b);
         }
         Op::Devide => {
             // Result of this operation is rax
-            cc.codegen.instr0(Cqo);
-            cc.codegen.instr1(Idiv, RBX);
+            compile_division(cc, size, unsigned);
         }
         Op::Mod => {
-            cc.codegen.instr0(Cqo);
-            cc.codegen.instr1(Idiv, RBX);
+            compile_division(cc, size, unsigned);
             cc.codegen.instr2(Mov, RAX, RDX);
         }
         Op::Or => {
-            cc.codegen.instr2(Or, RAX, RBX);
+            cc.codegen.instr2(Or, a, b);
         }
         Op::And => {
-            cc.codegen.instr2(And, RAX, RBX);
+            cc.codegen.instr2(And, a, b);
+        }
+        Op::Xor => {
+            cc.codegen.instr2(Xor, a, b);
         }
         Op::Lsh => {
             cc.codegen.instr2(Mov, RCX, RBX);
-            cc.codegen.instr2(Sal, RAX, CL);
+            cc.codegen.instr2(Sal, a, CL);
         }
         Op::Rsh => {
             cc.codegen.instr2(Mov, RCX, RBX);
-            cc.codegen.instr2(Sar, RAX, CL);
-        }
-        Op::LogicalOr => {
-            cc.codegen.instr2(Or, RAX, RBX);
-            return Ok(ExprOpr::new(RAX, VariableType::Bool));
+            let mnem = if unsigned { Shr } else { Sar };
+            cc.codegen.instr2(mnem, a, CL);
         }
-        Op::LogicalAnd => {
-            cc.codegen.instr2(And, RAX, RBX);
-            return Ok(ExprOpr::new(RAX, VariableType::Bool));
-        }
-        Op::Not => {
+        Op::LogicalOr | Op::LogicalAnd => unreachable!("logical operations are short circuited"),
+        Op::Not | Op::BitNot => {
             return Err(CompilationError::InValidBinaryOperation(
                 bexpr.op.to_owned(),
                 left.vtype.to_string(),
@@ -203,21 +433,113 @@ fn compile_binary_expr(
             ));
         }
     }
-    Ok(ExprOpr::new(RAX, left.vtype.cast(&right.vtype)?))
+    Ok(ExprOpr::new(RAX, res_type))
+}
+
+/// Multiplies the offset of a typed pointer in register by size of the pointee
+pub fn scale_pointer_offset(
+    cc: &mut CompilerContext,
+    reg: Reg,
+    pointee: &VariableType,
+) -> Result<(), CompilationError> {
+    let stride = resolve_type(cc, pointee)?.stride();
+    if stride != 1 {
+        cc.codegen.instr2(Mov, RCX, stride);
+        cc.codegen.instr2(Imul, reg, RCX);
+    }
+    Ok(())
 }
 
 fn compile_array_index(
     cc: &mut CompilerContext,
     block: &Block,
     ai: &ArrayIndex,
+    loc: &Loc,
 ) -> Result<ExprOpr, CompilationError> {
-    let v_map = cc.variables_map.get(&ai.ident, block)?;
+    // Index is kept on the stack while the array address is computed
     let indexer = compile_expr(cc, block, &ai.indexer)?;
-    mov_unknown_to_register(cc, RBX, indexer.value);
-    let mem_acss = v_map.mem_with_offset_reg(Reg::RBX);
-    match v_map.vtype {
-        VariableType::Array(t, _) => Ok(ExprOpr::new(mem_acss, t.as_ref().clone())),
-        _ => unreachable!(),
+    mov_unknown_to_register(cc, RAX, indexer.value);
+    cc.codegen.instr1(Push, RAX);
+    let array = compile_expr(cc, block, &ai.array)?;
+    let Opr::Mem(mem) = array.value else {
+        return Err(CompilationError::UnexpectedType(array.vtype.to_string()));
+    };
+    let (item_vtype, len, base, disp) = match array.vtype {
+        VariableType::Array(t, n) => {
+            let (base, disp) = match mem.addr_type {
+                MemAddrType::Addr(r) => (r, 0),
+                MemAddrType::Disp(r, d) => (r, d),
+                MemAddrType::AddrRela(_) => {
+                    displaceable_mem(cc, mem, RSI);
+                    (RSI, 0)
+                }
+                MemAddrType::Sib(..) => {
+                    cc.codegen.instr2(Lea, RSI, mem.with_offset(0, 8));
+                    (RSI, 0)
+                }
+            };
+            (t, Opr::from(n), base, disp)
+        }
+        // Slices hold the address of the first item and the length
+        VariableType::Slice(t) => {
+            let mem = displaceable_mem(cc, mem, RSI);
+            cc.codegen.instr2(Mov, RCX, mem.with_offset(8, 8));
+            cc.codegen.instr2(Mov, RSI, mem.with_offset(0, 8));
+            (t, RCX.into(), RSI, 0)
+        }
+        _ => return Err(CompilationError::UnexpectedType(array.vtype.to_string())),
+    };
+    cc.codegen.instr1(Pop, RBX);
+    if !cc.options.no_bounds_check {
+        compile_bounds_check(cc, len, loc);
+    }
+    let stride = item_vtype.stride();
+    let scale = match stride {
+        1 | 2 | 4 | 8 => stride as u8,
+        _ => {
+            cc.codegen.instr2(Mov, RCX, stride);
+            cc.codegen.instr2(Imul, RBX, RCX);
+            1
+        }
+    };
+    Ok(ExprOpr::new(
+        MemAddr::new_sib_s(item_vtype.mem_size(), base, disp, RBX, scale),
+        *item_vtype,
+    ))
+}
+
+/// Jumps to the panic routine if the index in RBX is not less than the length
+/// Negative indexes are treated as large unsigned values
+fn compile_bounds_check(cc: &mut CompilerContext, len: Opr, loc: &Loc) {
+    let msg = panic_message(cc, &format!("{loc}: Index out of bounds\n"));
+    cc.codegen.instr2(Mov, RDI, msg);
+    cc.codegen.instr2(Cmp, RBX, len);
+    cc.codegen.instr1(Jae, Opr::rel("runtime_panic"));
+}
+
+/// Length of an array, slice or string literal
+fn compile_len(
+    cc: &mut CompilerContext,
+    block: &Block,
+    fc: &FunctionCall,
+) -> Result<ExprOpr, CompilationError> {
+    if fc.args.len() != 1 {
+        return Err(CompilationError::Err(format!(
+            "Function (len) expects 1 argument but {} were given",
+            fc.args.len()
+        )));
+    }
+    if let ExprType::String(s) = &fc.args[0].etype {
+        return Ok(ExprOpr::new(s.len(), VariableType::Long));
+    }
+    let eo = compile_expr(cc, block, &fc.args[0])?;
+    match (eo.vtype, eo.value) {
+        (VariableType::Array(_, n), _) => Ok(ExprOpr::new(n, VariableType::Long)),
+        (VariableType::Slice(_), Opr::Mem(mem)) => {
+            let mem = displaceable_mem(cc, mem, RAX);
+            Ok(ExprOpr::new(mem.with_offset(8, 8), VariableType::Long))
+        }
+        (vtype, _) => Err(CompilationError::UnexpectedType(vtype.to_string())),
     }
 }
 
@@ -230,11 +552,14 @@ fn compile_unaray_expr(
     if left_eo.value.is_literal() {
         return fold_unary_expr(&left_eo, &uexpr.op);
     }
-    let new_type = match left_eo.vtype {
+    if left_eo.vtype == VariableType::Float {
+        return compile_float_unary_expr(cc, uexpr, left_eo);
+    }
+    let new_type = match &left_eo.vtype {
         VariableType::UInt => VariableType::Int,
         VariableType::ULong => VariableType::Long,
-        VariableType::Char => VariableType::Int,
-        _ => left_eo.vtype,
+        VariableType::Char | VariableType::UShort => VariableType::Int,
+        _ => left_eo.vtype.clone(),
     };
     match uexpr.op {
         Op::Sub => {
@@ -243,35 +568,88 @@ fn compile_unaray_expr(
             Ok(ExprOpr::new(RAX, new_type))
         }
         Op::Plus => Ok(ExprOpr::new(left_eo.value, new_type)),
-        Op::Not => {
+        Op::Not if left_eo.vtype == VariableType::Bool => {
+            mov_unknown_to_register(cc, RAX, left_eo.value);
+            cc.codegen.instr2(Xor, RAX, 1);
+            Ok(ExprOpr::new(RAX, VariableType::Bool))
+        }
+        Op::BitNot if left_eo.vtype.is_integer() => {
             mov_unknown_to_register(cc, RAX, left_eo.value);
             cc.codegen.instr1(Not, RAX);
-            Ok(ExprOpr::new(RAX, new_type))
+            Ok(ExprOpr::new(RAX, left_eo.vtype))
         }
+        Op::Not | Op::BitNot => Err(CompilationError::InValidUnaryOperation(
+            uexpr.op.to_owned(),
+            left_eo.vtype.to_string(),
+        )),
         _ => {
             unreachable!();
         }
     }
 }
 
+fn compile_float_unary_expr(
+    cc: &mut CompilerContext,
+    uexpr: &UnaryExpr,
+    left_eo: ExprOpr,
+) -> Result<ExprOpr, CompilationError> {
+    match uexpr.op {
+        Op::Sub => {
+            // Negative literals are stored directly in data section
+            if let ExprType::Float(f) = uexpr.right.etype {
+                let id = cc
+                    .codegen
+                    .add_data((-f).to_le_bytes().to_vec(), VariableType::Float);
+                return Ok(ExprOpr::new(
+                    MemAddr::new_rela_s(8, id),
+                    VariableType::Float,
+                ));
+            }
+            mov_unknown_to_xmm(cc, XMM0, left_eo.value, &left_eo.vtype);
+            // Flip the sign bit
+            cc.codegen.instr2(Mov, RAX, i64::MIN);
+            cc.codegen.instr2(Movq, XMM1, RAX);
+            cc.codegen.instr2(Xorpd, XMM0, XMM1);
+            Ok(ExprOpr::new(XMM0, VariableType::Float))
+        }
+        Op::Plus => Ok(left_eo),
+        _ => Err(CompilationError::InValidUnaryOperation(
+            uexpr.op.to_owned(),
+            left_eo.vtype.to_string(),
+        )),
+    }
+}
+
 fn compile_access(
     cc: &mut CompilerContext,
     block: &Block,
-    ident: &str,
-    expr: &Expr,
+    base: &Expr,
+    item: &str,
 ) -> Result<ExprOpr, CompilationError> {
-    let v_map = cc.variables_map.get(ident, block)?;
-    let VariableType::Struct(struc) = v_map.vtype.clone() else {
-        return Err(CompilationError::UnexpectedType(v_map.vtype.to_string()));
+    let mut base_eo = compile_expr(cc, block, base)?;
+    // Pointers are dereferenced implicitly
+    if let VariableType::TypedPointer(pointee) = &base_eo.vtype {
+        let vtype = resolve_type(cc, pointee)?;
+        mov_unknown_to_register(cc, RDX, base_eo.value);
+        base_eo = ExprOpr::new(MemAddr::new(RDX), vtype);
+    }
+    let VariableType::Struct(struc) = base_eo.vtype else {
+        return Err(CompilationError::UnexpectedType(base_eo.vtype.to_string()));
+    };
+    let Opr::Mem(mem) = base_eo.value else {
+        return Err(CompilationError::UnexpectedType(struc.ident));
     };
-    let acv = match &expr.etype {
-        ExprType::Variable(v) => struc.items.get(v).unwrap(),
-        _ => todo!(),
+    let Some(acv) = struc.get(item) else {
+        return Err(CompilationError::UndefinedStructItem(
+            struc.ident.clone(),
+            item.to_owned(),
+        ));
     };
-    mov_unknown_to_register(cc, RDX, mem!(RBP, v_map.offset).into());
-    cc.codegen.instr2(Add, RDX, acv.offset);
-    mov_unknown_to_register(cc, RAX, MemAddr::new_s(acv.vtype.item_size(), RDX).into());
-    Ok(ExprOpr::new(RAX, acv.vtype.clone()))
+    let mem_acss = displaceable_mem(cc, mem, RDX);
+    Ok(ExprOpr::new(
+        mem_acss.with_offset(acv.offset, acv.vtype.mem_size()),
+        acv.vtype.clone(),
+    ))
 }
 
 fn compile_ptr(
@@ -280,69 +658,644 @@ fn compile_ptr(
     expr: &Expr,
 ) -> Result<ExprOpr, CompilationError> {
     match &expr.etype {
-        ExprType::Variable(v) => {
-            let v_map = cc.variables_map.get(v, block)?;
-            match v_map.vtype {
-                VariableType::Array(_, _) => {
-                    cc.codegen.instr2(Lea, RAX, mem!(RBP, v_map.offset));
-                    Ok(ExprOpr::new(RAX, VariableType::Pointer))
-                }
-                _ => {
-                    cc.codegen.instr2(Mov, RAX, RBP);
-                    cc.codegen.instr2(Sub, RAX, -(v_map.offset));
-                    Ok(ExprOpr::new(RAX, VariableType::Pointer))
-                }
-            }
+        ExprType::Variable(v) if cc.variables_map.get(v, block).is_err() => {
+            compile_function_value(cc, v)
         }
-        _ => {
-            todo!("Impl Pointers");
+        ExprType::Variable(_)
+        | ExprType::Access(..)
+        | ExprType::ArrayIndex(_)
+        | ExprType::DeRef(_) => {
+            let eo = compile_expr(cc, block, expr)?;
+            let Opr::Mem(mem) = eo.value else {
+                return Err(CompilationError::UnexpectedType(eo.vtype.to_string()));
+            };
+            compile_mem_ptr(cc, mem, eo.vtype.pointer_to())
         }
+        _ => Err(CompilationError::UnexpectedType("Literal".to_owned())),
     }
 }
 
+/// Types that can be converted to each other with the as operator
+#[derive(PartialEq)]
+enum CastKind {
+    Integer,
+    Address,
+    Float,
+}
+
+impl CastKind {
+    fn of(vtype: &VariableType) -> Option<Self> {
+        match vtype {
+            VariableType::Float => Some(Self::Float),
+            VariableType::Pointer
+            | VariableType::TypedPointer(_)
+            | VariableType::Function(_)
+            | VariableType::String => Some(Self::Address),
+            VariableType::Bool => Some(Self::Integer),
+            VariableType::Enum(_) if !vtype.is_aggregate() => Some(Self::Integer),
+            _ if vtype.is_integer() => Some(Self::Integer),
+            _ => None,
+        }
+    }
+}
+
+/// Explicit conversion of a value to another type
+fn compile_cast(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+    target: &VariableType,
+) -> Result<ExprOpr, CompilationError> {
+    let target = resolve_type(cc, target)?;
+    let eo = compile_expr(cc, block, expr)?;
+    if eo.vtype == target {
+        return Ok(eo);
+    }
+    if let Some(folded) = fold_cast(&eo, &target) {
+        return Ok(folded);
+    }
+    let invalid = || CompilationError::InvalidTypeCasting(eo.vtype.to_string(), target.to_string());
+    let (Some(from), Some(to)) = (CastKind::of(&eo.vtype), CastKind::of(&target)) else {
+        return Err(invalid());
+    };
+    // Integers can not be turned into enums and addresses are not numbers
+    if matches!(target, VariableType::Enum(_))
+        || (from == CastKind::Float && to == CastKind::Address)
+        || (from == CastKind::Address && to == CastKind::Float)
+    {
+        return Err(invalid());
+    }
+    if from == CastKind::Float {
+        if to == CastKind::Float {
+            return Ok(ExprOpr::new(eo.value, target));
+        }
+        mov_float_to_register(cc, RAX, eo.value);
+    } else {
+        // Values are extended to 64 bits based on the sign of their type
+        mov_unknown_to_register(cc, RAX, eo.value.clone());
+        match eo.vtype.size() {
+            1 => cc.codegen.instr2(Movzx, EAX, AL),
+            2 if eo.vtype.is_unsigned() => cc.codegen.instr2(Movzx, EAX, AX),
+            2 => cc.codegen.instr2(Movsx, RAX, AX),
+            4 if eo.vtype.is_unsigned() => cc.codegen.instr2(Mov, EAX, EAX),
+            4 => cc.codegen.instr2(Movsxd, RAX, EAX),
+            _ => (),
+        }
+        if to == CastKind::Float {
+            mov_unknown_to_xmm(cc, XMM0, RAX.into(), &eo.vtype);
+            return Ok(ExprOpr::new(XMM0, target));
+        }
+    }
+    // Values are truncated to the size of the target
+    match target {
+        VariableType::Bool => {
+            cc.codegen.instr2(Mov, RCX, 0);
+            cc.codegen.instr2(Mov, RDX, 1);
+            cc.codegen.instr2(Cmp, RAX, 0);
+            cc.codegen.instr2(Cmovne, RCX, RDX);
+            return Ok(ExprOpr::new(RCX, target));
+        }
+        _ if target.size() == 1 => cc.codegen.instr2(Movzx, EAX, AL),
+        _ if target.size() == 2 => cc.codegen.instr2(Movzx, EAX, AX),
+        _ if target.size() == 4 => cc.codegen.instr2(Mov, EAX, EAX),
+        _ => (),
+    }
+    Ok(ExprOpr::new(RAX, target))
+}
+
+/// Address of a function that can be stored or called indirectly
+fn compile_function_value(cc: &CompilerContext, ident: &str) -> Result<ExprOpr, CompilationError> {
+    match cc.namespace_map.get(ident) {
+        Some(NSType::Function(decl)) => Ok(ExprOpr::new(
+            Opr::Rela(ident.to_owned()),
+            VariableType::Function(FunctionType::from_decl(decl)),
+        )),
+        Some(NSType::Ffi(..)) => Err(CompilationError::Err(format!(
+            "Ffi function ({ident}) can not be used as a value"
+        ))),
+        Some(NSType::GenericFunction(..)) => Err(CompilationError::Err(format!(
+            "Generic function ({ident}) can not be used as a value"
+        ))),
+        _ => Err(CompilationError::UndefinedVariable(ident.to_owned())),
+    }
+}
+
+/// Returns the enum type and the tag of an enum variant
+/// Variants of generic enums take their type arguments from the expected type
+pub fn enum_variant(
+    cc: &CompilerContext,
+    ev: &EnumVariantExpr,
+    expected: &VariableType,
+) -> Result<(EnumType, i32), CompilationError> {
+    let et = match (cc.namespace_map.get(&ev.ident), expected) {
+        (Some(NSType::Enum(et)), _) => et,
+        (Some(NSType::GenericEnum(_)), VariableType::Enum(et)) if et.base_ident() == ev.ident => et,
+        (Some(NSType::GenericEnum(_)), _) => {
+            return Err(CompilationError::UninferredEnumType(ev.ident.clone()))
+        }
+        _ => return Err(CompilationError::UnknownType(ev.ident.clone())),
+    };
+    let Some((tag, variant)) = et.get(&ev.variant) else {
+        return Err(CompilationError::UndefinedEnumVariant(
+            ev.ident.clone(),
+            ev.variant.clone(),
+        ));
+    };
+    if variant.payload.items.len() != ev.args.len() {
+        return Err(CompilationError::Err(format!(
+            "Enum variant ({}::{}) expects {} values but {} were given",
+            ev.ident,
+            ev.variant,
+            variant.payload.items.len(),
+            ev.args.len()
+        )));
+    }
+    Ok((et.clone(), tag))
+}
+
+fn compile_enum_variant(
+    cc: &mut CompilerContext,
+    ev: &EnumVariantExpr,
+) -> Result<ExprOpr, CompilationError> {
+    let (et, tag) = enum_variant(cc, ev, &VariableType::Any)?;
+    if !ev.args.is_empty() {
+        return Err(CompilationError::Err(format!(
+            "Enum variant ({}::{}) with payload can only be assigned to a variable",
+            ev.ident, ev.variant
+        )));
+    }
+    Ok(ExprOpr::new(tag, VariableType::Enum(et)))
+}
+
+/// Constructs an enum variant and its payload in memory
+/// If saved is true, address of the memory is read from the top of the stack
+pub fn compile_enum_into(
+    cc: &mut CompilerContext,
+    block: &Block,
+    ev: &EnumVariantExpr,
+    expected: &VariableType,
+    dst: MemAddr,
+    saved: bool,
+) -> Result<VariableType, CompilationError> {
+    let (et, tag) = enum_variant(cc, ev, expected)?;
+    let items = et.get(&ev.variant).unwrap().1.payload.items.clone();
+    // Payload values are kept on the stack until all of them are computed
+    for (arg, item) in ev.args.iter().zip(items.iter()) {
+        let eo = compile_expr(cc, block, arg)?;
+        item.vtype.cast(&eo.vtype)?;
+        if item.vtype.is_aggregate() || matches!(item.vtype, VariableType::Array(..)) {
+            let Opr::Mem(mem) = eo.value else {
+                return Err(CompilationError::UnmatchingTypes(
+                    item.vtype.to_string(),
+                    eo.vtype.to_string(),
+                ));
+            };
+            let mem = displaceable_mem(cc, mem, RAX);
+            cc.codegen.instr2(Lea, RAX, mem.with_offset(0, 8));
+        } else if item.vtype == VariableType::Float {
+            mov_unknown_to_xmm(cc, XMM0, eo.value, &eo.vtype);
+            cc.codegen.instr2(Movq, RAX, XMM0);
+        } else if eo.vtype == VariableType::Float {
+            mov_float_to_register(cc, RAX, eo.value);
+        } else {
+            mov_unknown_to_register(cc, RAX, eo.value);
+        }
+        cc.codegen.instr1(Push, RAX);
+    }
+    let dst = if saved {
+        cc.codegen.instr2(Mov, RAX, RSP);
+        cc.codegen
+            .instr2(Mov, RDI, memq!(RAX, 8 * items.len() as i32));
+        MemAddr::new(RDI)
+    } else {
+        displaceable_mem(cc, dst, RDI)
+    };
+    let payload_offset = et.payload_offset() as i32;
+    for item in items.iter().rev() {
+        let offset = payload_offset + item.offset;
+        if item.vtype.is_aggregate() || matches!(item.vtype, VariableType::Array(..)) {
+            cc.codegen.instr1(Pop, RSI);
+            copy_memory(
+                cc,
+                dst.with_offset(offset, 0),
+                MemAddr::new(RSI),
+                item.vtype.size(),
+            );
+        } else {
+            let size = item.vtype.mem_size();
+            cc.codegen.instr1(Pop, RAX);
+            cc.codegen
+                .instr2(Mov, dst.with_offset(offset, size), RAX.convert(size));
+        }
+    }
+    cc.codegen.instr2(Mov, dst.with_offset(0, 4), tag);
+    Ok(VariableType::Enum(et))
+}
+
+/// Stores address and length of an array as a slice
+pub fn store_array_slice(cc: &mut CompilerContext, dst: MemAddr, src: MemAddr, len: usize) {
+    let src = displaceable_mem(cc, src, RAX);
+    cc.codegen.instr2(Lea, RAX, src.with_offset(0, 8));
+    let dst = displaceable_mem(cc, dst, RDI);
+    cc.codegen.instr2(Mov, dst.with_offset(0, 8), RAX);
+    cc.codegen.instr2(Mov, RAX, len);
+    cc.codegen.instr2(Mov, dst.with_offset(8, 8), RAX);
+}
+
+/// Loads the address of memory to RAX
+fn compile_mem_ptr(
+    cc: &mut CompilerContext,
+    mem: MemAddr,
+    vtype: VariableType,
+) -> Result<ExprOpr, CompilationError> {
+    let mem = displaceable_mem(cc, mem, RAX);
+    cc.codegen.instr2(Lea, RAX, mem.with_offset(0, 8));
+    Ok(ExprOpr::new(RAX, vtype))
+}
+
+/// Returns length and address of a slice or an array in reverse order
+fn slice_parts(
+    cc: &mut CompilerContext,
+    vtype: &VariableType,
+    eo: ExprOpr,
+) -> Result<Vec<ExprOpr>, CompilationError> {
+    let vtype = vtype.cast(&eo.vtype)?;
+    let Opr::Mem(mem) = eo.value else {
+        return Err(CompilationError::UnexpectedType(eo.vtype.to_string()));
+    };
+    let mem = displaceable_mem(cc, mem, RSI);
+    match eo.vtype {
+        VariableType::Array(_, n) if vtype != eo.vtype => {
+            cc.codegen.instr2(Lea, RSI, mem.with_offset(0, 8));
+            Ok(vec![
+                ExprOpr::new(n, VariableType::Long),
+                ExprOpr::new(RSI, VariableType::Pointer),
+            ])
+        }
+        VariableType::Slice(_) => {
+            cc.codegen.instr2(Lea, RSI, mem.with_offset(0, 8));
+            Ok(vec![
+                ExprOpr::new(memq!(RSI, 8), VariableType::Long),
+                ExprOpr::new(memq!(RSI), VariableType::Pointer),
+            ])
+        }
+        _ => Err(CompilationError::UnmatchingTypes(
+            vtype.to_string(),
+            eo.vtype.to_string(),
+        )),
+    }
+}
+
+/// Address of the called function
+enum Callee {
+    Label(String),
+    Ffi(String),
+    /// Function value pushed to the stack before the arguments
+    Value,
+}
+
 fn compile_function_call(
     cc: &mut CompilerContext,
     block: &Block,
     fc: &FunctionCall,
+    loc: &Loc,
+) -> Result<ExprOpr, CompilationError> {
+    if let Some(receiver) = &fc.receiver {
+        return compile_method_call(cc, block, receiver, fc, loc);
+    }
+    // Variables holding a function value shadow functions with the same name
+    if cc.variables_map.get(&fc.ident, block).is_ok() {
+        let callee = Expr {
+            etype: ExprType::Variable(fc.ident.clone()),
+            loc: loc.clone(),
+        };
+        return compile_indirect_call(cc, block, &callee, &fc.args);
+    }
+    let (fun, callee) = match cc.namespace_map.get(&fc.ident) {
+        Some(NSType::Function(f)) => (f.to_owned(), Callee::Label(fc.ident.clone())),
+        Some(NSType::Ffi(f, ff)) => (f.to_owned(), Callee::Ffi(ff.to_owned())),
+        Some(NSType::GenericFunction(def)) => {
+            let def = def.clone();
+            let (label, decl) = instantiate_function(cc, block, &def, &fc.args, loc)?;
+            (decl, Callee::Label(label))
+        }
+        Some(_) => return Err(CompilationError::UndefinedNameSpace(fc.ident.clone())),
+        None => return Err(CompilationError::FunctionOutOfScope(fc.ident.clone())),
+    };
+    compile_call(cc, block, &fun, &fc.args, callee)
+}
+
+/// Calls a method of the struct behind the receiver
+/// Struct items holding a function value are called instead of methods with the same name
+fn compile_method_call(
+    cc: &mut CompilerContext,
+    block: &Block,
+    receiver: &Expr,
+    fc: &FunctionCall,
+    loc: &Loc,
+) -> Result<ExprOpr, CompilationError> {
+    let vtype = expr_type(cc, block, receiver)?;
+    let st = match &vtype {
+        VariableType::Struct(st) => st.clone(),
+        VariableType::TypedPointer(t) => match resolve_type(cc, t)? {
+            VariableType::Struct(st) => st,
+            _ => return Err(CompilationError::UnexpectedType(vtype.to_string())),
+        },
+        _ => return Err(CompilationError::UnexpectedType(vtype.to_string())),
+    };
+    if st.get(&fc.ident).is_some() {
+        let callee = Expr {
+            etype: ExprType::Access(Box::new(receiver.clone()), fc.ident.clone()),
+            loc: loc.clone(),
+        };
+        return compile_indirect_call(cc, block, &callee, &fc.args);
+    }
+    // Methods of generic structs are defined on the struct and not its instances
+    let type_ident = match cc.generic_instances.get(&st.ident) {
+        Some((base, _)) => base.clone(),
+        None => st.ident.clone(),
+    };
+    let ident = method_ident(&type_ident, &fc.ident);
+    let self_type = match cc.namespace_map.get(&ident) {
+        Some(NSType::Function(decl)) => decl.args[0].typedef.clone(),
+        Some(NSType::GenericFunction(def)) => def.decl.args[0].typedef.clone(),
+        _ => {
+            return Err(CompilationError::UndefinedMethod(
+                st.ident,
+                fc.ident.clone(),
+            ))
+        }
+    };
+    // Receiver is passed by address to methods that take a pointer to self
+    let etype = match (&vtype, self_type) {
+        (VariableType::Struct(_), VariableType::TypedPointer(_)) => {
+            ExprType::Ptr(Box::new(receiver.clone()))
+        }
+        (VariableType::TypedPointer(_), t) if !matches!(t, VariableType::TypedPointer(_)) => {
+            ExprType::DeRef(Box::new(receiver.clone()))
+        }
+        _ => receiver.etype.clone(),
+    };
+    let mut args = vec![Expr {
+        etype,
+        loc: receiver.loc.clone(),
+    }];
+    args.extend(fc.args.iter().cloned());
+    let fc = FunctionCall {
+        ident,
+        args,
+        receiver: None,
+    };
+    compile_function_call(cc, block, &fc, loc)
+}
+
+fn compile_indirect_call(
+    cc: &mut CompilerContext,
+    block: &Block,
+    callee: &Expr,
+    args: &[Expr],
+) -> Result<ExprOpr, CompilationError> {
+    let eo = compile_expr(cc, block, callee)?;
+    let VariableType::Function(ft) = &eo.vtype else {
+        return Err(CompilationError::UnexpectedType(eo.vtype.to_string()));
+    };
+    if ft.args.len() != args.len() {
+        return Err(CompilationError::Err(format!(
+            "Function value of type ({}) expects {} arguments but {} were given",
+            eo.vtype,
+            ft.args.len(),
+            args.len()
+        )));
+    }
+    let mut fun = ft.decl("");
+    for arg in fun.args.iter_mut() {
+        arg.typedef = resolve_type(cc, &arg.typedef)?;
+    }
+    fun.ret_type = resolve_type(cc, &fun.ret_type)?;
+    save_temp_value(cc, eo.value);
+    compile_call(cc, block, &fun, args, Callee::Value)
+}
+
+/// Type of an expression
+/// Expressions that can not be typed by looking them up are compiled
+/// and the generated code is dropped
+pub fn expr_type(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+) -> Result<VariableType, CompilationError> {
+    if let Some(vtype) = known_type(cc, block, expr) {
+        return Ok(vtype);
+    }
+    let len = cc.codegen.instructs_len();
+    let mem_offset = cc.mem_offset;
+    // Large tuples need a destination even when only their type is needed
+    let tuple_dest = cc.tuple_dest.replace(MemAddr::new(RDI));
+    let res = compile_expr(cc, block, expr).map(|eo| eo.vtype);
+    cc.codegen.truncate(len);
+    cc.mem_offset = mem_offset;
+    cc.tuple_dest = tuple_dest;
+    res
+}
+
+/// Type of variables, literals, calls and the places they refer to
+/// without generating any code, None if the expression has to be compiled
+fn known_type(cc: &mut CompilerContext, block: &Block, expr: &Expr) -> Option<VariableType> {
+    match &expr.etype {
+        ExprType::Variable(v) => cc.variables_map.get(v, block).ok().map(|v| v.vtype),
+        ExprType::Bool(_) | ExprType::Compare(_) => Some(VariableType::Bool),
+        ExprType::Char(_) => Some(VariableType::Char),
+        ExprType::Int(x) if *x < 0 => Some(VariableType::ULong),
+        ExprType::Int(x) => match i32::try_from(*x) {
+            Ok(_) => Some(VariableType::Int),
+            Err(_) => Some(VariableType::Long),
+        },
+        ExprType::Float(_) => Some(VariableType::Float),
+        ExprType::String(_) => Some(VariableType::String),
+        ExprType::SizeOf(_) | ExprType::AlignOf(_) => Some(VariableType::Long),
+        ExprType::Cast(_, vtype) => resolve_type(cc, vtype).ok(),
+        ExprType::Access(base, item) => {
+            let vtype = match known_type(cc, block, base)? {
+                VariableType::TypedPointer(t) => resolve_type(cc, &t).ok()?,
+                vtype => vtype,
+            };
+            match vtype {
+                VariableType::Struct(st) => st.get(item).map(|i| i.vtype.clone()),
+                _ => None,
+            }
+        }
+        ExprType::ArrayIndex(ai) => match known_type(cc, block, &ai.array)? {
+            VariableType::Array(t, _) | VariableType::Slice(t) => Some(*t),
+            _ => None,
+        },
+        ExprType::If(ie) => {
+            let then_type = known_type(cc, block, &ie.then)?;
+            then_type.cast(&known_type(cc, block, &ie.otherwise)?).ok()
+        }
+        ExprType::DeRef(e) => match known_type(cc, block, e)? {
+            VariableType::TypedPointer(t) => resolve_type(cc, &t).ok(),
+            VariableType::Pointer => Some(VariableType::Any),
+            _ => None,
+        },
+        ExprType::FunctionCall(fc)
+            if fc.receiver.is_none() && cc.variables_map.get(&fc.ident, block).is_err() =>
+        {
+            match cc.namespace_map.get(&fc.ident) {
+                Some(NSType::Function(decl)) | Some(NSType::Ffi(decl, _)) => {
+                    Some(decl.ret_type.clone())
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Calls a function with the System V calling convention
+fn compile_call(
+    cc: &mut CompilerContext,
+    block: &Block,
+    fun: &FunctionDecl,
+    args: &[Expr],
+    callee: Callee,
 ) -> Result<ExprOpr, CompilationError> {
+    // Large values are written to the address passed as a hidden first argument
+    let ret_dest = match returns_in_memory(&fun.ret_type) {
+        true => Some(cc.tuple_dest.take().ok_or_else(|| {
+            CompilationError::Err(
+                "Values larger than 16 bytes can only be stored in variables".to_string(),
+            )
+        })?),
+        false => None,
+    };
+    // Every argument is kept on the stack when some of them may not fit in registers
+    let slices = fun
+        .args
+        .iter()
+        .filter(|a| matches!(a.typedef, VariableType::Slice(_)))
+        .count();
+    let spill_args = args.len() + slices + ret_dest.is_some() as usize > 6;
     let mut expr_list = Vec::new();
-    for arg in fc.args.iter().rev() {
-        let expr_op = compile_expr(cc, block, arg)?;
+    for (i, arg) in args.iter().enumerate().rev() {
+        let mut expr_op = compile_expr(cc, block, arg)?;
+        // Slices are passed as an address and a length
+        if let Some(VariableType::Slice(_)) = fun.args.get(i).map(|a| &a.typedef) {
+            for part in slice_parts(cc, &fun.args[i].typedef, expr_op)? {
+                if part.is_temp() {
+                    save_temp_value(cc, part.value.clone());
+                } else if spill_args {
+                    mov_unknown_to_register(cc, RAX, part.value.clone());
+                    cc.codegen.instr1(Push, RAX);
+                }
+                expr_list.push(part);
+            }
+            continue;
+        }
+        // Convert arguments to the type of parameters
+        if let Some(farg) = fun.args.get(i) {
+            farg.typedef.check_distinct(&expr_op.vtype)?;
+            if farg.typedef == VariableType::Float && expr_op.vtype != VariableType::Float {
+                mov_unknown_to_xmm(cc, XMM0, expr_op.value, &expr_op.vtype);
+                expr_op = ExprOpr::new(XMM0, VariableType::Float);
+            } else if farg.typedef != VariableType::Float && expr_op.vtype == VariableType::Float {
+                mov_float_to_register(cc, RAX, expr_op.value);
+                expr_op = ExprOpr::new(RAX, farg.typedef.clone());
+            }
+        }
         if expr_op.is_temp() {
             save_temp_value(cc, expr_op.value.clone());
+        } else if spill_args {
+            mov_unknown_to_register(cc, RAX, expr_op.value.clone());
+            cc.codegen.instr1(Push, RAX);
         }
         expr_list.push(expr_op);
     }
-    for (i, item) in expr_list.iter().rev().enumerate() {
-        if item.is_temp() {
-            restore_last_temp_value(cc, function_args_register(i));
+    expr_list.reverse();
+    // Spilled arguments are accessed relative to the last pushed one
+    if spill_args {
+        cc.codegen.instr2(Mov, RAX, RSP);
+    }
+    let mut int_args = ret_dest.is_some() as usize;
+    let mut float_args = 0;
+    let mut stack_args = Vec::new();
+    for (i, item) in expr_list.iter().enumerate() {
+        let is_float = item.vtype == VariableType::Float;
+        let reg = match is_float {
+            true if float_args < 8 => function_float_args_register(float_args),
+            false if int_args < 6 => function_args_register(int_args),
+            _ => {
+                stack_args.push(i);
+                continue;
+            }
+        };
+        if is_float {
+            float_args += 1;
+        } else {
+            int_args += 1;
+        }
+        if spill_args {
+            let mnem = if is_float { Movsd } else { Mov };
+            cc.codegen.instr2(mnem, reg, memq!(RAX, 8 * i as i32));
+        } else if item.is_temp() {
+            restore_last_temp_value(cc, reg);
+        } else if is_float {
+            mov_unknown_to_xmm(cc, reg, item.value.clone(), &item.vtype);
         } else {
-            mov_unknown_to_register(cc, function_args_register(i), item.value.clone());
+            mov_unknown_to_register(cc, reg, item.value.clone());
         }
     }
-    let Some(func) = cc.namespace_map.get(&fc.ident) else {
-        return Err(CompilationError::FunctionOutOfScope(fc.ident.clone()));
-    };
-    cc.codegen.instr2(Mov, RAX, 0);
-    let fun;
-    match func {
-        NSType::Function(f) => {
-            fun = f.to_owned();
-            cc.codegen.instr1(Call, Opr::Loc(fc.ident.clone()));
-        }
-        NSType::Ffi(f, ff) => {
-            fun = f.to_owned();
-            cc.codegen.instr1(Pop, RBP);
-            cc.codegen.instr1(Call, Opr::Rela(ff.to_string()));
-            cc.codegen.instr1(Push, RBP);
-            cc.codegen.instr2(Mov, RBP, RSP);
-        }
-        _ => return Err(CompilationError::UndefinedNameSpace(fc.ident.clone())),
-    }
-    if fun.ret_type != VariableType::Void {
-        Ok(ExprOpr::new(RAX, fun.ret_type.clone()))
-    } else {
-        Ok(ExprOpr::new(0, fun.ret_type.clone()))
+    if let Some(dest) = ret_dest {
+        cc.codegen.instr2(Lea, RDI, dest);
+    }
+    if let Callee::Value = callee {
+        if spill_args {
+            cc.codegen
+                .instr2(Mov, RBX, memq!(RAX, 8 * expr_list.len() as i32));
+        } else {
+            cc.codegen.instr1(Pop, RBX);
+        }
+    }
+    // C functions expect the stack to be 16 byte aligned
+    if let Callee::Ffi(_) = callee {
+        cc.codegen.instr2(Mov, RBX, RSP);
+        cc.codegen.instr2(And, RSP, -16);
+        if stack_args.len() % 2 == 1 {
+            cc.codegen.instr2(Sub, RSP, 8);
+        }
+    }
+    for i in stack_args.iter().rev() {
+        cc.codegen.instr1(Push, memq!(RAX, 8 * *i as i32));
+    }
+    // Number of vector registers used for variadic functions
+    cc.codegen.instr2(Mov, RAX, float_args);
+    let value_slot = matches!(callee, Callee::Value) as usize;
+    match callee {
+        Callee::Ffi(ff) => {
+            cc.codegen.instr1(Call, Opr::Rela(ff));
+            cc.codegen.instr2(Mov, RSP, RBX);
+        }
+        Callee::Label(ident) => {
+            cc.codegen.instr1(Call, Opr::Loc(ident));
+            if !stack_args.is_empty() {
+                cc.codegen.instr2(Add, RSP, 8 * stack_args.len());
+            }
+        }
+        Callee::Value => {
+            cc.codegen.instr1(Call, RBX);
+            if !stack_args.is_empty() {
+                cc.codegen.instr2(Add, RSP, 8 * stack_args.len());
+            }
+        }
+    }
+    if spill_args {
+        // Function value is kept under the spilled arguments
+        cc.codegen
+            .instr2(Add, RSP, 8 * (expr_list.len() + value_slot));
+    }
+    match fun.ret_type {
+        VariableType::Void => Ok(ExprOpr::new(0, fun.ret_type.clone())),
+        VariableType::Float => Ok(ExprOpr::new(XMM0, fun.ret_type.clone())),
+        _ => Ok(ExprOpr::new(RAX, fun.ret_type.clone())),
     }
 }
 
@@ -353,22 +1306,19 @@ fn compile_deref(
 ) -> Result<ExprOpr, CompilationError> {
     let t = compile_expr(cc, block, expr)?;
     match t.vtype {
-        VariableType::Array(_, _) => {
-            todo!("Changed!");
+        VariableType::TypedPointer(pointee) => {
+            let vtype = resolve_type(cc, &pointee)?;
+            mov_unknown_to_register(cc, RAX, t.value);
+            Ok(ExprOpr::new(MemAddr::new_s(vtype.mem_size(), RAX), vtype))
         }
         VariableType::Pointer => {
-            let Opr::R64(r) = t.value else {
-                return Err(CompilationError::UnmatchingTypes(
-                    VariableType::Pointer,
-                    t.vtype,
-                ));
-            };
-            mov_unknown_to_register(cc, RCX, memq!(r).into());
+            mov_unknown_to_register(cc, RCX, t.value);
+            mov_unknown_to_register(cc, RCX, memq!(RCX).into());
             Ok(ExprOpr::new(RCX, VariableType::Any))
         }
         _ => Err(CompilationError::UnmatchingTypes(
-            VariableType::Pointer,
-            t.vtype,
+            VariableType::Pointer.to_string(),
+            t.vtype.to_string(),
         )),
     }
 }
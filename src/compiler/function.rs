@@ -24,36 +24,63 @@
 **********************************************************************************************/
 
 use crate::{
-    assembler::{mnemonic::Mnemonic::*, register::Reg::*},
+    assembler::{memory::MemAddr, mnemonic::Mnemonic::*, register::Reg::*},
+    memq,
     parser::{
         block::Block,
         function::{FunctionArg, FunctionDef},
+        types::VariableType,
     },
 };
 
 use super::{
-    block::compile_function_block_alrady_scoped, function_args_register_sized,
-    variables::VariableMap, CompilerContext, VariableMapBase,
+    block::compile_function_block_alrady_scoped,
+    function_args_register_sized, function_float_args_register,
+    tuples::{returns_in_memory, RET_PTR},
+    variables::VariableMap,
+    CompilerContext, NSType, VariableMapBase,
 };
 
 pub fn function_args(cc: &mut CompilerContext, block: &Block, args: &[FunctionArg]) {
-    for (args_count, arg) in args.iter().enumerate() {
+    let mut int_args = 0;
+    let mut float_args = 0;
+    let mut stack_args = 0;
+    for arg in args.iter() {
         let map = VariableMap::new(
             VariableMapBase::Stack(block.id.to_string()),
             cc.mem_offset,
             arg.typedef.clone(),
             false,
         );
-        if args_count < 6 {
-            let mem_acss = map.mem();
-            let reg = function_args_register_sized(args_count, &map.vtype);
-            cc.codegen.instr2(Mov, mem_acss, reg);
-        } else {
-            todo!();
+        // Slices are passed as an address and a length
+        let parts = match map.vtype {
+            VariableType::Slice(_) => vec![
+                (map.mem().with_offset(0, 8), VariableType::Pointer),
+                (map.mem().with_offset(8, 8), VariableType::Long),
+            ],
+            _ => vec![(map.mem(), map.vtype.clone())],
+        };
+        let size = 8 * parts.len();
+        for (mem_acss, vtype) in parts {
+            if vtype == VariableType::Float && float_args < 8 {
+                let reg = function_float_args_register(float_args);
+                cc.codegen.instr2(Movsd, mem_acss, reg);
+                float_args += 1;
+            } else if vtype != VariableType::Float && int_args < 6 {
+                let reg = function_args_register_sized(int_args, &vtype);
+                cc.codegen.instr2(Mov, mem_acss, reg);
+                int_args += 1;
+            } else {
+                // Rest of the arguments are pushed by the caller after the return address
+                let reg_size = mem_acss.size;
+                cc.codegen.instr2(Mov, RAX, memq!(RBP, 16 + stack_args * 8));
+                cc.codegen.instr2(Mov, mem_acss, RAX.convert(reg_size));
+                stack_args += 1;
+            }
         }
         let _ = cc.variables_map.insert(&arg.ident, map);
-        cc.mem_offset += 8;
-        cc.codegen.instr2(Sub, RSP, 8);
+        cc.mem_offset += size;
+        cc.codegen.instr2(Sub, RSP, size);
     }
 }
 
@@ -65,18 +92,31 @@ pub fn compile_function(cc: &mut CompilerContext, f: &FunctionDef) {
 
     cc.codegen.instr1(Push, RBP);
     cc.codegen.instr2(Mov, RBP, RSP);
-    function_args(cc, &f.block, &f.decl.args);
+    // RBX is callee saved so functions can be called back from C
+    cc.codegen.instr1(Push, RBX);
+    cc.mem_offset = 8;
+    let decl = match cc.namespace_map.get(&f.decl.ident) {
+        Some(NSType::Function(decl)) => decl.clone(),
+        _ => f.decl.clone(),
+    };
+    let mut args = decl.args.clone();
+    if returns_in_memory(&decl.ret_type) {
+        args.insert(
+            0,
+            FunctionArg {
+                ident: RET_PTR.to_string(),
+                typedef: VariableType::Pointer,
+            },
+        );
+    }
+    function_args(cc, &f.block, &args);
     /*--- Scoping function variables ---*/
     compile_function_block_alrady_scoped(cc, &f.block);
     //compile_block(cc, &f.block);
     // revert rbp
     cc.codegen.set_lable(f.block.end_name());
-    cc.codegen.instr1(Push, RAX);
-    // TODO: Issue a warning for assgigning variables in defer block
-    compile_function_block_alrady_scoped(cc, &f.defer_block);
     /*--- Unscoping function variables ---*/
-    cc.codegen.instr1(Pop, RAX);
-
+    cc.codegen.instr2(Mov, RBX, memq!(RBP, -8));
     cc.codegen.instr0(Leave);
     cc.codegen.instr0(Ret);
 }
@@ -0,0 +1,287 @@
+/**********************************************************************************************
+*
+*   compiler/generics: monomorphization of generic functions and structs
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use std::collections::BTreeMap;
+
+use crate::{
+    error_handeling::{CompilationError, Loc},
+    log_cerror,
+    parser::{
+        block::Block,
+        expr::Expr,
+        function::{FunctionDecl, FunctionDef},
+        types::{generic_ident, FunctionType, VariableType},
+    },
+};
+
+use super::{
+    expr::expr_type, function::compile_function, resolve_function_decl, CompilerContext, NSType,
+};
+
+/// Generic function waiting to be compiled for a set of type arguments
+pub struct FunctionInstance {
+    pub def: FunctionDef,
+    pub type_args: BTreeMap<String, VariableType>,
+    /// Location of the call that needed this instance
+    pub loc: Loc,
+}
+
+/// Replace type parameters of the current instance with their type arguments
+/// and name generic types after the type arguments they are used with
+pub fn concrete_type(
+    cc: &mut CompilerContext,
+    vtype: &VariableType,
+) -> Result<VariableType, CompilationError> {
+    match vtype {
+        VariableType::Custom(s) => Ok(cc.generic_args.get(s).unwrap_or(vtype).clone()),
+        VariableType::Generic(s, args) => {
+            let args = args
+                .iter()
+                .map(|t| concrete_type(cc, t))
+                .collect::<Result<Vec<_>, _>>()?;
+            let params = match cc.namespace_map.get(s) {
+                Some(NSType::GenericStruct(st)) => st.type_params.len(),
+                Some(NSType::GenericEnum(et)) => et.type_params.len(),
+                _ => return Err(CompilationError::UnknownType(s.to_owned())),
+            };
+            if params != args.len() {
+                return Err(CompilationError::InvalidTypeArguments(
+                    s.to_owned(),
+                    params,
+                    args.len(),
+                ));
+            }
+            let ident = generic_ident(s, &args);
+            cc.generic_instances
+                .insert(ident.clone(), (s.to_owned(), args));
+            Ok(VariableType::Custom(ident))
+        }
+        VariableType::Array(t, size) => {
+            Ok(VariableType::Array(Box::new(concrete_type(cc, t)?), *size))
+        }
+        VariableType::Slice(t) => Ok(VariableType::Slice(Box::new(concrete_type(cc, t)?))),
+        VariableType::Tuple(ts) => Ok(VariableType::Tuple(
+            ts.iter()
+                .map(|t| concrete_type(cc, t))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        VariableType::TypedPointer(t) => {
+            Ok(VariableType::TypedPointer(Box::new(concrete_type(cc, t)?)))
+        }
+        VariableType::Function(ft) => Ok(VariableType::Function(FunctionType {
+            args: ft
+                .args
+                .iter()
+                .map(|t| concrete_type(cc, t))
+                .collect::<Result<Vec<_>, _>>()?,
+            ret_type: Box::new(concrete_type(cc, &ft.ret_type)?),
+        })),
+        _ => Ok(vtype.clone()),
+    }
+}
+
+pub fn is_generic_enum(cc: &CompilerContext, ident: &str) -> bool {
+    matches!(cc.namespace_map.get(ident), Some(NSType::GenericEnum(_)))
+}
+
+/// Returns the label and declaration of the generic function instance
+/// that accepts the given arguments
+pub fn instantiate_function(
+    cc: &mut CompilerContext,
+    block: &Block,
+    def: &FunctionDef,
+    args: &[Expr],
+    loc: &Loc,
+) -> Result<(String, FunctionDecl), CompilationError> {
+    let type_args = infer_type_args(cc, block, &def.decl, args)?;
+    let mangled: Vec<String> = type_args.iter().map(mangle_type).collect();
+    let label = format!("{}@{}", def.decl.ident, mangled.join("@"));
+    if let Some(NSType::Function(decl)) = cc.namespace_map.get(&label) {
+        return Ok((label, decl.clone()));
+    }
+    let mut def = def.clone();
+    let type_args: BTreeMap<String, VariableType> =
+        def.decl.type_params.drain(..).zip(type_args).collect();
+    def.decl.ident = label.clone();
+    def.block.rename_master(&label);
+    let outer = std::mem::replace(&mut cc.generic_args, type_args.clone());
+    let signature = concrete_signature(cc, &mut def.decl);
+    cc.generic_args = outer;
+    signature?;
+    let decl = resolve_function_decl(cc, &def.decl);
+    cc.namespace_map
+        .insert(label.clone(), NSType::Function(decl.clone()));
+    cc.instance_queue.push(FunctionInstance {
+        def,
+        type_args,
+        loc: loc.clone(),
+    });
+    Ok((label, decl))
+}
+
+fn concrete_signature(
+    cc: &mut CompilerContext,
+    decl: &mut FunctionDecl,
+) -> Result<(), CompilationError> {
+    for arg in decl.args.iter_mut() {
+        arg.typedef = concrete_type(cc, &arg.typedef)?;
+    }
+    decl.ret_type = concrete_type(cc, &decl.ret_type)?;
+    Ok(())
+}
+
+/// Compile the body of a generic function instance
+/// Errors in the body are also reported at the call that needed the instance
+pub fn compile_function_instance(cc: &mut CompilerContext, instance: FunctionInstance) {
+    let errors = cc.errors;
+    cc.generic_args = instance.type_args;
+    compile_function(cc, &instance.def);
+    cc.generic_args.clear();
+    if cc.errors > errors {
+        log_cerror!(
+            instance.loc,
+            "Generic function can not be instantiated as ({})",
+            instance.def.decl.ident
+        );
+    }
+}
+
+/// Infer type arguments of a generic function from the type of call arguments
+fn infer_type_args(
+    cc: &mut CompilerContext,
+    block: &Block,
+    decl: &FunctionDecl,
+    args: &[Expr],
+) -> Result<Vec<VariableType>, CompilationError> {
+    if decl.args.len() != args.len() {
+        return Err(CompilationError::Err(format!(
+            "Function ({}) expects {} arguments but {} were given",
+            decl.ident,
+            decl.args.len(),
+            args.len()
+        )));
+    }
+    let types = args
+        .iter()
+        .map(|arg| expr_type(cc, block, arg).map(|t| t.named()))
+        .collect::<Result<Vec<_>, _>>();
+    let mut bindings = BTreeMap::new();
+    for (farg, vtype) in decl.args.iter().zip(types?) {
+        unify(
+            cc,
+            &decl.type_params,
+            &farg.typedef,
+            &vtype,
+            true,
+            &mut bindings,
+        )?;
+    }
+    decl.type_params
+        .iter()
+        .map(|p| match bindings.remove(p) {
+            Some((t, _)) => Ok(t),
+            None => Err(CompilationError::UninferredTypeParam(
+                p.to_owned(),
+                decl.ident.clone(),
+            )),
+        })
+        .collect()
+}
+
+/// Bind type parameters that appear in the parameter type to parts of the argument type
+/// Integers passed directly as a type parameter can be widened,
+/// type arguments found inside other types have to match exactly
+fn unify(
+    cc: &CompilerContext,
+    params: &[String],
+    param: &VariableType,
+    arg: &VariableType,
+    direct: bool,
+    bindings: &mut BTreeMap<String, (VariableType, bool)>,
+) -> Result<(), CompilationError> {
+    match (param, arg) {
+        (_, VariableType::Any) => Ok(()),
+        (VariableType::Custom(p), _) if params.contains(p) => {
+            let loose = direct && arg.is_integer();
+            let binding = match bindings.get(p) {
+                None => (arg.clone(), loose),
+                Some((t, _)) if t == arg => return Ok(()),
+                Some((t, true)) if loose => (t.promote(arg)?, true),
+                Some((t, true)) if t.is_integer() && arg.is_integer() => (arg.clone(), false),
+                Some((t, false)) if loose && t.is_integer() => return Ok(()),
+                Some((t, _)) => {
+                    return Err(CompilationError::ConflictingTypeArgument(
+                        p.to_owned(),
+                        t.to_string(),
+                        arg.to_string(),
+                    ))
+                }
+            };
+            bindings.insert(p.to_owned(), binding);
+            Ok(())
+        }
+        (VariableType::Generic(s, pargs), VariableType::Custom(ident)) => {
+            match cc.generic_instances.get(ident) {
+                Some((base, args)) if base == s && args.len() == pargs.len() => {
+                    for (p, a) in pargs.iter().zip(args.iter()) {
+                        unify(cc, params, p, a, false, bindings)?;
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+        (VariableType::TypedPointer(p), VariableType::TypedPointer(a))
+        | (VariableType::Array(p, _), VariableType::Array(a, _))
+        | (VariableType::Slice(p), VariableType::Slice(a))
+        | (VariableType::Slice(p), VariableType::Array(a, _)) => {
+            unify(cc, params, p, a, false, bindings)
+        }
+        (VariableType::Tuple(ps), VariableType::Tuple(ts)) if ps.len() == ts.len() => {
+            for (p, a) in ps.iter().zip(ts.iter()) {
+                unify(cc, params, p, a, false, bindings)?;
+            }
+            Ok(())
+        }
+        (VariableType::Function(pf), VariableType::Function(af))
+            if pf.args.len() == af.args.len() =>
+        {
+            for (p, a) in pf.args.iter().zip(af.args.iter()) {
+                unify(cc, params, p, a, false, bindings)?;
+            }
+            unify(cc, params, &pf.ret_type, &af.ret_type, false, bindings)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Type name that can be used in labels
+fn mangle_type(vtype: &VariableType) -> String {
+    vtype
+        .to_string()
+        .trim_start_matches('@')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,270 @@
+/**********************************************************************************************
+*
+*   compiler/literals: storing array and struct literals
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use crate::{
+    assembler::{
+        instructions::Opr,
+        memory::MemAddr,
+        mnemonic::Mnemonic::*,
+        register::Reg::{self, *},
+        utils::copy_memory,
+    },
+    error_handeling::{CompilationError, Loc},
+    optim::{check_range, wide_value},
+    parser::{
+        block::Block,
+        constants::{eval_const, ConstValue},
+        expr::{Expr, ExprType, StructLiteral},
+        types::{StructType, VariableType},
+    },
+};
+
+use super::{
+    resolve_type,
+    tuples::{store_item, value_type},
+    CompilerContext,
+};
+
+/// Runs of the same 8 bytes longer than this are stored in a loop
+const FILL_LOOP_MIN: usize = 16;
+
+pub fn is_literal(value: &Expr) -> bool {
+    matches!(
+        value.etype,
+        ExprType::ArrayLit(_) | ExprType::ArrayRepeat(..) | ExprType::StructLit(_)
+    )
+}
+
+/// Type of an array or struct literal,
+/// item type of arrays is the type that all of their items are promoted to
+pub fn literal_type(
+    cc: &mut CompilerContext,
+    block: &Block,
+    value: &Expr,
+) -> Result<VariableType, CompilationError> {
+    match &value.etype {
+        ExprType::ArrayLit(items) => {
+            let mut item_type = value_type(cc, block, &items[0])?;
+            for item in items.iter().skip(1) {
+                item_type = item_type.promote(&value_type(cc, block, item)?)?;
+            }
+            Ok(VariableType::Array(Box::new(item_type), items.len()))
+        }
+        ExprType::ArrayRepeat(item, count) => Ok(VariableType::Array(
+            Box::new(value_type(cc, block, item)?),
+            *count,
+        )),
+        ExprType::StructLit(sl) => resolve_type(cc, &VariableType::Custom(sl.ident.clone())),
+        _ => unreachable!(),
+    }
+}
+
+/// Stores an array or struct literal in stack memory
+/// Literals made of constants are stored as immediate values
+pub fn store_literal(
+    cc: &mut CompilerContext,
+    block: &Block,
+    dest: &MemAddr,
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<(), CompilationError> {
+    if let Some(data) = literal_data(vtype, value)? {
+        store_bytes(cc, block, &value.loc, dest, &data);
+        return Ok(());
+    }
+    match (&value.etype, vtype) {
+        (ExprType::ArrayLit(items), VariableType::Array(t, n)) if items.len() == *n => {
+            for (i, e) in items.iter().enumerate() {
+                let mem = dest.with_offset((i * t.stride()) as i32, t.mem_size());
+                store_item(cc, block, &mem, t, e)?;
+            }
+            Ok(())
+        }
+        (ExprType::ArrayRepeat(item, count), VariableType::Array(t, n)) if count == n => {
+            // The value is computed once and copied to the other items
+            store_item(cc, block, &dest.with_offset(0, t.mem_size()), t, item)?;
+            for i in 1..*n {
+                let mem = dest.with_offset((i * t.stride()) as i32, t.mem_size());
+                copy_memory(cc, mem, dest.clone(), t.size());
+            }
+            Ok(())
+        }
+        (ExprType::StructLit(sl), VariableType::Struct(st)) if sl.ident == st.base_ident() => {
+            check_struct_items(st, sl)?;
+            for item in st.items.iter() {
+                let (_, e) = sl.items.iter().find(|(i, _)| *i == item.ident).unwrap();
+                let mem = dest.with_offset(item.offset, item.vtype.mem_size());
+                store_item(cc, block, &mem, &item.vtype, e)?;
+            }
+            Ok(())
+        }
+        _ => Err(CompilationError::UnmatchingTypes(
+            vtype.to_string(),
+            literal_type(cc, block, value)?.to_string(),
+        )),
+    }
+}
+
+/// Every item of the struct should be given exactly once
+fn check_struct_items(st: &StructType, sl: &StructLiteral) -> Result<(), CompilationError> {
+    if let Some((ident, _)) = sl.items.iter().find(|(i, _)| st.get(i).is_none()) {
+        return Err(CompilationError::UndefinedStructItem(
+            sl.ident.clone(),
+            ident.clone(),
+        ));
+    }
+    if let Some(item) = st
+        .items
+        .iter()
+        .find(|item| !sl.items.iter().any(|(i, _)| *i == item.ident))
+    {
+        return Err(CompilationError::MissingStructItem(
+            sl.ident.clone(),
+            item.ident.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Bytes of a literal made of constants
+/// Returns None if any item is computed at runtime
+pub fn literal_data(
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<Option<Vec<u8>>, CompilationError> {
+    let mut data = vec![0; vtype.size()];
+    Ok(write_constant(&mut data, 0, vtype, value)?.then_some(data))
+}
+
+/// Writes a constant item to its offset in data, returns false if the item is not constant
+/// Constant integers that do not fit their item type are errors
+fn write_constant(
+    data: &mut [u8],
+    offset: usize,
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<bool, CompilationError> {
+    match (&value.etype, vtype) {
+        (ExprType::ArrayLit(items), VariableType::Array(t, n)) if items.len() == *n => {
+            for (i, e) in items.iter().enumerate() {
+                if !write_constant(data, offset + i * t.stride(), t, e)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (ExprType::ArrayRepeat(item, count), VariableType::Array(t, n)) if count == n => {
+            for i in 0..*n {
+                if !write_constant(data, offset + i * t.stride(), t, item)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (ExprType::StructLit(sl), VariableType::Struct(st)) if sl.ident == st.base_ident() => {
+            if check_struct_items(st, sl).is_err() {
+                return Ok(false);
+            }
+            for item in st.items.iter() {
+                let (_, e) = sl.items.iter().find(|(i, _)| *i == item.ident).unwrap();
+                if !write_constant(data, offset + item.offset as usize, &item.vtype, e)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (ExprType::Tuple(items), VariableType::Struct(st)) if items.len() == st.items.len() => {
+            for (item, e) in st.items.iter().zip(items.iter()) {
+                if !write_constant(data, offset + item.offset as usize, &item.vtype, e)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (ExprType::Float(f), VariableType::Float) => {
+            data[offset..offset + 8].copy_from_slice(&f.to_le_bytes());
+            Ok(true)
+        }
+        (_, t) if t.is_integer() || *t == VariableType::Bool => {
+            let Ok(ConstValue::Num(v, t)) = eval_const(value) else {
+                return Ok(false);
+            };
+            if (t == VariableType::Bool) != (*vtype == VariableType::Bool)
+                || vtype.check_distinct(&t).is_err()
+            {
+                return Ok(false);
+            }
+            let v = check_range(wide_value(v, &t), vtype)?;
+            let size = vtype.size();
+            data[offset..offset + size].copy_from_slice(&v.to_le_bytes()[..size]);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Stores constant bytes through RAX using the largest possible moves
+fn store_bytes(cc: &mut CompilerContext, block: &Block, loc: &Loc, dest: &MemAddr, data: &[u8]) {
+    let mut loaded: Option<u64> = None;
+    let mut offset = 0;
+    while offset < data.len() {
+        let chunk = match data.len() - offset {
+            8.. => 8,
+            4..=7 => 4,
+            2..=3 => 2,
+            _ => 1,
+        };
+        let mut bytes = [0u8; 8];
+        bytes[..chunk].copy_from_slice(&data[offset..offset + chunk]);
+        let value = u64::from_le_bytes(bytes);
+        let mask = u64::MAX >> (64 - 8 * chunk);
+        if loaded.is_none_or(|v| v & mask != value) {
+            cc.codegen.instr2(Mov, RAX, value as i64);
+            loaded = Some(value);
+        }
+        let run = data[offset..]
+            .chunks_exact(8)
+            .take_while(|c| *c == &bytes[..])
+            .count();
+        if chunk == 8 && run > FILL_LOOP_MIN {
+            let label = block.name_with_prefix(&format!("FILL{}_{}_{offset}", loc.line, loc.col));
+            cc.codegen
+                .instr2(Lea, RDI, dest.with_offset(offset as i32, 8));
+            cc.codegen.instr2(Mov, RCX, run as i32);
+            cc.codegen.set_lable(label.clone());
+            cc.codegen.instr2(Mov, MemAddr::new_s(8, RDI), RAX);
+            cc.codegen.instr2(Add, RDI, 8);
+            cc.codegen.instr2(Sub, RCX, 1);
+            cc.codegen.instr1(Jne, Opr::Loc(label));
+            offset += run * 8;
+            continue;
+        }
+        cc.codegen.instr2(
+            Mov,
+            dest.with_offset(offset as i32, chunk as u8),
+            Reg::convert(&RAX, chunk as u8),
+        );
+        offset += chunk;
+    }
+}
@@ -24,32 +24,51 @@
 **********************************************************************************************/
 pub mod bif;
 mod block;
+mod conditions;
 mod expr;
 mod function;
+mod generics;
+mod literals;
+mod results;
 mod stmts;
+mod tuples;
 mod variables;
 
 use crate::assembler::instructions::Opr;
+use crate::assembler::memory::MemAddr;
 use crate::assembler::mnemonic::Mnemonic;
 use crate::assembler::{register::Reg, Codegen};
-use crate::compiler::{bif::Bif, function::compile_function};
+use crate::compiler::{bif::Bif, block::DeferScope, function::compile_function};
+use crate::error_handeling::CompilationError;
 use crate::parser::block::Block;
-use crate::parser::function::FunctionDecl;
+use crate::parser::function::{FunctionDecl, FunctionDef};
 use crate::parser::parse_source_file;
 use crate::parser::program::{ProgramFile, ProgramItem};
-use crate::parser::types::StructType;
 use crate::parser::types::VariableType;
-use crate::{log_error, CompilerOptions};
+use crate::parser::types::{EnumType, StructType};
+use crate::{log_cerror, log_error, CompilerOptions};
 use std::collections::{BTreeMap, HashSet};
 use std::process::exit;
 
+use self::generics::{compile_function_instance, concrete_type, FunctionInstance};
+use self::results::builtin_enums;
+use self::tuples::returns_by_value;
 use self::variables::{insert_variable, NameSpaceMapping, VariableMapBase};
 
 /// Name Space Typing
 pub enum NSType {
     Function(FunctionDecl),
     Struct(StructType),
+    Enum(EnumType),
     Ffi(FunctionDecl, String),
+    /// Generic functions are compiled for each instance
+    GenericFunction(FunctionDef),
+    /// Generic structs are laid out for each instance
+    GenericStruct(StructType),
+    /// Generic enums are laid out for each instance
+    GenericEnum(EnumType),
+    /// Resolved type of a type alias or a distinct type
+    Alias(VariableType),
 }
 
 pub struct CompilerContext {
@@ -60,6 +79,17 @@ pub struct CompilerContext {
     pub bif_set: HashSet<Bif>,
     pub mem_offset: usize,
     pub program_file: String,
+    /// Type arguments of the generic item that is being compiled
+    pub generic_args: BTreeMap<String, VariableType>,
+    /// Generic struct instances and their type arguments
+    pub generic_instances: BTreeMap<String, (String, Vec<VariableType>)>,
+    pub instance_queue: Vec<FunctionInstance>,
+    /// Memory that the next call returning a large tuple writes to
+    pub tuple_dest: Option<MemAddr>,
+    /// Blocks that are being compiled and their defers
+    pub defer_scopes: Vec<DeferScope>,
+    /// Number of compiled defers, used for unique block ids
+    pub defer_count: usize,
     errors: usize,
 }
 
@@ -73,6 +103,12 @@ impl CompilerContext {
             variables_map: NameSpaceMapping::new(),
             namespace_map: BTreeMap::new(),
             mem_offset: 0,
+            generic_args: BTreeMap::new(),
+            generic_instances: BTreeMap::new(),
+            instance_queue: Vec::new(),
+            tuple_dest: None,
+            defer_scopes: Vec::new(),
+            defer_count: 0,
             errors: 0,
         }
     }
@@ -114,6 +150,20 @@ pub fn function_args_register(arg_numer: usize) -> Reg {
     }
 }
 
+pub fn function_float_args_register(arg_numer: usize) -> Reg {
+    match arg_numer {
+        0 => Reg::XMM0,
+        1 => Reg::XMM1,
+        2 => Reg::XMM2,
+        3 => Reg::XMM3,
+        4 => Reg::XMM4,
+        5 => Reg::XMM5,
+        6 => Reg::XMM6,
+        7 => Reg::XMM7,
+        _ => unreachable!(),
+    }
+}
+
 fn _frame_size(mem_offset: usize) -> usize {
     2 << mem_offset.ilog2() as usize
 }
@@ -122,42 +172,335 @@ pub fn compile(cc: &mut CompilerContext, path: String) {
     let program = parse_source_file(path.clone());
     compile_init_function(cc, &program);
     for item in program.items.iter() {
-        if let ProgramItem::Func(f) = item {
-            compile_function(cc, f);
+        match item {
+            ProgramItem::Func(f) if f.decl.type_params.is_empty() => compile_function(cc, f),
+            _ => (),
         }
     }
+    // Instances may call other generic functions
+    while let Some(instance) = cc.instance_queue.pop() {
+        compile_function_instance(cc, instance);
+    }
     if cc.errors > 0 {
         log_error!("Compilation Failed due to {} previous errors!", cc.errors);
         exit(-1);
     }
 }
 
+/// Resolve user defined item types and compute the layout of a struct or enum
+fn resolve_user_type(
+    cc: &mut CompilerContext,
+    items: &BTreeMap<String, ProgramItem>,
+    ident: &str,
+    visiting: &mut Vec<String>,
+) -> Result<VariableType, CompilationError> {
+    match cc.namespace_map.get(ident) {
+        Some(NSType::Struct(st)) => return Ok(VariableType::Struct(st.clone())),
+        Some(NSType::Enum(et)) => return Ok(VariableType::Enum(et.clone())),
+        Some(NSType::Alias(t)) => return Ok(t.clone()),
+        _ => (),
+    }
+    if visiting.iter().any(|x| x == ident) {
+        return Err(CompilationError::RecursiveType(ident.to_owned()));
+    }
+    visiting.push(ident.to_owned());
+    if let Some((base, args)) = cc.generic_instances.get(ident).cloned() {
+        let res = instantiate_struct(cc, items, ident, &base, &args, visiting)?;
+        visiting.pop();
+        return Ok(res);
+    }
+    let res = match items.get(ident) {
+        Some(ProgramItem::Struct(st)) => {
+            let mut st = st.clone();
+            for item in st.items.iter_mut() {
+                item.vtype = resolve_item_type(cc, items, &item.vtype, visiting)?;
+            }
+            st.compute_layout();
+            cc.namespace_map
+                .insert(st.ident.clone(), NSType::Struct(st.clone()));
+            VariableType::Struct(st)
+        }
+        Some(ProgramItem::Enum(et)) => {
+            let mut et = et.clone();
+            for variant in et.variants.iter_mut() {
+                for item in variant.payload.items.iter_mut() {
+                    item.vtype = resolve_item_type(cc, items, &item.vtype, visiting)?;
+                }
+            }
+            et.compute_layout();
+            cc.namespace_map
+                .insert(et.ident.clone(), NSType::Enum(et.clone()));
+            VariableType::Enum(et)
+        }
+        Some(ProgramItem::TypeAlias(ta)) => {
+            let mut vtype = resolve_item_type(cc, items, &ta.vtype, visiting)?;
+            if ta.distinct {
+                if !vtype.is_integer() || matches!(vtype, VariableType::Distinct(..)) {
+                    return Err(CompilationError::InvalidDistinctType(
+                        ta.ident.clone(),
+                        vtype.to_string(),
+                    ));
+                }
+                vtype =
+                    VariableType::Distinct(ta.ident.clone(), Box::new(vtype.underlying().clone()));
+            } else if vtype.is_integer() && !matches!(vtype, VariableType::Distinct(..)) {
+                // Integer aliases keep their name for error messages
+                vtype = VariableType::Alias(ta.ident.clone(), Box::new(vtype.underlying().clone()));
+            }
+            cc.namespace_map
+                .insert(ta.ident.clone(), NSType::Alias(vtype.clone()));
+            vtype
+        }
+        _ => return Err(CompilationError::UnknownType(ident.to_owned())),
+    };
+    visiting.pop();
+    Ok(res)
+}
+
+/// Layout of a generic struct with the given type arguments
+fn instantiate_struct(
+    cc: &mut CompilerContext,
+    items: &BTreeMap<String, ProgramItem>,
+    ident: &str,
+    base: &str,
+    args: &[VariableType],
+    visiting: &mut Vec<String>,
+) -> Result<VariableType, CompilationError> {
+    let mut st = match cc.namespace_map.get(base) {
+        Some(NSType::GenericStruct(st)) => st.clone(),
+        Some(NSType::GenericEnum(et)) => {
+            let et = et.clone();
+            return instantiate_enum(cc, items, ident, et, args, visiting);
+        }
+        _ => return Err(CompilationError::UnknownType(base.to_owned())),
+    };
+    let type_args = st.type_params.drain(..).zip(args.iter().cloned()).collect();
+    let outer = std::mem::replace(&mut cc.generic_args, type_args);
+    let item_types = st
+        .items
+        .iter()
+        .map(|item| concrete_type(cc, &item.vtype))
+        .collect::<Result<Vec<_>, _>>();
+    cc.generic_args = outer;
+    for (item, vtype) in st.items.iter_mut().zip(item_types?) {
+        item.vtype = resolve_item_type(cc, items, &vtype, visiting)?;
+    }
+    st.ident = ident.to_owned();
+    st.compute_layout();
+    cc.namespace_map
+        .insert(st.ident.clone(), NSType::Struct(st.clone()));
+    Ok(VariableType::Struct(st))
+}
+
+/// Layout of a generic enum with the given type arguments
+fn instantiate_enum(
+    cc: &mut CompilerContext,
+    items: &BTreeMap<String, ProgramItem>,
+    ident: &str,
+    mut et: EnumType,
+    args: &[VariableType],
+    visiting: &mut Vec<String>,
+) -> Result<VariableType, CompilationError> {
+    let type_args = et.type_params.drain(..).zip(args.iter().cloned()).collect();
+    let outer = std::mem::replace(&mut cc.generic_args, type_args);
+    let payloads = et
+        .variants
+        .iter()
+        .map(|v| {
+            v.payload
+                .items
+                .iter()
+                .map(|item| concrete_type(cc, &item.vtype))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>();
+    cc.generic_args = outer;
+    for (variant, vtypes) in et.variants.iter_mut().zip(payloads?) {
+        for (item, vtype) in variant.payload.items.iter_mut().zip(vtypes) {
+            item.vtype = resolve_item_type(cc, items, &vtype, visiting)?;
+        }
+    }
+    et.ident = ident.to_owned();
+    et.compute_layout();
+    cc.namespace_map
+        .insert(et.ident.clone(), NSType::Enum(et.clone()));
+    Ok(VariableType::Enum(et))
+}
+
+fn resolve_item_type(
+    cc: &mut CompilerContext,
+    items: &BTreeMap<String, ProgramItem>,
+    vtype: &VariableType,
+    visiting: &mut Vec<String>,
+) -> Result<VariableType, CompilationError> {
+    let vtype = &concrete_type(cc, vtype)?;
+    match vtype {
+        VariableType::Custom(s) => resolve_user_type(cc, items, s, visiting),
+        VariableType::Array(t, size) => Ok(VariableType::Array(
+            Box::new(resolve_item_type(cc, items, t, visiting)?),
+            *size,
+        )),
+        VariableType::Slice(t) => Ok(VariableType::Slice(Box::new(resolve_item_type(
+            cc, items, t, visiting,
+        )?))),
+        VariableType::Tuple(ts) => {
+            let ts = ts
+                .iter()
+                .map(|t| resolve_item_type(cc, items, t, visiting))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(VariableType::Struct(StructType::tuple(ts)))
+        }
+        // Pointee types are resolved when the pointer is dereferenced
+        VariableType::TypedPointer(t) => match pointee_ident(t) {
+            Some(s)
+                if !items.contains_key(s)
+                    && !cc.namespace_map.contains_key(s)
+                    && !cc.generic_instances.contains_key(s) =>
+            {
+                Err(CompilationError::UnknownType(s.to_owned()))
+            }
+            _ => Ok(vtype.clone()),
+        },
+        _ => Ok(vtype.clone()),
+    }
+}
+
+/// Name of the user defined type a pointer refers to
+fn pointee_ident(vtype: &VariableType) -> Option<&str> {
+    match vtype {
+        VariableType::Custom(s) => Some(s),
+        VariableType::Array(t, _) | VariableType::Slice(t) | VariableType::TypedPointer(t) => {
+            pointee_ident(t)
+        }
+        _ => None,
+    }
+}
+
+/// Resolve user defined types to structs and enums
+pub fn resolve_type(
+    cc: &mut CompilerContext,
+    vtype: &VariableType,
+) -> Result<VariableType, CompilationError> {
+    let vtype = &concrete_type(cc, vtype)?;
+    match vtype {
+        // Generic struct instances are laid out the first time they are used
+        VariableType::Custom(s) => resolve_user_type(cc, &BTreeMap::new(), s, &mut Vec::new()),
+        VariableType::Array(t, size) => {
+            Ok(VariableType::Array(Box::new(resolve_type(cc, t)?), *size))
+        }
+        VariableType::Slice(t) => Ok(VariableType::Slice(Box::new(resolve_type(cc, t)?))),
+        VariableType::Tuple(ts) => {
+            let ts = ts
+                .iter()
+                .map(|t| resolve_type(cc, t))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(VariableType::Struct(StructType::tuple(ts)))
+        }
+        VariableType::TypedPointer(t) => {
+            resolve_type(cc, t)?;
+            Ok(vtype.clone())
+        }
+        _ => Ok(vtype.clone()),
+    }
+}
+
+/// Type that an alias or a distinct type names
+/// Structs and enums are kept by name like in any other signature
+fn alias_target(cc: &CompilerContext, vtype: &VariableType) -> Option<VariableType> {
+    match vtype {
+        VariableType::Custom(s) => match cc.namespace_map.get(s) {
+            Some(NSType::Alias(t)) => Some(t.named()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Aliases in function signatures are replaced with the type they name,
+/// plain enums are passed as integers,
+/// slices as an address and a length and tuples or enums are returned by value
+fn resolve_function_decl(cc: &mut CompilerContext, decl: &FunctionDecl) -> FunctionDecl {
+    let mut resolve = |vtype: &VariableType, is_ret: bool| {
+        let vtype = concrete_type(cc, vtype).unwrap_or_else(|_| vtype.clone());
+        let vtype = alias_target(cc, &vtype).unwrap_or(vtype);
+        match resolve_type(cc, &vtype) {
+            Ok(VariableType::Enum(et)) if et.is_plain() => VariableType::Enum(et),
+            Ok(VariableType::Slice(t)) => VariableType::Slice(t),
+            Ok(t) if is_ret && returns_by_value(&t) => t,
+            _ => vtype,
+        }
+    };
+    let mut decl = decl.clone();
+    for arg in decl.args.iter_mut() {
+        arg.typedef = resolve(&arg.typedef, false);
+    }
+    decl.ret_type = resolve(&decl.ret_type, true);
+    decl
+}
+
+fn collect_user_types(cc: &mut CompilerContext, program: &ProgramFile) {
+    let mut items = BTreeMap::<String, ProgramItem>::new();
+    // User defined types may shadow the built-in ones
+    for et in builtin_enums() {
+        cc.namespace_map
+            .insert(et.ident.clone(), NSType::GenericEnum(et));
+    }
+    for item in program.items.iter() {
+        match item {
+            ProgramItem::Struct(st) if !st.type_params.is_empty() => {
+                cc.namespace_map
+                    .insert(st.ident.clone(), NSType::GenericStruct(st.clone()));
+            }
+            ProgramItem::Enum(et) if !et.type_params.is_empty() => {
+                cc.namespace_map
+                    .insert(et.ident.clone(), NSType::GenericEnum(et.clone()));
+            }
+            ProgramItem::Struct(_) | ProgramItem::Enum(_) | ProgramItem::TypeAlias(_) => {
+                items.insert(item.get_key(), item.clone());
+            }
+            _ => (),
+        }
+    }
+    for ident in items.keys() {
+        if let Err(e) = resolve_user_type(cc, &items, ident, &mut Vec::new()) {
+            log_error!("Type ({ident}): {e}");
+            cc.error();
+        }
+    }
+}
+
 fn collect_types(cc: &mut CompilerContext, program: &ProgramFile) {
+    collect_user_types(cc, program);
     let global_block = Block::new_global("#".to_string(), crate::parser::block::BlockType::Global);
     for item in program.items.iter() {
         match item {
+            ProgramItem::Func(f) if !f.decl.type_params.is_empty() => {
+                cc.namespace_map
+                    .insert(f.decl.ident.clone(), NSType::GenericFunction(f.clone()));
+            }
             ProgramItem::Func(f) => {
+                let decl = resolve_function_decl(cc, &f.decl);
                 cc.namespace_map
-                    .insert(f.decl.ident.clone(), NSType::Function(f.decl.clone()));
+                    .insert(f.decl.ident.clone(), NSType::Function(decl));
             }
             ProgramItem::FFI(ff, f) => {
                 //cc.codegen.ffi_map.insert(f.ident.clone(), ff.clone());
+                let decl = resolve_function_decl(cc, f);
                 cc.namespace_map
-                    .insert(f.ident.clone(), NSType::Ffi(f.clone(), ff.clone()));
+                    .insert(f.ident.clone(), NSType::Ffi(decl, ff.clone()));
                 //cc.functions_map.insert(f.ident.clone(), f.clone());
             }
-            ProgramItem::Struct(s) => {
-                //cc.structs_map.insert(s.ident.clone(), s.clone());
-                cc.namespace_map
-                    .insert(s.ident.clone(), NSType::Struct(s.clone()));
-            }
+            // Constants are replaced with their values while parsing
+            ProgramItem::Struct(_)
+            | ProgramItem::Enum(_)
+            | ProgramItem::TypeAlias(_)
+            | ProgramItem::Const(..) => (),
             ProgramItem::StaticVar(sv) => {
-                let _ = insert_variable(
-                    cc,
-                    &global_block,
-                    sv,
-                    VariableMapBase::Global(sv.ident.clone()),
-                );
+                let base = VariableMapBase::Global(sv.ident.clone());
+                if let Err(e) = insert_variable(cc, &global_block, sv, base) {
+                    cc.error();
+                    log_cerror!(sv.loc, "{e}");
+                }
             }
         }
     }
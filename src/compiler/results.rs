@@ -0,0 +1,227 @@
+/**********************************************************************************************
+*
+*   compiler/results: built-in Result and Option enums, the ? operator and unwrap
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use crate::{
+    assembler::{
+        instructions::Opr,
+        memory::MemAddr,
+        mnemonic::Mnemonic::*,
+        register::Reg::*,
+        utils::{copy_memory, displaceable_mem, mov_unknown_to_register},
+    },
+    error_handeling::{CompilationError, Loc},
+    lexer::Lexer,
+    optim::ExprOpr,
+    parser::{
+        block::Block,
+        expr::{Expr, FunctionCall},
+        program::{generate_ast, ProgramItem},
+        types::{EnumType, VariableType},
+    },
+};
+
+use super::{
+    bif::panic_message,
+    block::compile_return_defers,
+    expr::compile_expr,
+    tuples::{load_return_registers, return_memory, returns_in_memory},
+    CompilerContext, NSType,
+};
+
+/// Built-in generic enums, the first variant holds the value
+const BUILTIN_ENUMS: &str = "
+enum Result<T, E> { Ok(value @T), Err(error @E) }
+enum Option<T> { Some(value @T), None }
+";
+
+pub fn builtin_enums() -> Vec<EnumType> {
+    let mut lexer = Lexer::new("builtin".to_string(), BUILTIN_ENUMS.to_string());
+    generate_ast(&mut lexer)
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            ProgramItem::Enum(et) => Some(et),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Instances of the built-in Result and Option enums
+fn result_enum(vtype: &VariableType) -> Option<&EnumType> {
+    match vtype {
+        VariableType::Enum(et)
+            if matches!(et.base_ident(), "Result" | "Option")
+                && et.base_ident() != et.ident
+                && matches!(et.variants[0].ident.as_str(), "Ok" | "Some") =>
+        {
+            Some(et)
+        }
+        _ => None,
+    }
+}
+
+/// Loads the address of a Result or an Option to RSI
+/// Values returned in registers are pushed to the stack first
+fn result_operand(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+) -> Result<(EnumType, bool), CompilationError> {
+    let eo = compile_expr(cc, block, expr)?;
+    let Some(et) = result_enum(&eo.vtype).cloned() else {
+        return Err(CompilationError::Err(format!(
+            "Expected a Result or an Option, found type ({})",
+            eo.vtype
+        )));
+    };
+    match eo.value {
+        Opr::Mem(mem) => {
+            let mem = displaceable_mem(cc, mem, RSI);
+            cc.codegen.instr2(Lea, RSI, mem.with_offset(0, 8));
+            Ok((et, false))
+        }
+        _ => {
+            cc.codegen.instr1(Push, RDX);
+            cc.codegen.instr1(Push, RAX);
+            cc.codegen.instr2(Mov, RSI, RSP);
+            Ok((et, true))
+        }
+    }
+}
+
+/// Value of the first variant of the Result or Option in RSI
+fn result_value(
+    cc: &mut CompilerContext,
+    et: &EnumType,
+    pushed: bool,
+) -> Result<ExprOpr, CompilationError> {
+    let item = et.variants[0].payload.items[0].clone();
+    let offset = et.payload_offset() as i32 + item.offset;
+    if item.vtype.is_aggregate() || matches!(item.vtype, VariableType::Array(..)) {
+        if pushed {
+            return Err(CompilationError::Err(format!(
+                "Values of type ({}) can only be taken from variables",
+                item.vtype
+            )));
+        }
+        return Ok(ExprOpr::new(MemAddr::new_disp(RSI, offset), item.vtype));
+    }
+    let mem = MemAddr::new_disp_s(item.vtype.mem_size(), RSI, offset);
+    mov_unknown_to_register(cc, RAX, mem.into());
+    if pushed {
+        cc.codegen.instr2(Add, RSP, 16);
+    }
+    Ok(ExprOpr::new(RAX, item.vtype))
+}
+
+/// Compiles expr? by returning the error or None from the function early
+/// Defers of the function still run
+pub fn compile_try(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+    loc: &Loc,
+) -> Result<ExprOpr, CompilationError> {
+    let ret_type = match cc.namespace_map.get(&block.master_start_name()) {
+        Some(NSType::Function(f)) => f.ret_type.clone(),
+        _ => VariableType::Void,
+    };
+    let (et, pushed) = result_operand(cc, block, expr)?;
+    let err_variant = et.variants.get(1).unwrap();
+    let ret_et = match result_enum(&ret_type) {
+        Some(ret_et) if ret_et.base_ident() == et.base_ident() => ret_et.clone(),
+        _ => {
+            return Err(CompilationError::Err(format!(
+                "The ? operator can not return ({}::{}) from a function returning ({})",
+                et.base_ident(),
+                err_variant.ident,
+                ret_type
+            )))
+        }
+    };
+    let ret_variant = ret_et.variants.get(1).unwrap();
+    if err_variant.payload != ret_variant.payload {
+        return Err(CompilationError::UnmatchingTypes(
+            ret_type.to_string(),
+            VariableType::Enum(et).to_string(),
+        ));
+    }
+    let ok_label = block.name_with_prefix(&format!("TRY{}_{}", loc.line, loc.col));
+    cc.codegen.instr2(Cmp, MemAddr::new_s(4, RSI), 0);
+    cc.codegen.instr1(Jz, Opr::Loc(ok_label.clone()));
+    // Error and None are built where the function returns its value
+    let dest = match returns_in_memory(&ret_type) {
+        true => return_memory(cc, block)?,
+        false => {
+            cc.codegen.instr2(Sub, RSP, 16);
+            cc.codegen.instr2(Mov, RCX, RSP);
+            MemAddr::new(RCX)
+        }
+    };
+    if let Some(item) = err_variant.payload.items.first() {
+        copy_memory(
+            cc,
+            dest.with_offset(ret_et.payload_offset() as i32, 0),
+            MemAddr::new_disp(RSI, et.payload_offset() as i32),
+            item.vtype.size(),
+        );
+    }
+    // Err and None are the second variant
+    cc.codegen.instr2(Mov, dest.with_offset(0, 4), 1);
+    match returns_in_memory(&ret_type) {
+        true => cc.codegen.instr2(Mov, RAX, RCX),
+        false => load_return_registers(cc, &dest, ret_type.size()),
+    }
+    compile_return_defers(cc)?;
+    cc.codegen.instr1(Jmp, Opr::Loc(block.master_end_name()));
+    cc.codegen.set_lable(ok_label);
+    result_value(cc, &et, pushed)
+}
+
+/// Value of a Result or an Option
+/// Aborts the program with the location of the call if there is no value
+pub fn compile_unwrap(
+    cc: &mut CompilerContext,
+    block: &Block,
+    fc: &FunctionCall,
+    loc: &Loc,
+) -> Result<ExprOpr, CompilationError> {
+    if fc.args.len() != 1 {
+        return Err(CompilationError::Err(format!(
+            "Function (unwrap) expects 1 argument but {} were given",
+            fc.args.len()
+        )));
+    }
+    let (et, pushed) = result_operand(cc, block, &fc.args[0])?;
+    let msg = format!(
+        "{loc}: Called unwrap on {}::{}\n",
+        et.base_ident(),
+        et.variants[1].ident
+    );
+    let msg = panic_message(cc, &msg);
+    cc.codegen.instr2(Mov, RDI, msg);
+    cc.codegen.instr2(Cmp, MemAddr::new_s(4, RSI), 0);
+    cc.codegen.instr1(Jne, Opr::rel("runtime_panic"));
+    result_value(cc, &et, pushed)
+}
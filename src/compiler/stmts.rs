@@ -29,25 +29,42 @@ use crate::{
         memory::MemAddr,
         mnemonic::Mnemonic::*,
         register::Reg::*,
-        utils::{mov_unknown_to_register, restore_last_temp_value, save_temp_value},
+        utils::{
+            copy_memory, displaceable_mem, extend_integer, mov_float_to_register,
+            mov_unknown_to_register, mov_unknown_to_xmm,
+        },
     },
     error_handeling::CompilationError,
-    log_cerror, log_warn, mem,
+    log_cerror, log_warn,
+    optim::ExprOpr,
     parser::{
         assign::{Assign, AssignOp},
         block::Block,
-        expr::{CompareExpr, CompareOp, Expr, ExprType},
-        stmt::{ElseBlock, ForLoop, IFStmt, Stmt, StmtType, WhileStmt},
+        constants::{eval_const, ConstValue},
+        expr::{ArrayIndex, CompareExpr, CompareOp, Expr, ExprType, FunctionCall},
+        stmt::{
+            ElseBlock, ForLoop, ForRange, IFStmt, MatchArm, MatchPattern, MatchStmt, Stmt,
+            StmtType, WhileStmt,
+        },
         types::VariableType,
+        variable_decl::VariableDeclare,
     },
 };
 
 use super::{
     bif::Bif,
-    block::compile_block,
-    expr::{compile_compare_expr, compile_expr},
-    variables::insert_variable,
-    CompilerContext, VariableMapBase,
+    block::{compile_block, compile_loop_defers, compile_return_defers, insert_defer},
+    conditions::compile_condition_jump,
+    expr::{
+        compile_division, compile_enum_into, compile_expr, expr_type, scale_pointer_offset,
+        store_array_slice,
+    },
+    generics::is_generic_enum,
+    literals::is_literal,
+    resolve_type,
+    tuples::{compile_value_return, is_call, is_tuple, returns_by_value, value_operand},
+    variables::{insert_variable, reserve_stack, VariableMap},
+    CompilerContext, NSType, VariableMapBase,
 };
 
 fn compile_if_stmt(
@@ -55,17 +72,11 @@ fn compile_if_stmt(
     ifs: &IFStmt,
     exit_tag: String,
 ) -> Result<(), CompilationError> {
-    let condition_eo = compile_expr(cc, &ifs.then_block, &ifs.condition)?;
-    VariableType::Bool.cast(&condition_eo.vtype)?;
-
     let next_loc = match ifs.else_block.as_ref() {
         ElseBlock::None => exit_tag.clone(),
         _ => ifs.then_block.end_name(),
     };
-
-    mov_unknown_to_register(cc, RAX, condition_eo.value);
-    cc.codegen.instr2(Test, RAX, RAX);
-    cc.codegen.instr1(Jz, Opr::Loc(next_loc.clone()));
+    compile_condition_jump(cc, &ifs.then_block, &ifs.condition, false, &next_loc)?;
 
     compile_block(cc, &ifs.then_block);
     match ifs.else_block.as_ref() {
@@ -89,6 +100,165 @@ fn compile_if_stmt(
     }
 }
 
+/// Returns the value each arm is compared to, None for wildcards
+/// Fails if an arm is unreachable or the arms are not exhaustive
+fn match_arm_values(
+    vtype: &VariableType,
+    arms: &[MatchArm],
+) -> Result<Vec<Option<i64>>, CompilationError> {
+    let mut values = Vec::<Option<i64>>::new();
+    for arm in arms.iter() {
+        if values.contains(&None) {
+            return Err(CompilationError::UnreachableMatchArm(
+                arm.pattern.to_string(),
+            ));
+        }
+        let value = match (&arm.pattern, vtype) {
+            (MatchPattern::Wildcard, _) => None,
+            (MatchPattern::Variant(e, v, items), VariableType::Enum(et))
+                if e == et.base_ident() =>
+            {
+                let Some((tag, variant)) = et.get(v) else {
+                    return Err(CompilationError::UndefinedEnumVariant(e.clone(), v.clone()));
+                };
+                if !items.is_empty() && items.len() != variant.payload.items.len() {
+                    return Err(CompilationError::Err(format!(
+                        "Pattern ({}) expects {} items",
+                        arm.pattern,
+                        variant.payload.items.len()
+                    )));
+                }
+                Some(tag as i64)
+            }
+            (
+                MatchPattern::Int(i),
+                VariableType::Int | VariableType::UInt | VariableType::Long | VariableType::ULong,
+            ) => Some(*i),
+            _ => {
+                return Err(CompilationError::UnmatchingTypes(
+                    vtype.to_string(),
+                    arm.pattern.to_string(),
+                ));
+            }
+        };
+        if values.contains(&value) {
+            return Err(CompilationError::UnreachableMatchArm(
+                arm.pattern.to_string(),
+            ));
+        }
+        values.push(value);
+    }
+    if !values.contains(&None) {
+        let missing = match vtype {
+            VariableType::Enum(et) => et
+                .variants
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !values.contains(&Some(*i as i64)))
+                .map(|(_, v)| format!("{}::{}", et.base_ident(), v.ident))
+                .collect::<Vec<String>>(),
+            _ => vec!["_".to_string()],
+        };
+        if !missing.is_empty() {
+            return Err(CompilationError::NonExhaustiveMatch(missing.join(", ")));
+        }
+    }
+    Ok(values)
+}
+
+/// Compiles match stmts to a chain of compares
+fn compile_match(
+    cc: &mut CompilerContext,
+    block: &Block,
+    ms: &MatchStmt,
+) -> Result<(), CompilationError> {
+    let eo = match is_call(&ms.expr) {
+        true => compile_call_stmt(cc, block, &ms.expr)?,
+        false => compile_expr(cc, block, &ms.expr)?,
+    };
+    let values = match_arm_values(&eo.vtype, &ms.arms)?;
+    let Some(first_arm) = ms.arms.first() else {
+        return Ok(());
+    };
+    let exit_tag = first_arm.block.name_with_prefix("MTE");
+    // Payload items are copied from a memory that is not changed by the arms
+    let mut value = eo.value.clone();
+    if let (Opr::Mem(mem), true) = (&value, eo.is_temp()) {
+        let offset = reserve_stack(cc, &eo.vtype);
+        let map = VariableMap::new(
+            VariableMapBase::Stack(block.id.clone()),
+            offset,
+            eo.vtype.clone(),
+            false,
+        );
+        copy_memory(cc, map.mem(), mem.clone(), eo.vtype.size());
+        value = map.mem().into();
+    }
+    // Memory of the payload bindings is reserved before any arm is taken
+    let mut bindings = Vec::<Vec<(String, VariableMap, i32)>>::new();
+    for arm in ms.arms.iter() {
+        let mut arm_bindings = Vec::new();
+        if let (MatchPattern::Variant(_, v, items), VariableType::Enum(et)) =
+            (&arm.pattern, &eo.vtype)
+        {
+            let payload = &et.get(v).unwrap().1.payload;
+            for (ident, item) in items.iter().zip(payload.items.iter()) {
+                if ident == "_" {
+                    continue;
+                }
+                let offset = reserve_stack(cc, &item.vtype);
+                let map = VariableMap::new(
+                    VariableMapBase::Stack(arm.block.id.clone()),
+                    offset,
+                    item.vtype.clone(),
+                    false,
+                );
+                let src_offset = et.payload_offset() as i32 + item.offset;
+                arm_bindings.push((ident.clone(), map, src_offset));
+            }
+        }
+        bindings.push(arm_bindings);
+    }
+    let reg_size = match eo.vtype {
+        VariableType::Long | VariableType::ULong => 8,
+        _ => 4,
+    };
+    mov_unknown_to_register(cc, RAX, value.clone());
+    for (arm, value) in ms.arms.iter().zip(values.iter()) {
+        match value {
+            Some(v) => {
+                match i32::try_from(*v) {
+                    Ok(v) => cc.codegen.instr2(Cmp, RAX.convert(reg_size), v),
+                    Err(_) => {
+                        cc.codegen.instr2(Mov, RBX, *v);
+                        cc.codegen.instr2(Cmp, RAX, RBX);
+                    }
+                }
+                cc.codegen.instr1(Jz, Opr::Loc(arm.block.start_name()));
+            }
+            None => cc.codegen.instr1(Jmp, Opr::Loc(arm.block.start_name())),
+        }
+    }
+    if !values.contains(&None) {
+        cc.codegen.instr1(Jmp, Opr::Loc(exit_tag.clone()));
+    }
+    for (arm, arm_bindings) in ms.arms.iter().zip(bindings) {
+        cc.codegen.set_lable(arm.block.start_name());
+        for (ident, map, src_offset) in arm_bindings {
+            let Opr::Mem(mem) = &value else {
+                return Err(CompilationError::UnexpectedType(eo.vtype.to_string()));
+            };
+            let src = displaceable_mem(cc, mem.clone(), RSI).with_offset(src_offset, 0);
+            copy_memory(cc, map.mem().with_offset(0, 0), src, map.vtype.size());
+            cc.variables_map.insert(&ident, map)?;
+        }
+        compile_block(cc, &arm.block);
+        cc.codegen.instr1(Jmp, Opr::Loc(exit_tag.clone()));
+    }
+    cc.codegen.set_lable(exit_tag);
+    Ok(())
+}
+
 fn compile_print(
     cc: &mut CompilerContext,
     block: &Block,
@@ -103,9 +273,36 @@ fn compile_print(
             cc.codegen.instr2(Mov, RDX, s.len());
             cc.codegen.instr0(Syscall);
         }
-        _ => {
+        _ if expr_opr.vtype == VariableType::Float => {
+            cc.bif_set.insert(Bif::PrintFloat);
+            mov_unknown_to_xmm(cc, XMM0, expr_opr.value, &expr_opr.vtype);
+            cc.codegen.instr1(Call, Opr::Loc("print_float".to_string()));
+        }
+        _ if matches!(
+            expr_opr.vtype.underlying(),
+            VariableType::Short | VariableType::Int | VariableType::Long
+        ) =>
+        {
             cc.bif_set.insert(Bif::Print);
+            cc.bif_set.insert(Bif::PrintSigned);
             mov_unknown_to_register(cc, RDI, expr_opr.value);
+            extend_integer(cc, RDI, &expr_opr.vtype, 8);
+            cc.codegen
+                .instr1(Call, Opr::Loc("print_signed".to_string()));
+        }
+        _ => {
+            cc.bif_set.insert(Bif::Print);
+            // Bytes and words are zero extended since RDI may hold an address
+            match &expr_opr.value {
+                Opr::Mem(m) if m.size == 1 || m.size == 2 => {
+                    cc.codegen.instr2(Movzx, EDI, expr_opr.value)
+                }
+                Opr::R8(r) | Opr::R16(r) => cc.codegen.instr2(Movzx, EDI, *r),
+                _ => {
+                    mov_unknown_to_register(cc, RDI, expr_opr.value);
+                    extend_integer(cc, RDI, &expr_opr.vtype, 8);
+                }
+            }
             cc.codegen.instr1(Call, Opr::Loc("print".to_string()));
         }
     }
@@ -127,11 +324,17 @@ pub fn compile_stmt(
             compile_if_stmt(cc, ifs, exit_tag)
         }
         StmtType::Assign(a) => compile_assgin(cc, block, a),
+        StmtType::Match(m) => compile_match(cc, block, m),
         StmtType::While(w) => compile_while(cc, w),
+        StmtType::DoWhile(w) => compile_do_while(cc, w),
+        StmtType::Loop(b) => {
+            compile_loop(cc, b);
+            Ok(())
+        }
         StmtType::ForLoop(f) => compile_for_loop(cc, f),
         StmtType::Expr(e) => match &e.etype {
             ExprType::FunctionCall(fc) => {
-                let eo = compile_expr(cc, block, e)?;
+                let eo = compile_call_stmt(cc, block, e)?;
                 if eo.vtype != VariableType::Void {
                     log_warn!(
                         "({}), Unused return value of function {}!",
@@ -141,16 +344,41 @@ pub fn compile_stmt(
                 }
                 Ok(())
             }
+            ExprType::IndirectCall(..) => {
+                let eo = compile_call_stmt(cc, block, e)?;
+                if eo.vtype != VariableType::Void {
+                    log_warn!("({}), Unused return value of function call!", stmt.loc);
+                }
+                Ok(())
+            }
             _ => {
                 log_warn!("({}) Expression with no effect ignored!", stmt.loc);
                 Ok(())
             }
         },
         StmtType::Return(e) => {
+            let ret_type = match cc.namespace_map.get(&block.master_start_name()) {
+                Some(NSType::Function(f)) => f.ret_type.clone(),
+                _ => VariableType::Any,
+            };
+            if returns_by_value(&ret_type) {
+                compile_value_return(cc, block, &ret_type, e)?;
+                compile_return_defers(cc)?;
+                cc.codegen.instr1(Jmp, Opr::Loc(block.master_end_name()));
+                return Ok(());
+            }
             let ret_expr = compile_expr(cc, block, e)?;
-            mov_unknown_to_register(cc, RAX, ret_expr.value);
+            ret_type.check_distinct(&ret_expr.vtype)?;
+            if ret_type == VariableType::Float {
+                mov_unknown_to_xmm(cc, XMM0, ret_expr.value, &ret_expr.vtype);
+            } else if ret_expr.vtype == VariableType::Float {
+                mov_float_to_register(cc, RAX, ret_expr.value);
+            } else {
+                mov_unknown_to_register(cc, RAX, ret_expr.value);
+            }
             // cc.codegen.instr0(Leave);
             // cc.codegen.instr0(Ret);
+            compile_return_defers(cc)?;
             cc.codegen.instr1(Jmp, Opr::Loc(block.master_end_name()));
             Ok(())
         }
@@ -166,21 +394,29 @@ pub fn compile_stmt(
             }
             Ok(())
         }
-        StmtType::Break => compile_break_coninue(cc, block, true),
-        StmtType::Continue => compile_break_coninue(cc, block, false),
+        StmtType::Break(label) => compile_break_coninue(cc, block, label, true),
+        StmtType::Continue(label) => compile_break_coninue(cc, block, label, false),
+        StmtType::Defer(stmts) => {
+            insert_defer(cc, stmts);
+            Ok(())
+        }
     }
 }
 
 fn compile_break_coninue(
     cc: &mut CompilerContext,
     block: &Block,
+    label: &Option<String>,
     is_break: bool,
 ) -> Result<(), CompilationError> {
+    let loop_id = block.loop_block_id(label)?;
+    // Continue jumps to the next iteration of the loop
     let exit_loc = if is_break {
-        block.last_loop_end_name()?
+        format!("{loop_id}.BE__")
     } else {
-        block.last_loop_start_name()?
+        format!("{loop_id}.NXT__")
     };
+    compile_loop_defers(cc, &loop_id)?;
     cc.codegen.instr1(Jmp, Opr::Loc(exit_loc));
     Ok(())
 }
@@ -194,7 +430,7 @@ fn compile_inline_asm(
         let mut final_instr = instr.clone();
         let chars = final_instr.chars().collect::<Vec<char>>();
         let mut index = 0;
-        let is_empty = |index: usize| (index >= chars.len());
+        let is_empty = |index: usize| index >= chars.len();
         while !is_empty(index) {
             if chars[index] == '%' {
                 let mut ident = String::new();
@@ -228,45 +464,174 @@ fn compile_inline_asm(
 }
 
 fn compile_for_loop(cc: &mut CompilerContext, for_stmt: &ForLoop) -> Result<(), CompilationError> {
-    insert_variable(
-        cc,
-        &for_stmt.block,
-        &for_stmt.iterator,
-        VariableMapBase::Stack(for_stmt.block.id.clone()),
-    )?;
-    if !matches!(for_stmt.end_expr.etype, ExprType::Int(_)) {
+    match &for_stmt.range {
+        ForRange::Range(end, step) => compile_range_loop(cc, for_stmt, end, step.as_deref()),
+        ForRange::Items(items) => compile_items_loop(cc, for_stmt, items),
+    }
+}
+
+fn compile_range_loop(
+    cc: &mut CompilerContext,
+    for_stmt: &ForLoop,
+    end: &Expr,
+    step: Option<&Expr>,
+) -> Result<(), CompilationError> {
+    let block = &for_stmt.block;
+    let step = match step.map(eval_const) {
+        None => 1,
+        Some(Ok(ConstValue::Num(v, t))) if t.is_integer() && v != 0 && i32::try_from(v).is_ok() => {
+            v
+        }
+        Some(_) => {
+            return Err(CompilationError::Err(
+                "Step of a for loop should be a non-zero integer constant".to_string(),
+            ))
+        }
+    };
+    let base = VariableMapBase::Stack(block.id.clone());
+    insert_variable(cc, block, &for_stmt.iterator, base.clone())?;
+    let vtype = cc.variables_map.get(&for_stmt.iterator.ident, block)?.vtype;
+    if !vtype.is_integer() {
         return Err(CompilationError::Err(format!(
-            "Unsupported iterator type (must be type integer insted of ({:?}))",
-            for_stmt.end_expr.etype
+            "Unsupported iterator type (must be type integer insted of ({vtype}))"
         )));
     }
+    // The end is evaluated once before the loop starts
+    let end = match eval_const(end) {
+        Ok(ConstValue::Num(_, t)) if t.is_integer() => end.clone(),
+        _ => {
+            let ident = format!("$end{}:{}", end.loc.line, end.loc.col);
+            let hidden = VariableDeclare {
+                mutable: false,
+                ident: ident.clone(),
+                v_type: vtype,
+                init_value: Some(end.clone()),
+                loc: end.loc.clone(),
+            };
+            insert_variable(cc, block, &hidden, base)?;
+            Expr {
+                etype: ExprType::Variable(ident),
+                loc: end.loc.clone(),
+            }
+        }
+    };
     cc.codegen
-        .instr1(Jmp, Opr::Loc(for_stmt.block.name_with_prefix("CND")));
-    cc.codegen.set_lable(for_stmt.block.start_name());
-    compile_block(cc, &for_stmt.block);
+        .instr1(Jmp, Opr::Loc(block.name_with_prefix("CND")));
+    cc.codegen.set_lable(block.start_name());
+    compile_block(cc, block);
+    compile_loop_step(cc, block, &for_stmt.iterator, step, end)
+}
 
-    let v_map = cc
-        .variables_map
-        .get(&for_stmt.iterator.ident, &for_stmt.block)?;
-    let mem_acss = v_map.mem();
-    cc.codegen.instr1(Inc, mem_acss);
+/// Iterates over items of an array or a slice with a hidden index
+fn compile_items_loop(
+    cc: &mut CompilerContext,
+    for_stmt: &ForLoop,
+    items: &Expr,
+) -> Result<(), CompilationError> {
+    let block = &for_stmt.block;
+    let loc = &for_stmt.iterator.loc;
+    let base = VariableMapBase::Stack(block.id.clone());
+    let items_type = expr_type(cc, block, items)?;
+    if !matches!(items_type, VariableType::Array(..) | VariableType::Slice(_)) {
+        return Err(CompilationError::Err(format!(
+            "Can not iterate over type ({items_type}), expected an array or a slice"
+        )));
+    }
+    let hidden = |ident: String, v_type: VariableType, value: Expr| VariableDeclare {
+        mutable: true,
+        ident,
+        v_type,
+        init_value: Some(value),
+        loc: loc.clone(),
+    };
+    let variable = |ident: &str| Expr {
+        etype: ExprType::Variable(ident.to_string()),
+        loc: loc.clone(),
+    };
+    // Items are evaluated once before the loop starts
+    let items = match items.etype {
+        ExprType::Variable(_) => items.clone(),
+        _ => {
+            let ident = format!("$items{}:{}", loc.line, loc.col);
+            let decl = hidden(ident.clone(), VariableType::Any, items.clone());
+            insert_variable(cc, block, &decl, base.clone())?;
+            variable(&ident)
+        }
+    };
+    let zero = Expr {
+        etype: ExprType::Int(0),
+        loc: loc.clone(),
+    };
+    let index = hidden(
+        format!("$index{}:{}", loc.line, loc.col),
+        VariableType::Long,
+        zero,
+    );
+    insert_variable(cc, block, &index, base.clone())?;
+    let end = match items_type {
+        VariableType::Array(_, n) => Expr {
+            etype: ExprType::Int(n as i64),
+            loc: loc.clone(),
+        },
+        _ => Expr {
+            etype: ExprType::FunctionCall(FunctionCall {
+                ident: "len".to_string(),
+                args: vec![items.clone()],
+                receiver: None,
+            }),
+            loc: loc.clone(),
+        },
+    };
+    cc.codegen
+        .instr1(Jmp, Opr::Loc(block.name_with_prefix("CND")));
+    cc.codegen.set_lable(block.start_name());
+    let item = ArrayIndex {
+        array: Box::new(items),
+        indexer: Box::new(variable(&index.ident)),
+    };
+    let iterator = VariableDeclare {
+        init_value: Some(Expr {
+            etype: ExprType::ArrayIndex(item),
+            loc: loc.clone(),
+        }),
+        ..for_stmt.iterator.clone()
+    };
+    insert_variable(cc, block, &iterator, base)?;
+    compile_block(cc, block);
+    compile_loop_step(cc, block, &index, 1, end)
+}
 
-    cc.codegen.set_lable(for_stmt.block.name_with_prefix("CND"));
-    let cmp = CompareExpr {
-        left: Box::new(Expr {
-            loc: for_stmt.iterator.loc.clone(),
-            etype: ExprType::Variable(for_stmt.iterator.ident.clone()),
+/// Moves the iterator to the next value and jumps back while it has not reached the end
+fn compile_loop_step(
+    cc: &mut CompilerContext,
+    block: &Block,
+    iterator: &VariableDeclare,
+    step: i64,
+    end: Expr,
+) -> Result<(), CompilationError> {
+    cc.codegen.set_lable(block.name_with_prefix("NXT"));
+    let mem_acss = cc.variables_map.get(&iterator.ident, block)?.mem();
+    match step {
+        1 => cc.codegen.instr1(Inc, mem_acss),
+        _ => cc.codegen.instr2(Add, mem_acss, step as i32),
+    }
+    cc.codegen.set_lable(block.name_with_prefix("CND"));
+    let cmp = Expr {
+        loc: iterator.loc.clone(),
+        etype: ExprType::Compare(CompareExpr {
+            left: Box::new(Expr {
+                loc: iterator.loc.clone(),
+                etype: ExprType::Variable(iterator.ident.clone()),
+            }),
+            op: match step > 0 {
+                true => CompareOp::Smaller,
+                false => CompareOp::Bigger,
+            },
+            right: Box::new(end),
         }),
-        op: CompareOp::Smaller,
-        right: Box::new(for_stmt.end_expr.to_owned()),
     };
-    let condition_eo = compile_compare_expr(cc, &for_stmt.block, &cmp)?;
-    VariableType::Bool.cast(&condition_eo.vtype)?;
-    mov_unknown_to_register(cc, RAX, condition_eo.value);
-    cc.codegen.instr2(Test, RAX, RAX);
-    cc.codegen
-        .instr1(Jne, Opr::Loc(for_stmt.block.start_name()));
-    cc.codegen.set_lable(for_stmt.block.end_name());
+    compile_condition_jump(cc, block, &cmp, true, &block.start_name())?;
+    cc.codegen.set_lable(block.end_name());
     Ok(())
 }
 
@@ -275,21 +640,113 @@ fn compile_while(cc: &mut CompilerContext, w_stmt: &WhileStmt) -> Result<(), Com
         .instr1(Jmp, Opr::Loc(w_stmt.block.name_with_prefix("CND")));
     cc.codegen.set_lable(w_stmt.block.start_name());
     compile_block(cc, &w_stmt.block);
+    cc.codegen.set_lable(w_stmt.block.name_with_prefix("NXT"));
     cc.codegen.set_lable(w_stmt.block.name_with_prefix("CND"));
-    // Jump after a compare
-    let condition_eo = compile_expr(cc, &w_stmt.block, &w_stmt.condition)?;
-    VariableType::Bool.cast(&condition_eo.vtype)?;
-    mov_unknown_to_register(cc, RAX, condition_eo.value);
-    cc.codegen.instr2(Test, RAX, RAX);
-    cc.codegen.instr1(Jne, Opr::Loc(w_stmt.block.start_name()));
+    compile_condition_jump(
+        cc,
+        &w_stmt.block,
+        &w_stmt.condition,
+        true,
+        &w_stmt.block.start_name(),
+    )?;
+    cc.codegen.set_lable(w_stmt.block.end_name());
+    Ok(())
+}
+
+fn compile_do_while(cc: &mut CompilerContext, w_stmt: &WhileStmt) -> Result<(), CompilationError> {
+    cc.codegen.set_lable(w_stmt.block.start_name());
+    compile_block(cc, &w_stmt.block);
+    cc.codegen.set_lable(w_stmt.block.name_with_prefix("NXT"));
+    compile_condition_jump(
+        cc,
+        &w_stmt.block,
+        &w_stmt.condition,
+        true,
+        &w_stmt.block.start_name(),
+    )?;
     cc.codegen.set_lable(w_stmt.block.end_name());
     Ok(())
 }
 
+fn compile_loop(cc: &mut CompilerContext, block: &Block) {
+    cc.codegen.set_lable(block.start_name());
+    compile_block(cc, block);
+    cc.codegen.set_lable(block.name_with_prefix("NXT"));
+    cc.codegen.instr1(Jmp, Opr::Loc(block.start_name()));
+    cc.codegen.set_lable(block.end_name());
+}
+
+/// Assign a value to memory with the type of the target,
+/// float values are truncated when assigned to integers
+pub(super) fn assgin_typed_op(
+    cc: &mut CompilerContext,
+    op: &AssignOp,
+    opr: Opr,
+    opr_vtype: &VariableType,
+    mem_vtype: &VariableType,
+    mem_acss: MemAddr,
+) -> Result<(), CompilationError> {
+    if *mem_vtype == VariableType::Float {
+        assgin_float_op(cc, op, opr, opr_vtype, mem_acss)
+    } else if *opr_vtype == VariableType::Float {
+        mov_float_to_register(cc, RAX, opr);
+        assgin_op(cc, op, RAX.into(), mem_vtype, mem_acss)
+    } else if let (VariableType::TypedPointer(t), AssignOp::PlusEq | AssignOp::SubEq) =
+        (mem_vtype, op)
+    {
+        mov_unknown_to_register(cc, RAX, opr);
+        if *opr_vtype.underlying() == VariableType::Int {
+            cc.codegen.instr2(Movsxd, RAX, EAX);
+        }
+        scale_pointer_offset(cc, RAX, t)?;
+        assgin_op(cc, op, RAX.into(), mem_vtype, mem_acss)
+    } else if *opr_vtype.underlying() == VariableType::Int && mem_vtype.size() == 8 {
+        // Signed values keep their sign in wider memory
+        mov_unknown_to_register(cc, RAX, opr);
+        cc.codegen.instr2(Movsxd, RAX, EAX);
+        assgin_op(cc, op, RAX.into(), mem_vtype, mem_acss)
+    } else {
+        assgin_op(cc, op, opr, mem_vtype, mem_acss)
+    }
+}
+
+fn assgin_float_op(
+    cc: &mut CompilerContext,
+    op: &AssignOp,
+    opr: Opr,
+    opr_vtype: &VariableType,
+    mem_acss: MemAddr,
+) -> Result<(), CompilationError> {
+    let mnem = match op {
+        AssignOp::Eq => {
+            mov_unknown_to_xmm(cc, XMM0, opr, opr_vtype);
+            cc.codegen.instr2(Movsd, mem_acss, XMM0);
+            return Ok(());
+        }
+        AssignOp::PlusEq => Addsd,
+        AssignOp::SubEq => Subsd,
+        AssignOp::MultiEq => Mulsd,
+        AssignOp::DevideEq => Divsd,
+        _ => {
+            return Err(CompilationError::InValidBinaryOperation(
+                op.binary_op().unwrap(),
+                VariableType::Float.to_string(),
+                opr_vtype.to_string(),
+            ));
+        }
+    };
+    mov_unknown_to_xmm(cc, XMM1, opr, opr_vtype);
+    cc.codegen.instr2(Movsd, XMM0, mem_acss.clone());
+    cc.codegen.instr2(mnem, XMM0, XMM1);
+    cc.codegen.instr2(Movsd, mem_acss, XMM0);
+    Ok(())
+}
+
 fn assgin_op(
     cc: &mut CompilerContext,
     op: &AssignOp,
     opr: Opr,
+    mem_vtype: &VariableType,
     mem_acss: MemAddr,
 ) -> Result<(), CompilationError> {
     mov_unknown_to_register(cc, RAX, opr);
@@ -315,21 +772,104 @@ fn assgin_op(
         }
         AssignOp::DevideEq => {
             cc.codegen.instr2(Mov, RBX, RAX);
-            mov_unknown_to_register(cc, RAX, mem_acss.clone().into());
-            cc.codegen.instr0(Cqo);
-            cc.codegen.instr1(Idiv, RBX);
+            load_assign_target(cc, &mem_acss, mem_vtype);
+            compile_division(cc, reg_size.max(4), mem_vtype.is_unsigned());
             cc.codegen.instr2(Mov, mem_acss, RAX.convert(reg_size));
             Ok(())
         }
         AssignOp::ModEq => {
             cc.codegen.instr2(Mov, RBX, RAX);
-            mov_unknown_to_register(cc, RAX, mem_acss.clone().into());
-            cc.codegen.instr0(Cqo);
-            cc.codegen.instr1(Idiv, RBX);
+            load_assign_target(cc, &mem_acss, mem_vtype);
+            compile_division(cc, reg_size.max(4), mem_vtype.is_unsigned());
             cc.codegen.instr2(Mov, mem_acss, RDX.convert(reg_size));
             Ok(())
         }
+        AssignOp::AndEq | AssignOp::OrEq | AssignOp::XorEq => {
+            let mnem = match op {
+                AssignOp::AndEq => And,
+                AssignOp::OrEq => Or,
+                _ => Xor,
+            };
+            mov_unknown_to_register(cc, RBX, mem_acss.clone().into());
+            cc.codegen.instr2(mnem, RAX, RBX);
+            cc.codegen.instr2(Mov, mem_acss, RAX.convert(reg_size));
+            Ok(())
+        }
+        AssignOp::LshEq | AssignOp::RshEq => {
+            let mnem = match op {
+                AssignOp::LshEq => Sal,
+                _ if mem_vtype.is_unsigned() => Shr,
+                _ => Sar,
+            };
+            cc.codegen.instr2(Mov, RCX, RAX);
+            load_assign_target(cc, &mem_acss, mem_vtype);
+            cc.codegen.instr2(mnem, RAX.convert(reg_size.max(4)), CL);
+            cc.codegen.instr2(Mov, mem_acss, RAX.convert(reg_size));
+            Ok(())
+        }
+    }
+}
+
+/// Loads the assigned memory to RAX,
+/// bytes and words are extended so no garbage is shifted or divided with them
+fn load_assign_target(cc: &mut CompilerContext, mem_acss: &MemAddr, mem_vtype: &VariableType) {
+    match mem_acss.size {
+        1 => cc.codegen.instr2(Movzx, EAX, mem_acss.clone()),
+        2 if mem_vtype.is_unsigned() => cc.codegen.instr2(Movzx, EAX, mem_acss.clone()),
+        2 => cc.codegen.instr2(Movsx, EAX, mem_acss.clone()),
+        _ => mov_unknown_to_register(cc, RAX, mem_acss.clone().into()),
+    }
+}
+
+/// Returns the variable that owns an assignable expression
+/// e.g: list in list[2].name
+/// Returns the variable that owns the memory of an lvalue and type of the lvalue
+/// Memory behind a pointer has no owner
+fn lvalue_owner(
+    cc: &mut CompilerContext,
+    block: &Block,
+    expr: &Expr,
+) -> Result<(Option<String>, VariableType), CompilationError> {
+    match &expr.etype {
+        ExprType::Variable(v) => Ok((Some(v.clone()), cc.variables_map.get(v, block)?.vtype)),
+        ExprType::Access(base, item) => {
+            let (owner, vtype) = match lvalue_owner(cc, block, base)? {
+                (_, VariableType::TypedPointer(t)) => (None, resolve_type(cc, &t)?),
+                res => res,
+            };
+            let VariableType::Struct(st) = vtype else {
+                return Err(CompilationError::UnexpectedType(vtype.to_string()));
+            };
+            let Some(item) = st.get(item) else {
+                return Err(CompilationError::UndefinedStructItem(
+                    st.ident.clone(),
+                    item.to_owned(),
+                ));
+            };
+            Ok((owner, item.vtype.clone()))
+        }
+        ExprType::ArrayIndex(ai) => match lvalue_owner(cc, block, &ai.array)? {
+            (owner, VariableType::Array(t, _)) => Ok((owner, *t)),
+            (_, VariableType::Slice(t)) => Ok((None, *t)),
+            (_, vtype) => Err(CompilationError::UnexpectedType(vtype.to_string())),
+        },
+        ExprType::DeRef(_) => Ok((None, VariableType::Any)),
+        _ => Err(CompilationError::UnexpectedType("Literal".to_owned())),
+    }
+}
+
+/// Calls a function at the start of a statement
+/// Tuples and enums returned by value are stored in memory
+fn compile_call_stmt(
+    cc: &mut CompilerContext,
+    block: &Block,
+    call: &Expr,
+) -> Result<ExprOpr, CompilationError> {
+    let vtype = expr_type(cc, block, call)?;
+    if returns_by_value(&vtype) {
+        return value_operand(cc, block, &vtype, call);
     }
+    compile_expr(cc, block, call)
 }
 
 fn compile_assgin(
@@ -337,71 +877,87 @@ fn compile_assgin(
     block: &Block,
     assign: &Assign,
 ) -> Result<(), CompilationError> {
-    match &assign.left.etype {
-        ExprType::Variable(v) => {
-            let v_map = cc.variables_map.get(v, block)?;
-            if !v_map.is_mut {
-                return Err(CompilationError::ImmutableVariable(v.to_owned()));
-            }
-            let right_eo = compile_expr(cc, block, &assign.right)?;
-            v_map.vtype.cast(&right_eo.vtype)?;
-            assgin_op(cc, &assign.op, right_eo.value, v_map.mem())?;
-            Ok(())
+    if let (Some(root), _) = lvalue_owner(cc, block, &assign.left)? {
+        if !cc.variables_map.get(&root, block)?.is_mut {
+            return Err(CompilationError::ImmutableVariable(root));
         }
-        ExprType::ArrayIndex(ai) => {
-            let v_map = cc.variables_map.get(&ai.ident, block)?;
-            if !v_map.is_mut {
-                return Err(CompilationError::ImmutableVariable(ai.ident.clone()));
-            }
-            let right_eo = compile_expr(cc, block, &assign.right)?;
-            if right_eo.is_temp() {
-                save_temp_value(cc, right_eo.value.clone());
-            }
-            let _ = match &v_map.vtype {
-                VariableType::Array(t, _) => t.cast(&right_eo.vtype)?,
-                _ => unreachable!(),
-            };
-            let indexer = compile_expr(cc, block, &ai.indexer)?;
-            mov_unknown_to_register(cc, RBX, indexer.value);
-            let mem = MemAddr::new_sib_s(
-                v_map.vtype.item_size(),
-                RBP,
-                v_map.offset,
-                RBX,
-                v_map.vtype.item_size(),
-            );
-            if right_eo.is_temp() {
-                restore_last_temp_value(cc, RAX);
-                assgin_op(cc, &assign.op, RAX.into(), mem)?;
-            } else {
-                assgin_op(cc, &assign.op, right_eo.value, mem)?;
+    }
+    // Tuples and returned values are built before the target address is computed
+    let ltype = expr_type(cc, block, &assign.left)?;
+    let in_place = is_tuple(&ltype)
+        || is_literal(&assign.right)
+        || (returns_by_value(&ltype) && is_call(&assign.right));
+    let tuple_value = match in_place {
+        true => Some(value_operand(cc, block, &ltype, &assign.right)?),
+        false => None,
+    };
+    let left_eo = compile_expr(cc, block, &assign.left)?;
+    let Opr::Mem(mem) = left_eo.value.clone() else {
+        return Err(CompilationError::UnexpectedType(left_eo.vtype.to_string()));
+    };
+    // Address of the target is kept on the stack if registers are needed to access it
+    let saved = left_eo.is_temp();
+    if saved {
+        cc.codegen.instr2(Lea, RAX, mem.with_offset(0, 8));
+        cc.codegen.instr1(Push, RAX);
+    }
+    let is_enum = matches!(left_eo.vtype, VariableType::Enum(_));
+    if is_enum && !matches!(assign.op, AssignOp::Eq) {
+        return Err(CompilationError::UnexpectedType(left_eo.vtype.to_string()));
+    }
+    // Enum variants with payload or type arguments are constructed in place
+    if let ExprType::EnumVariant(ev) = &assign.right.etype {
+        if !ev.args.is_empty() || is_generic_enum(cc, &ev.ident) {
+            let vtype = compile_enum_into(cc, block, ev, &left_eo.vtype, mem, saved)?;
+            if saved {
+                cc.codegen.instr1(Pop, RDI);
             }
-            Ok(())
+            left_eo.vtype.cast(&vtype)?;
+            return Ok(());
         }
-        ExprType::Access(ident, expr) => {
-            let v_map = cc.variables_map.get(ident, block)?;
-            let VariableType::Struct(struc) = v_map.vtype.clone() else {
-                unreachable!();
-            };
-            match &expr.etype {
-                ExprType::Variable(i) => {
-                    let inner_var = struc.items.get(i).unwrap();
-                    let right_eo = compile_expr(cc, block, &assign.right)?;
-                    inner_var.vtype.cast(&right_eo.vtype)?;
-                    cc.codegen.instr2(Mov, RDX, mem!(RBP, v_map.offset));
-                    cc.codegen.instr2(Add, RDX, inner_var.offset);
-                    let mem = MemAddr::new_s(inner_var.vtype.item_size(), RDX);
-                    assgin_op(cc, &assign.op, right_eo.value, mem)?;
-                }
-                ExprType::ArrayIndex(_) => todo!(),
-                ExprType::Access(_, _) => todo!(),
-                _ => {
-                    return Err(CompilationError::UnexpectedType(struc.ident));
-                }
+    }
+    let right_eo = match tuple_value {
+        Some(value) => value,
+        None => compile_expr(cc, block, &assign.right)?,
+    };
+    // Typed pointers are moved by integer offsets
+    let moves_pointer = matches!(
+        (&left_eo.vtype, &assign.op),
+        (
+            VariableType::TypedPointer(_),
+            AssignOp::PlusEq | AssignOp::SubEq
+        )
+    ) && right_eo.vtype.is_integer();
+    if !moves_pointer {
+        left_eo.vtype.cast(&right_eo.vtype)?;
+    }
+    let mem = if saved {
+        cc.codegen.instr1(Pop, RDI);
+        MemAddr::new_s(mem.size, RDI)
+    } else {
+        mem
+    };
+    // Enum tags without payload are assigned like integers
+    let tag_only = is_enum && !right_eo.value.is_mem();
+    let is_array = matches!(left_eo.vtype, VariableType::Array(..));
+    if (left_eo.vtype.is_aggregate() || is_array) && !tag_only {
+        let (AssignOp::Eq, Opr::Mem(src)) = (&assign.op, right_eo.value) else {
+            return Err(CompilationError::UnexpectedType(left_eo.vtype.to_string()));
+        };
+        match right_eo.vtype {
+            VariableType::Array(_, n) if left_eo.vtype != right_eo.vtype => {
+                store_array_slice(cc, mem, src, n)
             }
-
-            Ok(())
+            _ => copy_memory(cc, mem, src, left_eo.vtype.size()),
         }
-        _ => Err(CompilationError::UnexpectedType("Literal".to_owned())),
+        return Ok(());
     }
+    assgin_typed_op(
+        cc,
+        &assign.op,
+        right_eo.value,
+        &right_eo.vtype,
+        &left_eo.vtype,
+        mem,
+    )
 }
@@ -0,0 +1,261 @@
+/**********************************************************************************************
+*
+*   compiler/tuples: storing and returning tuples and other values returned by value
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use crate::{
+    assembler::{
+        instructions::Opr,
+        memory::MemAddr,
+        mnemonic::Mnemonic::*,
+        register::Reg::{self, *},
+        utils::copy_memory,
+    },
+    error_handeling::CompilationError,
+    optim::ExprOpr,
+    parser::{
+        assign::AssignOp,
+        block::Block,
+        expr::{Expr, ExprType},
+        types::{StructType, VariableType},
+    },
+};
+
+use super::{
+    expr::{compile_enum_into, compile_expr, expr_type},
+    literals::{is_literal, literal_type, store_literal},
+    stmts::assgin_typed_op,
+    variables::{reserve_stack, VariableMap, VariableMapBase},
+    CompilerContext,
+};
+
+/// Hidden argument holding the address that large tuples are returned to
+pub const RET_PTR: &str = "$ret";
+
+pub fn is_tuple(vtype: &VariableType) -> bool {
+    matches!(vtype, VariableType::Struct(st) if st.is_tuple())
+}
+
+/// Tuples and enums with payload are returned in RAX:RDX
+pub fn returns_by_value(vtype: &VariableType) -> bool {
+    match vtype {
+        VariableType::Enum(et) => !et.is_plain(),
+        _ => is_tuple(vtype),
+    }
+}
+
+/// Values that do not fit in RAX:RDX are returned through a hidden pointer
+pub fn returns_in_memory(vtype: &VariableType) -> bool {
+    returns_by_value(vtype) && vtype.size() > 16
+}
+
+pub fn is_call(value: &Expr) -> bool {
+    matches!(
+        value.etype,
+        ExprType::FunctionCall(_) | ExprType::IndirectCall(..)
+    )
+}
+
+/// Type of a value that may be a tuple literal
+pub fn value_type(
+    cc: &mut CompilerContext,
+    block: &Block,
+    value: &Expr,
+) -> Result<VariableType, CompilationError> {
+    match &value.etype {
+        ExprType::Tuple(items) => {
+            let items = items
+                .iter()
+                .map(|e| value_type(cc, block, e))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(VariableType::Struct(StructType::tuple(items)))
+        }
+        _ if is_literal(value) => literal_type(cc, block, value),
+        _ => expr_type(cc, block, value),
+    }
+}
+
+/// Stores a value returned by value or a literal in stack memory
+/// Items of literals are converted to the item types,
+/// other values should have the same type
+pub fn store_value(
+    cc: &mut CompilerContext,
+    block: &Block,
+    dest: &MemAddr,
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<(), CompilationError> {
+    match (&value.etype, vtype) {
+        (ExprType::Tuple(items), VariableType::Struct(st)) => {
+            return store_tuple_items(cc, block, dest, st, value, items)
+        }
+        (ExprType::EnumVariant(ev), VariableType::Enum(_)) => {
+            compile_enum_into(cc, block, ev, vtype, dest.clone(), false)?;
+            return Ok(());
+        }
+        _ if is_literal(value) => return store_literal(cc, block, dest, vtype, value),
+        _ => (),
+    }
+    if is_call(value) {
+        cc.tuple_dest = Some(dest.clone());
+    }
+    let eo = compile_expr(cc, block, value);
+    cc.tuple_dest = None;
+    let eo = eo?;
+    if eo.vtype != *vtype {
+        return Err(CompilationError::UnmatchingTypes(
+            vtype.to_string(),
+            eo.vtype.to_string(),
+        ));
+    }
+    match eo.value {
+        Opr::Mem(src) => copy_memory(cc, dest.clone(), src, vtype.size()),
+        // Large values are already stored by the callee
+        _ if returns_in_memory(vtype) => (),
+        _ => store_return_registers(cc, dest, vtype.size()),
+    }
+    Ok(())
+}
+
+fn store_tuple_items(
+    cc: &mut CompilerContext,
+    block: &Block,
+    dest: &MemAddr,
+    st: &StructType,
+    value: &Expr,
+    items: &[Expr],
+) -> Result<(), CompilationError> {
+    if items.len() != st.items.len() {
+        return Err(CompilationError::UnmatchingTypes(
+            VariableType::Struct(st.clone()).to_string(),
+            value_type(cc, block, value)?.to_string(),
+        ));
+    }
+    for (item, e) in st.items.iter().zip(items.iter()) {
+        let mem = dest.with_offset(item.offset, item.vtype.mem_size());
+        store_item(cc, block, &mem, &item.vtype, e)?;
+    }
+    Ok(())
+}
+
+/// Stores an item of a tuple, array or struct converted to the item type
+pub fn store_item(
+    cc: &mut CompilerContext,
+    block: &Block,
+    mem: &MemAddr,
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<(), CompilationError> {
+    if is_tuple(vtype) || is_literal(value) {
+        return store_value(cc, block, mem, vtype, value);
+    }
+    let eo = compile_expr(cc, block, value)?;
+    vtype.cast(&eo.vtype)?;
+    if vtype.is_aggregate() || matches!(vtype, VariableType::Array(..)) {
+        let Opr::Mem(src) = eo.value else {
+            return Err(CompilationError::UnexpectedType(eo.vtype.to_string()));
+        };
+        copy_memory(cc, mem.clone(), src, vtype.size());
+    } else {
+        assgin_typed_op(cc, &AssignOp::Eq, eo.value, &eo.vtype, vtype, mem.clone())?;
+    }
+    Ok(())
+}
+
+/// Stores a value returned by value in a temporary stack slot
+/// Only used at the start of statements when no temporary values are pushed
+pub fn value_operand(
+    cc: &mut CompilerContext,
+    block: &Block,
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<ExprOpr, CompilationError> {
+    let offset = reserve_stack(cc, vtype);
+    let map = VariableMap::new(
+        VariableMapBase::Stack(block.id.clone()),
+        offset,
+        vtype.clone(),
+        false,
+    );
+    store_value(cc, block, &map.mem(), vtype, value)?;
+    Ok(ExprOpr::new(map.mem(), vtype.clone()))
+}
+
+/// Moves the returned value to RAX:RDX or to the memory behind the hidden pointer
+pub fn compile_value_return(
+    cc: &mut CompilerContext,
+    block: &Block,
+    vtype: &VariableType,
+    value: &Expr,
+) -> Result<(), CompilationError> {
+    let value = value_operand(cc, block, vtype, value)?;
+    let Opr::Mem(mem) = value.value else {
+        unreachable!();
+    };
+    if returns_in_memory(vtype) {
+        let dest = return_memory(cc, block)?;
+        copy_memory(cc, dest, mem, vtype.size());
+        cc.codegen.instr2(Mov, RAX, RCX);
+    } else {
+        load_return_registers(cc, &mem, vtype.size());
+    }
+    Ok(())
+}
+
+/// Memory that large values are returned to, its address is kept in RCX
+pub fn return_memory(cc: &mut CompilerContext, block: &Block) -> Result<MemAddr, CompilationError> {
+    let ret_ptr = cc.variables_map.get(RET_PTR, block)?.mem();
+    cc.codegen.instr2(Mov, RCX, ret_ptr);
+    Ok(MemAddr::new(RCX))
+}
+
+pub fn load_return_registers(cc: &mut CompilerContext, mem: &MemAddr, size: usize) {
+    cc.codegen.instr2(Mov, RAX, mem.with_offset(0, 8));
+    if size > 8 {
+        cc.codegen.instr2(Mov, RDX, mem.with_offset(8, 8));
+    }
+}
+
+/// Stores a tuple returned in RAX:RDX without writing past its size
+fn store_return_registers(cc: &mut CompilerContext, dest: &MemAddr, size: usize) {
+    let mut offset = 0;
+    for reg in [RAX, RDX] {
+        let end = size.min(offset + 8);
+        while offset < end {
+            let chunk = match end - offset {
+                8.. => 8,
+                4..=7 => 4,
+                2..=3 => 2,
+                _ => 1,
+            };
+            cc.codegen.instr2(
+                Mov,
+                dest.with_offset(offset as i32, chunk),
+                Reg::convert(&reg, chunk),
+            );
+            offset += chunk as usize;
+            if offset < end {
+                cc.codegen.instr2(Shr, reg, 8 * chunk as i32);
+            }
+        }
+    }
+}
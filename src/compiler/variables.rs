@@ -30,14 +30,29 @@ use crate::{
         memory::MemAddr,
         mnemonic::Mnemonic::*,
         register::Reg::{self, *},
-        utils::mov_unknown_to_register,
+        utils::{copy_memory, mov_float_to_register, mov_unknown_to_register, mov_unknown_to_xmm},
     },
     error_handeling::{error, CompilationError},
-    memq,
-    parser::{block::Block, types::VariableType, variable_decl::VariableDeclare},
+    parser::{
+        block::Block,
+        constants::{eval_const, eval_const_as, ConstValue},
+        expr::{EnumVariantExpr, Expr, ExprType, Op},
+        types::{align_to, VariableType},
+        variable_decl::VariableDeclare,
+    },
 };
 
-use super::{expr::compile_expr, CompilerContext, NSType};
+use super::{
+    expr::{compile_enum_into, compile_expr, enum_variant, expr_type, store_array_slice},
+    generics::is_generic_enum,
+    literals::{is_literal, literal_data},
+    resolve_type,
+    tuples::{
+        is_call, is_tuple, returns_by_value, returns_in_memory, store_value, value_operand,
+        value_type,
+    },
+    CompilerContext,
+};
 
 #[derive(Debug, Clone)]
 pub enum VariableMapBase {
@@ -68,36 +83,36 @@ impl VariableMap {
         matches!(self.base, VariableMapBase::Global(_))
     }
 
-    pub fn mem_with_offset_reg(&self, offset_reg: Reg) -> MemAddr {
-        MemAddr::new_sib_s(
-            self.vtype.item_size(),
-            Reg::RBP,
-            self.offset,
-            offset_reg,
-            self.vtype.item_size(),
-        )
-    }
-
     pub fn mem(&self) -> MemAddr {
         if let VariableMapBase::Global(g) = &self.base {
-            return MemAddr::new_rela_s(self.vtype.item_size(), g.to_string());
+            return MemAddr::new_rela_s(self.vtype.mem_size(), g.to_string());
         }
         match &self.vtype {
-            VariableType::Int | VariableType::UInt => MemAddr::new_disp_s(4, Reg::RBP, self.offset),
+            VariableType::Int | VariableType::UInt | VariableType::Enum(_) => {
+                MemAddr::new_disp_s(4, Reg::RBP, self.offset)
+            }
             VariableType::Long
             | VariableType::ULong
+            | VariableType::Float
             | VariableType::Custom(_)
+            | VariableType::Generic(..)
             | VariableType::Pointer
+            | VariableType::TypedPointer(_)
+            | VariableType::Function(_)
             | VariableType::String => MemAddr::new_disp_s(8, Reg::RBP, self.offset),
+            VariableType::Short | VariableType::UShort => {
+                MemAddr::new_disp_s(2, Reg::RBP, self.offset)
+            }
             VariableType::Bool | VariableType::Char => {
                 MemAddr::new_disp_s(1, Reg::RBP, self.offset)
             }
             VariableType::Any | VariableType::Void => unreachable!(),
-            VariableType::Array(item_vtype, _) => {
-                MemAddr::new_disp_s(item_vtype.item_size(), Reg::RBP, self.offset)
+            VariableType::Array(_, _) | VariableType::Distinct(..) | VariableType::Alias(..) => {
+                MemAddr::new_disp_s(self.vtype.mem_size(), Reg::RBP, self.offset)
+            }
+            VariableType::Struct(_) | VariableType::Slice(_) | VariableType::Tuple(_) => {
+                MemAddr::new_disp(Reg::RBP, self.offset)
             }
-            VariableType::Struct(_) => MemAddr::new_disp_s(8, Reg::RBP, self.offset),
-            VariableType::Float => todo!(),
         }
     }
 
@@ -169,24 +184,97 @@ impl NameSpaceMapping {
     }
 }
 
+/// Reserves aligned stack memory for a value of the type
+/// Returns the offset used for creating the variable map
+pub fn reserve_stack(cc: &mut CompilerContext, vtype: &VariableType) -> usize {
+    let end = cc.mem_offset + vtype.size();
+    let padding = align_to(end, vtype.align()) - end;
+    cc.mem_offset += padding;
+    let offset = cc.mem_offset;
+    cc.codegen.instr2(Sub, RSP, padding + vtype.size());
+    cc.mem_offset += vtype.size();
+    offset
+}
+
 pub fn insert_variable(
     cc: &mut CompilerContext,
     block: &Block,
     var: &VariableDeclare,
     var_base: VariableMapBase,
 ) -> Result<(), CompilationError> {
-    let mut vtype = var.v_type.clone();
+    // Structs are stored inline like any other variable
+    let mut vtype = resolve_type(cc, &var.v_type)?;
     let mut var_base = var_base;
-    // Declare variable memory
-    // No need to do any thing if variable is on the stack
-    if let VariableType::Custom(s) = &vtype {
-        let Some(NSType::Struct(struct_map)) = cc.namespace_map.get(s) else {
-            return Err(CompilationError::UnknownType(s.to_owned()));
+    // Libraries never run the init code so their statics should be constants
+    if let (VariableMapBase::Global(_), Some(init)) = (&var_base, &var.init_value) {
+        if insert_static_data(cc, block, var, &vtype, init)? {
+            return Ok(());
+        }
+        if cc.is_lib() {
+            return Err(CompilationError::NonConstantStatic(var.ident.clone()));
+        }
+    }
+    // Enum variants with payload or type arguments are constructed in place
+    if let Some(Expr {
+        etype: ExprType::EnumVariant(ev),
+        ..
+    }) = &var.init_value
+    {
+        if !ev.args.is_empty() || is_generic_enum(cc, &ev.ident) {
+            return insert_enum_variable(cc, block, var, var_base, &vtype, ev);
+        }
+    }
+    // Large values are stored in a hidden variable before ? takes their value
+    if let Some(Expr {
+        etype: ExprType::Try(value),
+        loc,
+    }) = &var.init_value
+    {
+        if is_call(value) && returns_in_memory(&expr_type(cc, block, value)?) {
+            let ident = format!("$try{}:{}", loc.line, loc.col);
+            let hidden = VariableDeclare {
+                mutable: false,
+                ident: ident.clone(),
+                v_type: VariableType::Any,
+                init_value: Some(*value.clone()),
+                loc: loc.clone(),
+            };
+            insert_variable(cc, block, &hidden, var_base.clone())?;
+            let mut var = var.clone();
+            let value = Expr {
+                etype: ExprType::Variable(ident),
+                loc: loc.clone(),
+            };
+            var.init_value = Some(Expr {
+                etype: ExprType::Try(Box::new(value)),
+                loc: loc.clone(),
+            });
+            return insert_variable(cc, block, &var, var_base);
+        }
+    }
+    // Tuples are stored item by item and calls return values in place
+    if let Some(init) = &var.init_value {
+        let in_place = |vtype: &VariableType| {
+            is_tuple(vtype) || is_literal(init) || (returns_by_value(vtype) && is_call(init))
+        };
+        let inferred =
+            is_call(init) || is_literal(init) || matches!(init.etype, ExprType::Tuple(_));
+        let vtype = match vtype.is_any() && inferred {
+            true => value_type(cc, block, init)?,
+            false => vtype.clone(),
         };
-        let struct_tag = cc.codegen.add_bss_seg(struct_map.size());
-        let mem_acss = memq!(RBP, -(cc.mem_offset as i32 + 8));
-        cc.codegen.instr2(Mov, mem_acss, Opr::Rela(struct_tag));
-        vtype = VariableType::Struct(struct_map.clone());
+        if in_place(&vtype) {
+            return insert_value_variable(cc, block, var, var_base, &vtype, init);
+        }
+    }
+    // Align stack variables to their natural alignment
+    if let (VariableMapBase::Stack(_), false) = (&var_base, vtype.is_any()) {
+        let end = cc.mem_offset + vtype.size();
+        let padding = align_to(end, vtype.align()) - end;
+        if padding > 0 {
+            cc.codegen.instr2(Sub, RSP, padding);
+            cc.mem_offset += padding;
+        }
     }
     // compile initial value
     if var.init_value.is_some() {
@@ -194,22 +282,53 @@ pub fn insert_variable(
         let expro = compile_expr(cc, block, &init_value)?;
         match vtype.cast(&expro.vtype) {
             Ok(vt) => {
+                // Numeric values are converted to the declared type,
+                // float values are truncated when stored in integer variables
+                let vt = if vtype.is_numeric() && expro.vtype.is_numeric() {
+                    vtype.clone()
+                } else {
+                    vt
+                };
+                let mem_size = vt.mem_size();
                 let mem_acss = match &var_base {
-                    VariableMapBase::Stack(_) => MemAddr::new_disp_s(
-                        vt.item_size(),
-                        RBP,
-                        -((cc.mem_offset + vt.size()) as i32),
-                    ),
+                    VariableMapBase::Stack(_) => {
+                        MemAddr::new_disp_s(mem_size, RBP, -((cc.mem_offset + vt.size()) as i32))
+                    }
                     VariableMapBase::Global(_) => {
                         let tag = cc.codegen.add_bss_seg(vt.size());
                         var_base = VariableMapBase::Global(tag.to_string());
-                        MemAddr::new_rela_s(vt.item_size(), tag.to_string())
+                        MemAddr::new_rela_s(mem_size, tag.to_string())
                     }
                 };
-                if expro.value.is_register() {
+                // Enum tags without payload are stored like integers
+                let tag_only = matches!(vt, VariableType::Enum(_)) && !expro.value.is_mem();
+                if (vt.is_aggregate() || matches!(vt, VariableType::Array(..))) && !tag_only {
+                    let Opr::Mem(src) = expro.value else {
+                        return Err(CompilationError::UnmatchingTypes(
+                            vt.to_string(),
+                            expro.vtype.to_string(),
+                        ));
+                    };
+                    match expro.vtype {
+                        VariableType::Array(_, n) if vt != expro.vtype => {
+                            store_array_slice(cc, mem_acss, src, n)
+                        }
+                        _ => copy_memory(cc, mem_acss, src, vt.size()),
+                    }
+                } else if vt == VariableType::Float {
+                    mov_unknown_to_xmm(cc, XMM0, expro.value, &expro.vtype);
+                    cc.codegen.instr2(Movsd, mem_acss, XMM0);
+                } else if expro.vtype == VariableType::Float {
+                    mov_float_to_register(cc, RAX, expro.value);
+                    cc.codegen
+                        .instr2(Mov, mem_acss, RAX.convert(vt.item_size()));
+                } else if expro.value.is_register() {
                     cc.codegen.instr2(Mov, mem_acss, expro.value.sized(&vt));
                 } else {
                     mov_unknown_to_register(cc, RAX, expro.value);
+                    if *expro.vtype.underlying() == VariableType::Int && vt.item_size() == 8 {
+                        cc.codegen.instr2(Movsxd, RAX, EAX);
+                    }
                     cc.codegen
                         .instr2(Mov, mem_acss, RAX.convert(vt.item_size()));
                 }
@@ -224,6 +343,12 @@ pub fn insert_variable(
     if vtype == VariableType::Any {
         return Err(CompilationError::UnknownType(var.ident.to_owned()));
     }
+    if var.init_value.is_none() {
+        if let VariableMapBase::Global(_) = var_base {
+            let tag = cc.codegen.add_bss_seg(vtype.size());
+            var_base = VariableMapBase::Global(tag);
+        }
+    }
     match &var_base {
         VariableMapBase::Stack(_) => {
             let var_map = VariableMap::new(var_base, cc.mem_offset, vtype.clone(), var.mutable);
@@ -238,3 +363,134 @@ pub fn insert_variable(
     }
     Ok(())
 }
+
+/// Places a static with a constant value in the data section
+/// Returns false if the value has to be computed at runtime
+fn insert_static_data(
+    cc: &mut CompilerContext,
+    block: &Block,
+    var: &VariableDeclare,
+    vtype: &VariableType,
+    init: &Expr,
+) -> Result<bool, CompilationError> {
+    let name = format!("static.{}", var.ident);
+    let vtype = if is_literal(init) || matches!(init.etype, ExprType::Tuple(_)) {
+        let vtype = match vtype.is_any() {
+            true => value_type(cc, block, init)?,
+            false => vtype.clone(),
+        };
+        let Some(data) = literal_data(&vtype, init)? else {
+            return Ok(false);
+        };
+        cc.codegen.add_named_data(&name, data, vtype.clone());
+        vtype
+    } else if let Some(f) = const_float(init, vtype) {
+        cc.codegen
+            .add_named_data(&name, f.to_le_bytes().to_vec(), VariableType::Float);
+        VariableType::Float
+    } else {
+        let value = match eval_const_as(init, Some(vtype)) {
+            Ok(value) if vtype.is_any() => value,
+            Ok(value) => match value.with_type(vtype) {
+                Ok(value) => value,
+                Err(_) => return Ok(false),
+            },
+            Err(_) => return Ok(false),
+        };
+        match value {
+            ConstValue::Num(v, t) => {
+                cc.codegen
+                    .add_named_data(&name, v.to_le_bytes()[..t.size()].to_vec(), t.clone());
+                t
+            }
+            ConstValue::Str(s) => {
+                let target = cc
+                    .codegen
+                    .add_data(s.as_bytes().to_vec(), VariableType::String);
+                cc.codegen
+                    .add_data_pointer(&name, &target, VariableType::Pointer);
+                VariableType::String
+            }
+        }
+    };
+    let var_map = VariableMap::new(VariableMapBase::Global(name), 0, vtype, var.mutable);
+    let _ = cc.variables_map.insert(&var.ident, var_map);
+    Ok(true)
+}
+
+/// Value of a float literal, integer constants are converted for float statics
+fn const_float(value: &Expr, vtype: &VariableType) -> Option<f64> {
+    match (&value.etype, vtype) {
+        (ExprType::Float(f), VariableType::Float | VariableType::Any) => Some(*f),
+        (ExprType::Unary(u), VariableType::Float | VariableType::Any) if u.op == Op::Sub => {
+            const_float(&u.right, vtype).map(|f| -f)
+        }
+        (_, VariableType::Float) => match eval_const(value) {
+            Ok(ConstValue::Num(v, t)) if t.is_integer() => Some(v as f64),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn insert_value_variable(
+    cc: &mut CompilerContext,
+    block: &Block,
+    var: &VariableDeclare,
+    var_base: VariableMapBase,
+    vtype: &VariableType,
+    init: &Expr,
+) -> Result<(), CompilationError> {
+    let var_map = match var_base {
+        VariableMapBase::Stack(_) => {
+            let offset = reserve_stack(cc, vtype);
+            let var_map = VariableMap::new(var_base, offset, vtype.clone(), var.mutable);
+            store_value(cc, block, &var_map.mem(), vtype, init)?;
+            var_map
+        }
+        VariableMapBase::Global(_) => {
+            // Relocatable memory can not be displaced so values are stored on the stack first
+            let value = value_operand(cc, block, vtype, init)?;
+            let tag = cc.codegen.add_bss_seg(vtype.size());
+            let var_map =
+                VariableMap::new(VariableMapBase::Global(tag), 0, vtype.clone(), var.mutable);
+            let Opr::Mem(src) = value.value else {
+                unreachable!();
+            };
+            copy_memory(cc, var_map.mem(), src, vtype.size());
+            var_map
+        }
+    };
+    let _ = cc.variables_map.insert(&var.ident, var_map);
+    Ok(())
+}
+
+fn insert_enum_variable(
+    cc: &mut CompilerContext,
+    block: &Block,
+    var: &VariableDeclare,
+    var_base: VariableMapBase,
+    vtype: &VariableType,
+    ev: &EnumVariantExpr,
+) -> Result<(), CompilationError> {
+    let etype = VariableType::Enum(enum_variant(cc, ev, vtype)?.0);
+    if !vtype.is_any() && *vtype != etype {
+        return Err(CompilationError::UnmatchingTypes(
+            vtype.to_string(),
+            etype.to_string(),
+        ));
+    }
+    let var_map = match var_base {
+        VariableMapBase::Stack(_) => {
+            let offset = reserve_stack(cc, &etype);
+            VariableMap::new(var_base, offset, etype, var.mutable)
+        }
+        VariableMapBase::Global(_) => {
+            let tag = cc.codegen.add_bss_seg(etype.size());
+            VariableMap::new(VariableMapBase::Global(tag), 0, etype, var.mutable)
+        }
+    };
+    compile_enum_into(cc, block, ev, &var_map.vtype, var_map.mem(), false)?;
+    let _ = cc.variables_map.insert(&var.ident, var_map);
+    Ok(())
+}
@@ -24,21 +24,38 @@
 **********************************************************************************************/
 use std::{error::Error, fmt::Display, process::exit};
 
-use crate::parser::{expr::Op, types::VariableType};
+use crate::parser::expr::Op;
 
 #[derive(Debug)]
 pub enum CompilationError {
     UndefinedVariable(String),
     UndefinedNameSpace(String),
+    UndefinedStructItem(String, String),
+    MissingStructItem(String, String),
+    UndefinedEnumVariant(String, String),
+    UndefinedMethod(String, String),
+    NonExhaustiveMatch(String),
+    UnreachableMatchArm(String),
     UnknownType(String),
+    RecursiveType(String),
+    InvalidDistinctType(String, String),
     UnexpectedType(String),
     InvalidTypeCasting(String, String),
     InValidBinaryOperation(Op, String, String),
+    InValidUnaryOperation(Op, String),
     FunctionOutOfScope(String),
     InvalidInlineAsm(String),
     ImmutableVariable(String),
-    UnmatchingTypes(VariableType, VariableType),
+    UnmatchingTypes(String, String),
     NotLoopBlock,
+    UndefinedLoopLabel(String),
+    DeferExit,
+    InvalidTypeArguments(String, usize, usize),
+    UninferredTypeParam(String, String),
+    UninferredEnumType(String),
+    ConflictingTypeArgument(String, String, String),
+    NonConstantExpr,
+    NonConstantStatic(String),
     Err(String),
 }
 impl Display for CompilationError {
@@ -46,15 +63,34 @@ impl Display for CompilationError {
         match self {
             Self::UndefinedVariable(v) => write!(f,"Undifiend Variable ({v})"),
             Self::UndefinedNameSpace(v) => write!(f,"Undifiend Name Space ({v})"),
+            Self::UndefinedStructItem(s, i) => write!(f,"Struct ({s}) has no item named ({i})"),
+            Self::MissingStructItem(s, i) => write!(f,"Struct literal of ({s}) is missing item ({i})"),
+            Self::UndefinedEnumVariant(e, v) => write!(f,"Enum ({e}) has no variant named ({v})"),
+            Self::UndefinedMethod(t, m) => write!(f,"Type ({t}) has no method named ({m})"),
+            Self::NonExhaustiveMatch(p) => write!(f,"Match is not exhaustive, missing pattern ({p})"),
+            Self::UnreachableMatchArm(p) => write!(f,"Unreachable match arm ({p})"),
             Self::UnknownType(v) => write!(f,"Unknown vaiable type ({v})"),
+            Self::RecursiveType(v) => write!(f,"Type ({v}) can not contain itself"),
+            Self::InvalidDistinctType(v, t) => write!(f,"Distinct type ({v}) should be an integer type found ({t})"),
             Self::UnexpectedType(t) => write!(f,"Unexpected type ({t})"),
             Self::InvalidTypeCasting(a, b) => write!(f, "Types ({a}) and ({b}) can not be casted to eachother for this operation"),
             Self::InValidBinaryOperation(op, a, b) => write!(f,"Invalid Operation ({op}) on types ({a}) and ({b})"),
+            Self::InValidUnaryOperation(op, a) => write!(f,"Invalid Operation ({op}) on type ({a})"),
             Self::FunctionOutOfScope(s) => write!(f,"Error: Function {s} is not avaliable in this scope. Make sure you are calling the correct function"),
             Self::InvalidInlineAsm(i) => write!(f,"Invalid Identifier for Inline asm instruct ({i})"),
             Self::ImmutableVariable(v) => write!(f,"Variable ({v}) is not mutable. Did you forgot to define it with '=' insted of ':=' ?" ),
             Self::UnmatchingTypes(a, b) => write!(f, "Expected type ({a}), found type ({b})"),
             Self::NotLoopBlock => write!(f, "Can not break or continue out of non-loop blocks!"),
+            Self::UndefinedLoopLabel(label) => {
+                write!(f, "Loop label ({label}) is not defined by an enclosing loop")
+            }
+            Self::DeferExit => write!(f, "Can not return, break or continue out of a defer block"),
+            Self::InvalidTypeArguments(t, n, m) => write!(f, "Type ({t}) expects {n} type arguments but {m} were given"),
+            Self::UninferredEnumType(e) => write!(f, "Type arguments of enum ({e}) can not be inferred, declare the type of the value"),
+            Self::UninferredTypeParam(p, func) => write!(f, "Type parameter ({p}) of function ({func}) can not be inferred from the arguments"),
+            Self::ConflictingTypeArgument(p, a, b) => write!(f, "Type parameter ({p}) can not be both ({a}) and ({b})"),
+            Self::NonConstantExpr => write!(f, "Expression can not be evaluated at compile time"),
+            Self::NonConstantStatic(v) => write!(f, "Static variable ({v}) of a library should be initialized with a constant"),
             Self::Err(e) => write!(f, "{e}"),
         }
     }
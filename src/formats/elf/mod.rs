@@ -19,9 +19,11 @@ pub mod program;
 pub mod sections;
 
 use self::{
-    flags::{STB_GLOBAL, STB_LOCAL, STT_FILE, STT_FUNC, STT_NOTYPE, STT_SECTION, STV_DEFAULT},
+    flags::{
+        STB_GLOBAL, STB_LOCAL, STT_FILE, STT_FUNC, STT_NOTYPE, STT_OBJECT, STT_SECTION, STV_DEFAULT,
+    },
     header::ElfHeader,
-    sections::{NOBITSSec, PROGBITSSec, RELASec, STRTABSec, SYMTABSec, Section, SymItem},
+    sections::{NOBITSSec, PROGBITSSec, RELASec, RelaItem, STRTABSec, SYMTABSec, Section, SymItem},
 };
 
 #[allow(unused)]
@@ -72,6 +74,10 @@ pub fn generate_elf(out_path: &Path, cc: &mut CompilerContext) {
         name: ".rela.text".into(),
         data: vec![],
     };
+    let mut data_rela_map = RELASec {
+        name: ".rela.data".into(),
+        data: vec![],
+    };
     let mut shstrtab = STRTABSec::new(".shstrtab");
     let mut sections = Vec::<Box<dyn Section>>::new();
     for sec in dyn_sections.iter() {
@@ -81,6 +87,7 @@ pub fn generate_elf(out_path: &Path, cc: &mut CompilerContext) {
     shstrtab.insert(".shstrtab");
     shstrtab.insert(".symtab");
     shstrtab.insert(".rela.text");
+    shstrtab.insert(".rela.data");
     shstrtab.insert(".strtab");
     if !cc.codegen.rela_map.is_empty() {
         for item in cc.codegen.rela_map.iter_mut() {
@@ -97,11 +104,29 @@ pub fn generate_elf(out_path: &Path, cc: &mut CompilerContext) {
             rela_map.push(item.to_owned());
         }
     }
+    // Pointers stored in the data section are absolute addresses
+    for item in cc.codegen.data_buf.values() {
+        let Some(target) = &item.reloc else {
+            continue;
+        };
+        let mut rela = RelaItem::new(
+            ".data",
+            SymbolType::DataSec,
+            0x1,
+            item.index as u64,
+            cc.codegen.data_buf[target].index as i64,
+        );
+        rela.r_section = sections.iter().position(|t| t.name() == ".data").unwrap() as u32 + 2;
+        data_rela_map.push(rela);
+    }
     sections.push(Box::new(shstrtab));
     sections.push(Box::new(symtab));
     if !cc.codegen.rela_map.is_empty() {
         sections.push(Box::new(rela_map));
     }
+    if !data_rela_map.is_empty() {
+        sections.push(Box::new(data_rela_map));
+    }
     sections.push(Box::new(strtab));
 
     let elf_sections = ElfSections::new(64 + (64 * (sections.len() + 1)) as u64, sections);
@@ -264,9 +289,11 @@ pub fn set_symbols(
             continue;
         }
         // push symbol info to sym_list
-        let info = match sym.1 == SymbolType::Ffi {
-            true => st_info!(STB_GLOBAL, STT_NOTYPE),
-            false => st_info!(STB_LOCAL, STT_NOTYPE),
+        let (info, size) = match cc.codegen.data_buf.get(label) {
+            Some(item) if sym.1 == SymbolType::DataSec => {
+                (st_info!(STB_LOCAL, STT_OBJECT), item.data.len() as u64)
+            }
+            _ => (st_info!(STB_LOCAL, STT_NOTYPE), 0),
         };
         let shndx_tag = match sym.1 {
             SymbolType::TextSec => ".text",
@@ -284,7 +311,7 @@ pub fn set_symbols(
             st_info: info,
             st_other: st_visibility!(STV_DEFAULT),
             st_shndx: shndx as u16 + 1,
-            st_size: 0,
+            st_size: size,
             st_value: sym.0 as u64,
         });
     }
@@ -302,17 +329,15 @@ pub fn set_symbols(
                     st_value: 0,
                 });
             }
-            NSType::Function(f) => {
-                if f.is_extern {
-                    symtab.insert(SymItem {
-                        st_name: strtab.index(&f.ident).unwrap(),
-                        st_info: st_info!(STB_GLOBAL, STT_FUNC),
-                        st_other: st_visibility!(STV_DEFAULT),
-                        st_shndx: 1,
-                        st_size: 0,
-                        st_value: 0,
-                    });
-                }
+            NSType::Function(f) if f.is_extern => {
+                symtab.insert(SymItem {
+                    st_name: strtab.index(&f.ident).unwrap(),
+                    st_info: st_info!(STB_GLOBAL, STT_FUNC),
+                    st_other: st_visibility!(STV_DEFAULT),
+                    st_shndx: 1,
+                    st_size: 0,
+                    st_value: cc.codegen.symbols_map[&f.ident].0 as u64,
+                });
             }
             _ => (),
         }
@@ -334,7 +334,9 @@ impl PROGBITSSec {
 
     pub fn dmap_to_data(items: &BTreeMap<String, DataItem>) -> IBytes {
         let mut data = Vec::new();
-        for item in items.values() {
+        let mut items = items.values().collect::<Vec<_>>();
+        items.sort_by_key(|x| x.index);
+        for item in items {
             data.extend(item.data.clone());
         }
         data
@@ -706,7 +708,10 @@ impl Section for RELASec {
     }
 
     fn link_and_info(&self) -> (Option<&'static str>, Option<&'static str>) {
-        (Some(".symtab"), Some(".text"))
+        match self.name.as_str() {
+            ".rela.data" => (Some(".symtab"), Some(".data")),
+            _ => (Some(".symtab"), Some(".text")),
+        }
     }
 
     fn padded_size(&self) -> usize {
@@ -25,16 +25,19 @@
 *     3. This notice may not be removed or altered from any source distribution.
 *
 **********************************************************************************************/
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
-use crate::error_handeling::{error, Loc};
+use crate::{
+    error_handeling::{error, Loc},
+    parser::constants::ConstValue,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TokenType {
     /// Identifies a variable or functuin e.g: a, main, print
     Identifier,
     /// Numeric value e.g: 12 ,0xf3, 0b110
-    Int(i32),
+    Int(i64),
     /// Floating value e.g: 0.5
     Float(f64),
     /// Character Literal e.g: 'A', '9', '\n'
@@ -53,8 +56,10 @@ pub enum TokenType {
     Devide,
     /// "%" Modulo
     Mod,
-    /// "!" Binary Not Operation
+    /// "!" Logical Not Operation
     Not,
+    /// "~" Bitwise Not Operation
+    Tilde,
     /// ">" Bigger Compare Oparation
     Bigger,
     /// "<" Smaller Compare Operation
@@ -109,6 +114,16 @@ pub enum TokenType {
     DivEq,
     /// "%=" Assgin and mod to itself
     ModEq,
+    /// "&=" Assgin and bitwise and to itself
+    AndEq,
+    /// "|=" Assgin and bitwise or to itself
+    OrEq,
+    /// "^=" Assgin and bitwise xor to itself
+    XorEq,
+    /// "<<=" Assgin and shift left itself
+    LshEq,
+    /// ">>=" Assgin and shift right itself
+    RshEq,
     /// "<<" Shift Left
     Lsh,
     /// ">>" Shift Right
@@ -121,6 +136,8 @@ pub enum TokenType {
     And,
     /// "|" Bitwise or
     Or,
+    /// "^" Bitwise xor
+    Xor,
     /// "#" NOT DEFINED YET
     Hash,
     /// "?" Question Mark
@@ -131,6 +148,10 @@ pub enum TokenType {
     Colon,
     /// "::" Const variable definition
     DoubleColon,
+    /// "=>" Match arm
+    FatArrow,
+    /// "->" Return type of function types
+    Arrow,
     /// "," Seperating Arguments
     Comma,
     /// "$" Dollar Sign
@@ -161,21 +182,102 @@ pub enum TokenType {
     For,
     // to (range)
     To,
+    // step (range)
+    Step,
+    // in (for each)
+    In,
+    // do (do while)
+    Do,
+    // Infinite Loop
+    Loop,
     // Defer
     Defer,
     // Static
     Static,
     // Extern
     Extern,
+    // Packed
+    Packed,
+    // Enum
+    Enum,
+    // Match
+    Match,
+    // Explicit cast
+    As,
+    // Size of a type
+    Sizeof,
+    // Alignment of a type
+    Alignof,
+    /// Keyword impl
+    Impl,
+    /// Keyword const
+    Const,
+    /// Keyword type
+    Type,
+    /// Keyword distinct
+    Distinct,
 }
 
 impl TokenType {
     pub fn is_assgin_token(&self) -> bool {
         matches!(
             self,
-            Self::Eq | Self::PlusEq | Self::SubEq | Self::MultiEq | Self::DivEq | Self::ModEq
+            Self::Eq
+                | Self::PlusEq
+                | Self::SubEq
+                | Self::MultiEq
+                | Self::DivEq
+                | Self::ModEq
+                | Self::AndEq
+                | Self::OrEq
+                | Self::XorEq
+                | Self::LshEq
+                | Self::RshEq
         )
     }
+
+    /// Checks if a value is expected right after this token
+    pub fn expects_operand(&self) -> bool {
+        self.is_assgin_token()
+            || matches!(
+                self,
+                Self::Plus
+                    | Self::Minus
+                    | Self::Multi
+                    | Self::Devide
+                    | Self::Mod
+                    | Self::Not
+                    | Self::Tilde
+                    | Self::Bigger
+                    | Self::Smaller
+                    | Self::ColonEq
+                    | Self::DoubleEq
+                    | Self::NotEq
+                    | Self::BiggerEq
+                    | Self::SmallerEq
+                    | Self::Lsh
+                    | Self::Rsh
+                    | Self::DoubleAnd
+                    | Self::DoubleOr
+                    | Self::And
+                    | Self::Or
+                    | Self::Xor
+                    | Self::Colon
+                    | Self::DoubleColon
+                    | Self::FatArrow
+                    | Self::Comma
+                    | Self::OParen
+                    | Self::OBracket
+                    | Self::If
+                    | Self::While
+                    | Self::Return
+                    | Self::Print
+                    | Self::Match
+                    | Self::In
+                    | Self::To
+                    | Self::Step
+            )
+    }
 }
 
 impl Display for TokenType {
@@ -193,6 +295,7 @@ impl Display for TokenType {
             TokenType::Devide => write!(f, "/"),
             TokenType::Mod => write!(f, "%"),
             TokenType::Not => write!(f, "!"),
+            TokenType::Tilde => write!(f, "~"),
             TokenType::Bigger => write!(f, ">"),
             TokenType::Smaller => write!(f, "<"),
             TokenType::Func => write!(f, "func"),
@@ -220,15 +323,23 @@ impl Display for TokenType {
             TokenType::MultiEq => write!(f, "*="),
             TokenType::DivEq => write!(f, "/="),
             TokenType::ModEq => write!(f, "%="),
+            TokenType::AndEq => write!(f, "&="),
+            TokenType::OrEq => write!(f, "|="),
+            TokenType::XorEq => write!(f, "^="),
+            TokenType::LshEq => write!(f, "<<="),
+            TokenType::RshEq => write!(f, ">>="),
             TokenType::Lsh => write!(f, "<<"),
             TokenType::Rsh => write!(f, ">>"),
             TokenType::And => write!(f, "&"),
             TokenType::Or => write!(f, "|"),
+            TokenType::Xor => write!(f, "^"),
             TokenType::Hash => write!(f, "#"),
             TokenType::QMark => write!(f, "?"),
             TokenType::SemiColon => write!(f, ";"),
             TokenType::Colon => write!(f, ":"),
             TokenType::DoubleColon => write!(f, "::"),
+            TokenType::FatArrow => write!(f, "=>"),
+            TokenType::Arrow => write!(f, "->"),
             TokenType::Comma => write!(f, ","),
             TokenType::Dollar => write!(f, "$"),
             TokenType::OParen => write!(f, "("),
@@ -246,9 +357,23 @@ impl Display for TokenType {
             TokenType::DoubleAnd => write!(f, "&&"),
             TokenType::For => write!(f, "for"),
             TokenType::To => write!(f, "to"),
+            TokenType::Step => write!(f, "step"),
+            TokenType::In => write!(f, "in"),
+            TokenType::Do => write!(f, "do"),
+            TokenType::Loop => write!(f, "loop"),
             TokenType::Defer => write!(f, "defer"),
             TokenType::Static => write!(f, "static"),
             TokenType::Extern => write!(f, "extern"),
+            TokenType::Packed => write!(f, "packed"),
+            TokenType::Type => write!(f, "type"),
+            TokenType::Distinct => write!(f, "distinct"),
+            TokenType::Enum => write!(f, "enum"),
+            TokenType::Match => write!(f, "match"),
+            TokenType::As => write!(f, "as"),
+            TokenType::Sizeof => write!(f, "sizeof"),
+            TokenType::Alignof => write!(f, "alignof"),
+            TokenType::Impl => write!(f, "impl"),
+            TokenType::Const => write!(f, "const"),
         }
     }
 }
@@ -309,6 +434,10 @@ pub struct Lexer {
     cur: usize,
     bol: usize,
     row: usize,
+    /// Values of the constants declared so far
+    pub constants: HashMap<String, ConstValue>,
+    /// Struct literals are not parsed right before a block e.g: if cond { .. }
+    pub struct_literals: bool,
 }
 
 impl Lexer {
@@ -326,6 +455,8 @@ impl Lexer {
             cur: 0,
             bol: 0,
             row: 0,
+            constants: HashMap::new(),
+            struct_literals: true,
         }
     }
 
@@ -412,16 +543,29 @@ impl Lexer {
 
     /// Scans the next token and sets the current token to the new token
     pub fn next_token(&mut self) -> Token {
-        let token = self._next_token();
+        let token = self._next_token(self.token.t_type);
         self.token = token.clone();
         token
     }
 
+    /// Returns the token after the next `count` tokens without moving the lexer
+    pub fn peek_token(&mut self, count: usize) -> Token {
+        let (cur, bol, row) = (self.cur, self.bol, self.row);
+        let mut token = self.token.clone();
+        for _ in 0..count {
+            token = self._next_token(token.t_type);
+        }
+        (self.cur, self.bol, self.row) = (cur, bol, row);
+        token
+    }
+
     /// Scans the next token
-    fn _next_token(&mut self) -> Token {
+    /// "~" is bitwise not where an operand is expected after the previous token
+    /// e.g: x = ~y, (~x), otherwise it starts a comment
+    fn _next_token(&mut self, prev: TokenType) -> Token {
         self.trim_left();
         while !self.is_empty() {
-            if self.source[self.cur] == '~' {
+            if self.source[self.cur] == '~' && !prev.expects_operand() {
                 self.drop_line();
                 self.trim_left();
             } else {
@@ -474,6 +618,20 @@ impl Lexer {
                 if Self::is_single_char_token(next).is_some() {
                     if let Some(dtt) = Self::is_double_char_token(first, next) {
                         self.drop();
+                        // Shifts followed by "=" are compound assignments
+                        let shift_eq = match dtt {
+                            TokenType::Lsh => TokenType::LshEq,
+                            TokenType::Rsh => TokenType::RshEq,
+                            _ => return Token::new(dtt, String::from_iter(vec![first, next]), loc),
+                        };
+                        if !self.is_empty() && self.source[self.cur] == '=' {
+                            self.drop();
+                            return Token::new(
+                                shift_eq,
+                                String::from_iter(vec![first, next, '=']),
+                                loc,
+                            );
+                        }
                         return Token::new(dtt, String::from_iter(vec![first, next]), loc);
                     }
                 }
@@ -629,9 +787,23 @@ impl Lexer {
             "ffi" => Some(TokenType::Ffi),
             "for" => Some(TokenType::For),
             "to" => Some(TokenType::To),
+            "step" => Some(TokenType::Step),
+            "in" => Some(TokenType::In),
+            "do" => Some(TokenType::Do),
+            "loop" => Some(TokenType::Loop),
             "defer" => Some(TokenType::Defer),
             "static" => Some(TokenType::Static),
             "extern" => Some(TokenType::Extern),
+            "packed" => Some(TokenType::Packed),
+            "type" => Some(TokenType::Type),
+            "distinct" => Some(TokenType::Distinct),
+            "enum" => Some(TokenType::Enum),
+            "match" => Some(TokenType::Match),
+            "as" => Some(TokenType::As),
+            "sizeof" => Some(TokenType::Sizeof),
+            "alignof" => Some(TokenType::Alignof),
+            "impl" => Some(TokenType::Impl),
+            "const" => Some(TokenType::Const),
             _ => None,
         }
     }
@@ -669,6 +841,8 @@ impl Lexer {
             '%' => Some(TokenType::Mod),
             '&' => Some(TokenType::And),
             '|' => Some(TokenType::Or),
+            '^' => Some(TokenType::Xor),
+            '~' => Some(TokenType::Tilde),
             _ => None,
         }
     }
@@ -689,9 +863,14 @@ impl Lexer {
             "*=" => Some(TokenType::MultiEq),
             "/=" => Some(TokenType::DivEq),
             "%=" => Some(TokenType::ModEq),
+            "&=" => Some(TokenType::AndEq),
+            "|=" => Some(TokenType::OrEq),
+            "^=" => Some(TokenType::XorEq),
             "==" => Some(TokenType::DoubleEq),
             ":=" => Some(TokenType::ColonEq),
             "::" => Some(TokenType::DoubleColon),
+            "=>" => Some(TokenType::FatArrow),
+            "->" => Some(TokenType::Arrow),
             "!=" => Some(TokenType::NotEq),
             ">=" => Some(TokenType::BiggerEq),
             "<=" => Some(TokenType::SmallerEq),
@@ -713,41 +892,40 @@ impl Lexer {
         // 0x001 0xff 0b0010
         let loc = self.get_current_loc();
         let mut lit_chars = literal.chars();
-        if literal.contains('x') {
+        // Literals up to u64::MAX are allowed and keep their bits in an i64
+        let (radix, digits) = if literal.contains('x') {
             self.expect_char(&lit_chars.next(), vec!['0']);
             self.expect_char(&lit_chars.next(), vec!['x']);
-            let mut value: i64 = 0;
-            for ch in lit_chars {
-                let digit = ch.to_digit(16).unwrap_or_else(|| {
-                    error(
-                        format!("Unknown character in parsing ({})", literal),
-                        loc.clone(),
-                    );
-                });
-                value = (value * 16i64) + digit as i64;
-            }
-            TokenType::Int(value as i32)
+            (16, lit_chars)
         } else if literal.contains('b') {
             self.expect_char(&lit_chars.next(), vec!['0']);
             self.expect_char(&lit_chars.next(), vec!['b']);
-            let mut value: i32 = 0;
-            for ch in lit_chars {
-                let digit = ch.to_digit(2).unwrap_or_else(|| {
+            (2, lit_chars)
+        } else if literal.contains('.') {
+            let value: f64 = literal.parse::<f64>().unwrap();
+            return TokenType::Float(value);
+        } else {
+            (10, lit_chars)
+        };
+        let mut value: u64 = 0;
+        for ch in digits {
+            let digit = ch.to_digit(radix).unwrap_or_else(|| {
+                error(
+                    format!("Unknown character in parsing ({})", literal),
+                    loc.clone(),
+                );
+            });
+            value = value
+                .checked_mul(radix as u64)
+                .and_then(|v| v.checked_add(digit as u64))
+                .unwrap_or_else(|| {
                     error(
-                        format!("Unknown character in parsing ({})", literal),
+                        format!("Integer literal ({}) does not fit in 64 bits", literal),
                         loc.clone(),
                     );
                 });
-                value = (value * 2i32) + digit as i32;
-            }
-            TokenType::Int(value)
-        } else if literal.contains('.') {
-            let value: f64 = literal.parse::<f64>().unwrap();
-            TokenType::Float(value)
-        } else {
-            let value: i32 = literal.parse::<i32>().unwrap();
-            TokenType::Int(value)
         }
+        TokenType::Int(value as i64)
     }
 
     /// Returns char if exits in a list
@@ -791,3 +969,10 @@ fn string_literal_escape_seq() {
     let mut lexer = Lexer::new(String::new(), "\"\\\"test\\\"\"".to_string());
     assert_eq!(lexer.tokenize_string_literal().t_type, TokenType::String);
 }
+
+#[test]
+fn wide_int_tokens() {
+    let mut lexer = Lexer::new(String::new(), "5000000000 0xFFFFFFFFFFFFFFFF".to_string());
+    assert_eq!(lexer.next_token().t_type, TokenType::Int(5000000000));
+    assert_eq!(lexer.next_token().t_type, TokenType::Int(-1));
+}
@@ -86,6 +86,7 @@ pub struct CompilerOptions {
     pub linker_flags: Vec<String>,
     pub use_libc: bool,
     pub create_bin: bool,
+    pub no_bounds_check: bool,
     pub target_platform: u8,
 }
 
@@ -136,6 +137,10 @@ pub fn help_command(program_name: &str) {
         "  {} Use C library Dynamicaly",
         padding_right("--use-libc", 20)
     );
+    println!(
+        "  {} Do not check array bounds at runtime",
+        padding_right("--no-bounds-check", 20)
+    );
     println!(
         "  {} Search for library LIBNAME",
         padding_right("-l<LIBNAME>", 20)
@@ -292,10 +297,7 @@ fn collect_compiler_options(args: &mut Args) -> (String, CompilerOptions) {
     }
     let compiler_path = args.next().unwrap();
     let mut input_path = String::new();
-    loop {
-        let Some(arg) = args.next() else {
-            break;
-        };
+    while let Some(arg) = args.next() {
         if arg.starts_with("-l") || arg.starts_with("-L") {
             co.linker_flags.push(arg.clone());
             continue;
@@ -322,6 +324,7 @@ fn collect_compiler_options(args: &mut Args) -> (String, CompilerOptions) {
             "--keep-asm" => co.keep_asm = true,
             "--keep-obj" => co.keep_obj = true,
             "--use-libc" => co.use_libc = true,
+            "--no-bounds-check" => co.no_bounds_check = true,
             "--lib" => {
                 co.static_lib = true;
                 co.dynamic_lib = false;
@@ -30,32 +30,53 @@ impl ExprOpr {
                 }
                 m.get_register() != Reg::RBP || m.get_s_register().is_some()
             }
-            Opr::R64(_) | Opr::R32(_) | Opr::R16(_) | Opr::R8(_) => true,
+            Opr::R64(_) | Opr::R32(_) | Opr::R16(_) | Opr::R8(_) | Opr::Xmm(_) => true,
         }
     }
 }
 
+/// Truncates a folded value to the width of its integer type
+fn wrap_to_width(val: i64, vtype: &VariableType) -> i64 {
+    match vtype.underlying() {
+        VariableType::Int => val as i32 as i64,
+        VariableType::UInt => val as u32 as i64,
+        VariableType::Short => val as i16 as i64,
+        VariableType::UShort => val as u16 as i64,
+        VariableType::Char => val as u8 as i64,
+        _ => val,
+    }
+}
+
 pub fn fold_binary_expr(
     left: &ExprOpr,
     right: &ExprOpr,
     op: &Op,
 ) -> Result<ExprOpr, CompilationError> {
-    let res_type = left.vtype.cast(&right.vtype)?;
-    let l_val = left.value.get_literal_value();
-    let r_val = right.value.get_literal_value();
+    let res_type = left.vtype.promote(&right.vtype)?;
+    let unsigned = res_type.is_unsigned();
+    let bits = if res_type.size() == 8 { 64 } else { 32 };
+    let l_val = wrap_to_width(left.value.get_literal_value(), &res_type);
+    let r_val = wrap_to_width(right.value.get_literal_value(), &res_type);
+    if matches!(op, Op::Devide | Op::Mod) && r_val == 0 {
+        return Err(CompilationError::Err("Division by zero".to_string()));
+    }
     let val = match op {
-        Op::Plus => l_val + r_val,
-        Op::Sub => l_val - r_val,
-        Op::Multi => l_val * r_val,
-        Op::Devide => l_val / r_val,
-        Op::Mod => l_val % r_val,
+        Op::Plus => l_val.wrapping_add(r_val),
+        Op::Sub => l_val.wrapping_sub(r_val),
+        Op::Multi => l_val.wrapping_mul(r_val),
+        Op::Devide if unsigned => (l_val as u64 / r_val as u64) as i64,
+        Op::Devide => l_val.wrapping_div(r_val),
+        Op::Mod if unsigned => (l_val as u64 % r_val as u64) as i64,
+        Op::Mod => l_val.wrapping_rem(r_val),
         Op::And => l_val & r_val,
         Op::Or => l_val | r_val,
-        Op::Lsh => l_val << r_val,
-        Op::Rsh => l_val >> r_val,
+        Op::Xor => l_val ^ r_val,
+        Op::Lsh => l_val.wrapping_shl(r_val as u32 % bits),
+        Op::Rsh if unsigned => ((l_val as u64) >> (r_val as u32 % bits)) as i64,
+        Op::Rsh => l_val >> (r_val as u32 % bits),
         Op::LogicalOr => ((l_val != 0) || (r_val != 0)) as i64,
         Op::LogicalAnd => ((l_val != 0) && (r_val != 0)) as i64,
-        Op::Not => {
+        Op::Not | Op::BitNot => {
             return Err(CompilationError::InValidBinaryOperation(
                 op.to_owned(),
                 left.vtype.to_string(),
@@ -63,7 +84,62 @@ pub fn fold_binary_expr(
             ));
         }
     };
-    Ok(ExprOpr::new(val, res_type))
+    Ok(ExprOpr::new(wrap_to_width(val, &res_type), res_type))
+}
+
+/// Value of a folded integer, 64-bit unsigned values are stored as i64
+pub fn wide_value(val: i64, vtype: &VariableType) -> i128 {
+    match vtype.underlying() {
+        VariableType::ULong => val as u64 as i128,
+        _ => val as i128,
+    }
+}
+
+/// Checks that a folded value can be held by its integer type
+pub fn check_range(val: i128, vtype: &VariableType) -> Result<i64, CompilationError> {
+    let (min, max) = match vtype.underlying() {
+        VariableType::Int => (i32::MIN as i128, i32::MAX as i128),
+        VariableType::UInt => (0, u32::MAX as i128),
+        VariableType::Short => (i16::MIN as i128, i16::MAX as i128),
+        VariableType::UShort => (0, u16::MAX as i128),
+        VariableType::Long => (i64::MIN as i128, i64::MAX as i128),
+        VariableType::ULong => (0, u64::MAX as i128),
+        VariableType::Char => (0, u8::MAX as i128),
+        _ => return Ok(val as i64),
+    };
+    if val < min || val > max {
+        return Err(CompilationError::Err(format!(
+            "Constant value ({val}) overflows type ({vtype})"
+        )));
+    }
+    Ok(val as i64)
+}
+
+/// Folds a binary expression of a constant declaration,
+/// values that do not fit the type are errors instead of wrapping around
+pub fn fold_const_binary_expr(
+    left: &ExprOpr,
+    right: &ExprOpr,
+    op: &Op,
+) -> Result<ExprOpr, CompilationError> {
+    let res = fold_binary_expr(left, right, op)?;
+    let bits = res.vtype.size() as i128 * 8;
+    let l_val = wide_value(left.value.get_literal_value(), &left.vtype);
+    let r_val = wide_value(right.value.get_literal_value(), &right.vtype);
+    let val = match op {
+        Op::Plus => l_val + r_val,
+        Op::Sub => l_val - r_val,
+        Op::Multi => l_val * r_val,
+        Op::Lsh | Op::Rsh if !(0..bits).contains(&r_val) => {
+            return Err(CompilationError::Err(format!(
+                "Shift count ({r_val}) should be less than the width of type ({})",
+                res.vtype
+            )));
+        }
+        _ => return Ok(res),
+    };
+    check_range(val, &res.vtype)?;
+    Ok(res)
 }
 
 pub fn fold_compare_expr(
@@ -71,29 +147,53 @@ pub fn fold_compare_expr(
     right: &ExprOpr,
     op: &CompareOp,
 ) -> Result<ExprOpr, CompilationError> {
-    let _ = left.vtype.cast(&right.vtype)?;
-    let l_val = left.value.get_literal_value();
-    let r_val = right.value.get_literal_value();
+    let vtype = left.vtype.promote(&right.vtype)?;
+    let l_val = wrap_to_width(left.value.get_literal_value(), &vtype);
+    let r_val = wrap_to_width(right.value.get_literal_value(), &vtype);
+    let ord = if vtype.is_unsigned() {
+        (l_val as u64).cmp(&(r_val as u64))
+    } else {
+        l_val.cmp(&r_val)
+    };
     let val = match op {
-        CompareOp::Eq => (l_val == r_val) as i32,
-        CompareOp::NotEq => (l_val != r_val) as i32,
-        CompareOp::Bigger => (l_val > r_val) as i32,
-        CompareOp::Smaller => (l_val < r_val) as i32,
-        CompareOp::BiggerEq => (l_val >= r_val) as i32,
-        CompareOp::SmallerEq => (l_val <= r_val) as i32,
+        CompareOp::Eq => ord.is_eq(),
+        CompareOp::NotEq => ord.is_ne(),
+        CompareOp::Bigger => ord.is_gt(),
+        CompareOp::Smaller => ord.is_lt(),
+        CompareOp::BiggerEq => ord.is_ge(),
+        CompareOp::SmallerEq => ord.is_le(),
     };
-    Ok(ExprOpr::new(val, VariableType::Bool))
+    Ok(ExprOpr::new(val as i32, VariableType::Bool))
 }
 
 pub fn fold_unary_expr(left: &ExprOpr, op: &Op) -> Result<ExprOpr, CompilationError> {
     let l_val = left.value.get_literal_value();
     let val = match op {
-        Op::Sub => -l_val,
+        Op::Sub => l_val.wrapping_neg(),
         Op::Plus => l_val,
-        Op::Not => !l_val,
+        Op::Not if left.vtype == VariableType::Bool => (l_val == 0) as i64,
+        Op::BitNot if left.vtype.is_integer() => !l_val,
+        Op::Not | Op::BitNot => {
+            return Err(CompilationError::InValidUnaryOperation(
+                op.to_owned(),
+                left.vtype.to_string(),
+            ));
+        }
         _ => {
             unreachable!();
         }
     };
-    Ok(ExprOpr::new(val, left.vtype.to_owned()))
+    Ok(ExprOpr::new(
+        wrap_to_width(val, &left.vtype),
+        left.vtype.to_owned(),
+    ))
+}
+
+/// Converts an integer literal to another integer type
+pub fn fold_cast(value: &ExprOpr, target: &VariableType) -> Option<ExprOpr> {
+    if !value.value.is_literal() || !value.vtype.is_integer() || !target.is_integer() {
+        return None;
+    }
+    let val = wrap_to_width(value.value.get_literal_value(), target);
+    Some(ExprOpr::new(val, target.clone()))
 }
@@ -28,7 +28,7 @@ use crate::{
 };
 
 use super::{
-    expr::{expr, Expr},
+    expr::{expr, Expr, Op},
     stmt::{Stmt, StmtType},
 };
 
@@ -61,6 +61,16 @@ pub enum AssignOp {
     DevideEq,
     /// %= move the modulo to current memory
     ModEq,
+    /// &= bitwise and with the current memory
+    AndEq,
+    /// |= bitwise or with the current memory
+    OrEq,
+    /// ^= bitwise xor with the current memory
+    XorEq,
+    /// <<= shift the current memory to left
+    LshEq,
+    /// >>= shift the current memory to right
+    RshEq,
 }
 impl AssignOp {
     /// Convert TokenType to AssignOp
@@ -72,11 +82,34 @@ impl AssignOp {
             TokenType::MultiEq => Self::MultiEq,
             TokenType::DivEq => Self::DevideEq,
             TokenType::ModEq => Self::ModEq,
+            TokenType::AndEq => Self::AndEq,
+            TokenType::OrEq => Self::OrEq,
+            TokenType::XorEq => Self::XorEq,
+            TokenType::LshEq => Self::LshEq,
+            TokenType::RshEq => Self::RshEq,
             _ => {
                 unreachable!();
             }
         }
     }
+
+    /// Binary operation applied to the current memory
+    /// Returns None for a plain assginment
+    pub fn binary_op(&self) -> Option<Op> {
+        match self {
+            Self::Eq => None,
+            Self::PlusEq => Some(Op::Plus),
+            Self::SubEq => Some(Op::Sub),
+            Self::MultiEq => Some(Op::Multi),
+            Self::DevideEq => Some(Op::Devide),
+            Self::ModEq => Some(Op::Mod),
+            Self::AndEq => Some(Op::And),
+            Self::OrEq => Some(Op::Or),
+            Self::XorEq => Some(Op::Xor),
+            Self::LshEq => Some(Op::Lsh),
+            Self::RshEq => Some(Op::Rsh),
+        }
+    }
 }
 
 /// parse assignemts
@@ -97,14 +130,14 @@ pub fn assign(lexer: &mut Lexer) -> Stmt {
         lexer.match_token(token_type);
         let right_expr = expr(lexer);
         lexer.match_token(TokenType::SemiColon);
-        return Stmt {
+        Stmt {
             stype: StmtType::Assign(Assign {
                 left: left_expr,
                 right: right_expr,
                 op: op_type,
             }),
             loc,
-        };
+        }
     } else {
         error(
             format!("Expected Semicolon found ({})", lexer.get_token_type()),
@@ -23,7 +23,7 @@
 *
 **********************************************************************************************/
 use crate::{
-    error_handeling::CompilationError,
+    error_handeling::{error, CompilationError},
     lexer::{Lexer, TokenType},
     parser::stmt::Stmt,
 };
@@ -32,8 +32,8 @@ use super::{
     assign::assign,
     expr::expr,
     preprocessing::parse_pre_functions,
-    stmt::{for_loop, if_stmt, while_stmt, StmtType},
-    variable_decl::variable_declare,
+    stmt::{do_while_stmt, for_loop, if_stmt, loop_label, match_stmt, while_stmt, StmtType},
+    variable_decl::variable_declares,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -50,21 +50,37 @@ pub fn parse_statement_outside_of_block(lexer: &mut Lexer, master: &String) -> V
     block.parse_stmt(lexer)
 }
 
+/// Id of the innermost loop block that contains the block
 pub fn get_last_loop_block_id(child_id: &str) -> Result<String, CompilationError> {
-    let mut last_index = 0;
-    let mut reached_loop = false;
-    for (i, chr) in child_id.chars().enumerate() {
-        if chr == '.' && reached_loop {
-            last_index = i
-        }
-        if chr == '$' {
-            reached_loop = true;
+    let Some(loop_index) = child_id.rfind('$') else {
+        return Err(CompilationError::NotLoopBlock);
+    };
+    let end = child_id[loop_index..]
+        .find('.')
+        .map_or(child_id.len(), |i| loop_index + i);
+    Ok(child_id[..end].to_string())
+}
+
+/// Id of the innermost loop block with the label that contains the block
+/// Labeled loops keep their label at the end of their id e.g: main.$0_outer
+pub fn get_labeled_loop_block_id(child_id: &str, label: &str) -> Result<String, CompilationError> {
+    let mut end = None;
+    let mut index = 0;
+    for segment in child_id.split('.') {
+        index += segment.len();
+        let loop_label = segment
+            .strip_prefix('$')
+            .map(|s| s.trim_start_matches(|c: char| c.is_ascii_digit()))
+            .and_then(|s| s.strip_prefix('_'));
+        if loop_label == Some(label) {
+            end = Some(index);
         }
+        index += 1;
     }
-    if last_index == 0 {
-        return Err(CompilationError::NotLoopBlock);
+    match end {
+        Some(end) => Ok(child_id[..end].to_string()),
+        None => Err(CompilationError::UndefinedLoopLabel(label.to_owned())),
     }
-    Ok(child_id.split_at(last_index).0.to_string())
 }
 
 pub fn get_first_block_id(child_id: &str) -> String {
@@ -94,7 +110,6 @@ pub fn get_parent_id(child_id: &str) -> String {
 pub struct Block {
     pub stmts: Vec<Stmt>,
     pub btype: BlockType,
-    pub defer_stmts: Vec<Stmt>,
     pub id: String,
     num_of_children: i32,
 }
@@ -109,7 +124,6 @@ impl Block {
         Self {
             num_of_children: 0,
             stmts: Vec::new(),
-            defer_stmts: Vec::new(),
             btype,
             id,
         }
@@ -121,7 +135,6 @@ impl Block {
             btype: BlockType::UnScoped,
             num_of_children: 0,
             stmts: Vec::new(),
-            defer_stmts: Vec::new(),
         }
     }
 
@@ -129,24 +142,52 @@ impl Block {
         Self {
             num_of_children: 0,
             stmts: Vec::new(),
-            defer_stmts: Vec::new(),
             btype,
             id: ident,
         }
     }
 
-    #[allow(dead_code)]
+    /// Moves the block and all of its children under a new master block
+    pub fn rename_master(&mut self, ident: &str) {
+        self.rename_blocks(&|id| match id.split_once('.') {
+            Some((_, rest)) => format!("{ident}.{rest}"),
+            None => ident.to_owned(),
+        });
+    }
+
+    /// Moves the block and all of its children from one parent to another
+    pub fn rename_parent(&mut self, parent: &str, new_parent: &str) {
+        self.rename_blocks(&|id| match id.strip_prefix(parent) {
+            Some(rest) => format!("{new_parent}{rest}"),
+            None => id.to_owned(),
+        });
+    }
+
+    pub fn rename_blocks(&mut self, rename: &dyn Fn(&str) -> String) {
+        self.id = rename(&self.id);
+        for stmt in self.stmts.iter_mut() {
+            stmt.rename_blocks(rename);
+        }
+    }
+
     pub fn master_start_name(&self) -> String {
         get_first_block_id(&self.id)
     }
     pub fn master_end_name(&self) -> String {
         format!("{}.Defer", get_first_block_id(&self.id))
     }
-    pub fn last_loop_start_name(&self) -> Result<String, CompilationError> {
-        Ok(format!("{}.BS__", get_last_loop_block_id(&self.id)?))
+    /// Id of the loop that is left by break or continue,
+    /// the innermost loop is used if no label is given
+    pub fn loop_block_id(&self, label: &Option<String>) -> Result<String, CompilationError> {
+        match label {
+            Some(label) => get_labeled_loop_block_id(&self.id, label),
+            None => get_last_loop_block_id(&self.id),
+        }
     }
-    pub fn last_loop_end_name(&self) -> Result<String, CompilationError> {
-        Ok(format!("{}.BE__", get_last_loop_block_id(&self.id)?))
+    /// Adds a label to the id of a loop block and its children
+    pub fn set_loop_label(&mut self, label: &str) {
+        let id = self.id.clone();
+        self.rename_parent(&id, &format!("{id}_{label}"));
     }
     #[allow(dead_code)]
     pub fn parent_start_name(&self) -> String {
@@ -194,12 +235,15 @@ impl Block {
             }
             TokenType::Var => {
                 let loc = lexer.get_token_loc();
-                let stmt = vec![Stmt {
-                    stype: StmtType::VariableDecl(variable_declare(lexer)),
-                    loc,
-                }];
+                let stmts = variable_declares(lexer)
+                    .into_iter()
+                    .map(|decl| Stmt {
+                        stype: StmtType::VariableDecl(decl),
+                        loc: loc.clone(),
+                    })
+                    .collect();
                 lexer.match_token(TokenType::SemiColon);
-                stmt
+                stmts
             }
             TokenType::Print => {
                 let loc = lexer.get_token_loc();
@@ -216,7 +260,7 @@ impl Block {
                 let loc = lexer.get_token_loc();
                 lexer.match_token(TokenType::Break);
                 let stmt = vec![Stmt {
-                    stype: StmtType::Break,
+                    stype: StmtType::Break(loop_label(lexer)),
                     loc,
                 }];
                 lexer.match_token(TokenType::SemiColon);
@@ -226,7 +270,7 @@ impl Block {
                 let loc = lexer.get_token_loc();
                 lexer.match_token(TokenType::Continue);
                 let stmt = vec![Stmt {
-                    stype: StmtType::Continue,
+                    stype: StmtType::Continue(loop_label(lexer)),
                     loc,
                 }];
                 lexer.match_token(TokenType::SemiColon);
@@ -239,6 +283,13 @@ impl Block {
                     loc,
                 }]
             }
+            TokenType::Match => {
+                let loc = lexer.get_token_loc();
+                vec![Stmt {
+                    stype: StmtType::Match(match_stmt(lexer, self)),
+                    loc,
+                }]
+            }
             TokenType::While => {
                 let loc = lexer.get_token_loc();
                 vec![Stmt {
@@ -253,6 +304,49 @@ impl Block {
                     loc,
                 }]
             }
+            TokenType::Do => {
+                let loc = lexer.get_token_loc();
+                vec![Stmt {
+                    stype: StmtType::DoWhile(do_while_stmt(lexer, self)),
+                    loc,
+                }]
+            }
+            TokenType::Loop => {
+                let loc = lexer.get_token_loc();
+                lexer.match_token(TokenType::Loop);
+                let mut block = Block::new(self, BlockType::Loop);
+                block.parse_block(lexer);
+                vec![Stmt {
+                    stype: StmtType::Loop(block),
+                    loc,
+                }]
+            }
+            // Labeled loop
+            TokenType::Identifier if lexer.peek_token(1).t_type == TokenType::Colon => {
+                let loc = lexer.get_token_loc();
+                let label = lexer.get_token().literal;
+                lexer.match_token(TokenType::Identifier);
+                lexer.match_token(TokenType::Colon);
+                let mut stmts = match lexer.get_token_type() {
+                    TokenType::While | TokenType::For | TokenType::Do | TokenType::Loop => {
+                        self.parse_stmt(lexer)
+                    }
+                    _ => error(
+                        format!(
+                            "Only loops can be labeled found ({})",
+                            lexer.get_token_type()
+                        ),
+                        loc,
+                    ),
+                };
+                match &mut stmts[0].stype {
+                    StmtType::While(w) | StmtType::DoWhile(w) => w.block.set_loop_label(&label),
+                    StmtType::ForLoop(f) => f.block.set_loop_label(&label),
+                    StmtType::Loop(b) => b.set_loop_label(&label),
+                    _ => unreachable!(),
+                }
+                stmts
+            }
             TokenType::Return => {
                 let loc = lexer.get_token_loc();
                 lexer.match_token(TokenType::Return);
@@ -263,7 +357,7 @@ impl Block {
                 lexer.match_token(TokenType::SemiColon);
                 stmt
             }
-            TokenType::Identifier => {
+            TokenType::Identifier | TokenType::Multi | TokenType::OParen => {
                 //Assgin Op
                 vec![assign(lexer)]
             }
@@ -283,8 +377,9 @@ impl Block {
                 }]
             }
             TokenType::Defer => {
+                let loc = lexer.get_token_loc();
                 lexer.match_token(TokenType::Defer);
-                if lexer.get_token_type() == TokenType::OCurly {
+                let stmts = if lexer.get_token_type() == TokenType::OCurly {
                     lexer.match_token(TokenType::OCurly);
                     let mut stmts = Vec::<Stmt>::new();
                     loop {
@@ -294,24 +389,29 @@ impl Block {
                         stmts.append(&mut self.parse_stmt(lexer));
                     }
                     lexer.match_token(TokenType::CCurly);
-                    self.defer_stmts.append(&mut stmts);
-                    vec![]
+                    stmts
                 } else {
-                    let mut stmt = self.parse_stmt(lexer);
-                    self.defer_stmts.append(&mut stmt);
-                    vec![]
-                }
-            }
-            _ => {
-                todo!();
+                    self.parse_stmt(lexer)
+                };
+                vec![Stmt {
+                    stype: StmtType::Defer(stmts),
+                    loc,
+                }]
             }
+            _ => error(
+                format!(
+                    "Unexpected Token ({}) at the start of a statement",
+                    lexer.get_token_type()
+                ),
+                lexer.get_token_loc(),
+            ),
         }
     }
 
     /// Parse Blocks
     /// # Argumenrs
     /// * lexer - address of mutable lexer
-    ///     Returns a vec of stmts
+    ///   Returns a vec of stmts
     pub fn parse_block(&mut self, lexer: &mut Lexer) {
         lexer.match_token(TokenType::OCurly);
         let mut stmts = Vec::<Stmt>::new();
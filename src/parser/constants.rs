@@ -0,0 +1,212 @@
+/**********************************************************************************************
+*
+*   parser/constants: parsing and compile time evaluation of constants
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use crate::{
+    error_handeling::{error, CompilationError, Loc},
+    lexer::{Lexer, TokenType},
+    optim::{
+        check_range, fold_cast, fold_compare_expr, fold_const_binary_expr, fold_unary_expr, ExprOpr,
+    },
+};
+
+use super::{
+    expr::{expr, CompareOp, Expr, ExprType, Op},
+    types::{type_def, VariableType},
+};
+
+/// Value of a constant
+/// * Num - integers, chars and bools with their type
+/// * Str - strings
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Num(i64, VariableType),
+    Str(String),
+}
+
+impl ConstValue {
+    pub fn vtype(&self) -> VariableType {
+        match self {
+            Self::Num(_, t) => t.clone(),
+            Self::Str(_) => VariableType::String,
+        }
+    }
+
+    /// Literal expression that replaces the constant
+    pub fn to_expr(&self, loc: Loc) -> Expr {
+        let etype = match self {
+            Self::Str(s) => ExprType::String(s.clone()),
+            Self::Num(v, VariableType::Bool) => ExprType::Bool(*v as u8),
+            Self::Num(v, VariableType::Char) => ExprType::Char(*v as u8),
+            Self::Num(v, VariableType::Int) if *v >= 0 => ExprType::Int(*v),
+            Self::Num(v, t) => ExprType::Cast(
+                Box::new(Expr {
+                    etype: ExprType::Int(*v),
+                    loc: loc.clone(),
+                }),
+                Box::new(t.clone()),
+            ),
+        };
+        Expr { etype, loc }
+    }
+
+    /// Converts the value to the declared type of the constant
+    pub fn with_type(self, vtype: &VariableType) -> Result<Self, CompilationError> {
+        match (&self, vtype) {
+            (Self::Str(_), VariableType::String) => Ok(self),
+            (Self::Num(_, VariableType::Bool), VariableType::Bool) => Ok(self),
+            (Self::Num(v, t), _) if t.is_integer() && vtype.is_integer() => {
+                let value = fold_cast(&ExprOpr::new(*v, t.clone()), vtype).unwrap();
+                Ok(Self::Num(value.value.get_literal_value(), vtype.clone()))
+            }
+            _ => Err(CompilationError::UnmatchingTypes(
+                vtype.to_string(),
+                self.vtype().to_string(),
+            )),
+        }
+    }
+}
+
+/// Parse constant definition
+/// The value is evaluated right away so it can be used by the rest of the file
+///
+/// Syntax:
+/// const NAME @type = expr;
+pub fn const_def(lexer: &mut Lexer) -> (String, ConstValue) {
+    lexer.match_token(TokenType::Const);
+    let ident = lexer.get_token().literal;
+    lexer.match_token(TokenType::Identifier);
+    let v_type = match lexer.get_token_type() {
+        TokenType::ATSign => Some(type_def(lexer)),
+        _ => None,
+    };
+    let loc = lexer.get_token_loc();
+    match lexer.get_token_type() {
+        TokenType::Eq | TokenType::ColonEq => {
+            lexer.next_token();
+        }
+        _ => error(
+            format!("Expected \"=\" found ({})", lexer.get_token_type()),
+            loc,
+        ),
+    }
+    let value_expr = expr(lexer);
+    lexer.match_token(TokenType::SemiColon);
+    let mut value = eval_const_as(&value_expr, v_type.as_ref())
+        .unwrap_or_else(|e| error(e, value_expr.loc.clone()));
+    if let Some(vtype) = v_type {
+        value = value
+            .with_type(&vtype)
+            .unwrap_or_else(|e| error(e, value_expr.loc.clone()));
+    }
+    lexer.constants.insert(ident.clone(), value.clone());
+    (ident, value)
+}
+
+/// Evaluates an expression of literals and constants
+pub fn eval_const(expr: &Expr) -> Result<ConstValue, CompilationError> {
+    eval_const_as(expr, None)
+}
+
+/// Evaluates an expression of literals and constants,
+/// integer literals take the given type so the value is computed in its width
+pub fn eval_const_as(
+    expr: &Expr,
+    vtype: Option<&VariableType>,
+) -> Result<ConstValue, CompilationError> {
+    let hint = vtype.filter(|t| t.is_integer());
+    match &expr.etype {
+        ExprType::Int(x) if hint.is_some() => {
+            let t = hint.unwrap();
+            let value = if *x < 0 {
+                *x as u64 as i128
+            } else {
+                *x as i128
+            };
+            Ok(ConstValue::Num(check_range(value, t)?, t.clone()))
+        }
+        // Literals are never negative, a negative value is a literal above i64::MAX
+        ExprType::Int(x) if *x < 0 => Ok(ConstValue::Num(*x, VariableType::ULong)),
+        ExprType::Int(x) => match i32::try_from(*x) {
+            Ok(_) => Ok(ConstValue::Num(*x, VariableType::Int)),
+            Err(_) => Ok(ConstValue::Num(*x, VariableType::Long)),
+        },
+        ExprType::Char(c) => Ok(ConstValue::Num(*c as i64, VariableType::Char)),
+        ExprType::Bool(b) => Ok(ConstValue::Num(*b as i64, VariableType::Bool)),
+        ExprType::String(s) => Ok(ConstValue::Str(s.clone())),
+        ExprType::Unary(u) => match eval_const_as(&u.right, hint)? {
+            ConstValue::Num(v, t) => {
+                let res = fold_unary_expr(&ExprOpr::new(v, t), &u.op)?;
+                Ok(ConstValue::Num(res.value.get_literal_value(), res.vtype))
+            }
+            ConstValue::Str(_) => Err(CompilationError::InValidUnaryOperation(
+                u.op.clone(),
+                VariableType::String.to_string(),
+            )),
+        },
+        ExprType::Binary(b) => match (
+            eval_const_as(&b.left, hint)?,
+            eval_const_as(&b.right, hint)?,
+        ) {
+            (ConstValue::Num(l, lt), ConstValue::Num(r, rt)) => {
+                let res =
+                    fold_const_binary_expr(&ExprOpr::new(l, lt), &ExprOpr::new(r, rt), &b.op)?;
+                Ok(ConstValue::Num(res.value.get_literal_value(), res.vtype))
+            }
+            (ConstValue::Str(l), ConstValue::Str(r)) if b.op == Op::Plus => {
+                Ok(ConstValue::Str(l + &r))
+            }
+            (l, r) => Err(CompilationError::InValidBinaryOperation(
+                b.op.clone(),
+                l.vtype().to_string(),
+                r.vtype().to_string(),
+            )),
+        },
+        ExprType::Compare(c) => match (eval_const(&c.left)?, eval_const(&c.right)?) {
+            (ConstValue::Num(l, lt), ConstValue::Num(r, rt)) => {
+                let res = fold_compare_expr(&ExprOpr::new(l, lt), &ExprOpr::new(r, rt), &c.op)?;
+                Ok(ConstValue::Num(res.value.get_literal_value(), res.vtype))
+            }
+            (ConstValue::Str(l), ConstValue::Str(r)) if c.op == CompareOp::Eq => {
+                Ok(ConstValue::Num((l == r) as i64, VariableType::Bool))
+            }
+            (ConstValue::Str(l), ConstValue::Str(r)) if c.op == CompareOp::NotEq => {
+                Ok(ConstValue::Num((l != r) as i64, VariableType::Bool))
+            }
+            (l, r) => Err(CompilationError::InvalidTypeCasting(
+                l.vtype().to_string(),
+                r.vtype().to_string(),
+            )),
+        },
+        ExprType::Cast(e, t) => eval_const(e)?.with_type(t),
+        ExprType::If(ie) => match eval_const(&ie.condition)? {
+            ConstValue::Num(v, VariableType::Bool) if v != 0 => eval_const(&ie.then),
+            ConstValue::Num(_, VariableType::Bool) => eval_const(&ie.otherwise),
+            value => Err(CompilationError::UnmatchingTypes(
+                VariableType::Bool.to_string(),
+                value.vtype().to_string(),
+            )),
+        },
+        _ => Err(CompilationError::NonConstantExpr),
+    }
+}
@@ -0,0 +1,88 @@
+/**********************************************************************************************
+*
+*   parser/enums: parsing enum defenitions
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use crate::{
+    error_handeling::error,
+    lexer::{Lexer, TokenType},
+};
+
+use super::types::{type_def, type_params, EnumType, EnumVariant, StructItemType, StructType};
+
+/// Parse enum definition
+///
+/// Syntax:
+/// enum Name<T, ...> { Variant, Variant(item @type, ...), ... }
+pub fn enum_def(lexer: &mut Lexer) -> EnumType {
+    lexer.match_token(TokenType::Enum);
+    let enum_ident = lexer.get_token().literal;
+    lexer.match_token(TokenType::Identifier);
+    let type_params = type_params(lexer);
+    lexer.match_token(TokenType::OCurly);
+    let mut variants = Vec::<EnumVariant>::new();
+    loop {
+        if lexer.get_token_type() == TokenType::CCurly {
+            lexer.match_token(TokenType::CCurly);
+            break;
+        }
+        let loc = lexer.get_token_loc();
+        let ident = lexer.get_token().literal;
+        lexer.match_token(TokenType::Identifier);
+        let mut items = Vec::<StructItemType>::new();
+        if lexer.get_token_type() == TokenType::OParen {
+            lexer.match_token(TokenType::OParen);
+            loop {
+                if lexer.get_token_type() == TokenType::CParen {
+                    lexer.match_token(TokenType::CParen);
+                    break;
+                }
+                let item_ident = lexer.get_token().literal;
+                lexer.match_token(TokenType::Identifier);
+                let ttype = type_def(lexer);
+                items.push(StructItemType::new(item_ident, 0, ttype));
+                if lexer.get_token_type() != TokenType::CParen {
+                    lexer.match_token(TokenType::Comma);
+                }
+            }
+        }
+        if variants.iter().any(|x| x.ident == ident) {
+            error(
+                format!(
+                    "Variant with the name {} already exists in enum {}",
+                    ident, enum_ident
+                ),
+                loc,
+            );
+        }
+        variants.push(EnumVariant {
+            payload: StructType::new(ident.clone(), items, false),
+            ident,
+        });
+        if lexer.get_token_type() != TokenType::CCurly {
+            lexer.match_token(TokenType::Comma);
+        }
+    }
+    let mut et = EnumType::new(enum_ident, variants);
+    et.type_params = type_params;
+    et
+}
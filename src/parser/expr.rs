@@ -25,6 +25,10 @@
 use crate::{
     error_handeling::{error, Loc},
     lexer::{Lexer, TokenType},
+    parser::{
+        constants::{eval_const, ConstValue},
+        types::{type_def, VariableType},
+    },
 };
 use core::fmt::Display;
 
@@ -52,7 +56,7 @@ pub enum ExprType {
     Compare(CompareExpr),
     /// Integer values
     /// e.g: 10
-    Int(i32),
+    Int(i64),
     /// Floating Number Values
     /// e.g: 3.5 0.1 1.0 PI
     Float(f64),
@@ -67,7 +71,7 @@ pub enum ExprType {
     Ptr(Box<Expr>),
     /// Member Access Operator
     /// e.g: fridge.milk
-    Access(String, Box<Expr>),
+    Access(Box<Expr>, String),
     /// String values
     /// e.g: "Hello\n"
     String(String),
@@ -77,19 +81,52 @@ pub enum ExprType {
     /// Function Call
     /// e.g: cat(), is_odd(10)
     FunctionCall(FunctionCall),
+    /// Call a function value
+    /// e.g: ops[2](1, 2)
+    IndirectCall(Box<Expr>, Vec<Expr>),
     /// Array Index
     /// e.g: list[10]
     ArrayIndex(ArrayIndex),
+    /// Tuple of values
+    /// e.g: (n, true)
+    Tuple(Vec<Expr>),
     /// Bool
     /// e.g: True, False
     Bool(u8),
+    /// Enum Variant
+    /// e.g: Color::Red, Shape::Rect(2, 3)
+    EnumVariant(EnumVariantExpr),
+    /// Value of a Result or an Option, returns early with the error or None
+    /// e.g: read(fd)?
+    Try(Box<Expr>),
+    /// Explicit type conversion
+    /// e.g: x as @long, p as @ptr
+    Cast(Box<Expr>, Box<VariableType>),
+    /// Size of a type in bytes
+    /// e.g: sizeof(@Vec2)
+    SizeOf(Box<VariableType>),
+    /// Alignment of a type in bytes
+    /// e.g: alignof(@long)
+    AlignOf(Box<VariableType>),
+    /// Array of values
+    /// e.g: [1, 2, 3]
+    ArrayLit(Vec<Expr>),
+    /// Array of a repeated value
+    /// e.g: [0; 16]
+    ArrayRepeat(Box<Expr>, usize),
+    /// Struct with the value of each item
+    /// e.g: Human { age: 1, height: 2 }
+    StructLit(StructLiteral),
+    /// Value chosen by a condition
+    /// e.g: if a > b { a } else { b }
+    If(Box<IfExpr>),
 }
 impl ExprType {
     /// returns true if token type is used in binary operations
     pub fn is_binary_op(t_token: TokenType) -> bool {
         matches!(
             t_token,
-            TokenType::Plus | TokenType::Minus | TokenType::And | TokenType::Or
+            TokenType::Plus | TokenType::Minus | TokenType::And | TokenType::Or | TokenType::Xor
         )
     }
 
@@ -120,6 +157,26 @@ impl ExprType {
     }
 }
 
+/// Struct Literal
+/// * ident: name of the struct
+/// * items: item names and their values in the written order
+#[derive(Debug, PartialEq, Clone)]
+pub struct StructLiteral {
+    pub ident: String,
+    pub items: Vec<(String, Expr)>,
+}
+
+/// If Expr
+/// * condition: condition that chooses the value
+/// * then: value if the condition is true
+/// * otherwise: value if the condition is false
+#[derive(Debug, PartialEq, Clone)]
+pub struct IfExpr {
+    pub condition: Expr,
+    pub then: Expr,
+    pub otherwise: Expr,
+}
+
 /// Unaray Expr
 /// Used for Exprssion with On Expression and one Operation
 /// * op: Operation Operation
@@ -153,14 +210,18 @@ pub enum Op {
     Multi,
     /// divition
     Devide,
-    /// Binary Not
+    /// Logical Not
     Not,
+    /// Bitwise Not
+    BitNot,
     /// Modulo
     Mod,
     /// Binary And
     And,
     /// Binary Or
     Or,
+    /// Binary Xor
+    Xor,
     /// Binary Left Shift
     Lsh,
     /// Binary Right Shift
@@ -179,9 +240,11 @@ impl Op {
             TokenType::Multi => Self::Multi,
             TokenType::Devide => Self::Devide,
             TokenType::Not => Self::Not,
+            TokenType::Tilde => Self::BitNot,
             TokenType::Mod => Self::Mod,
             TokenType::And => Self::And,
             TokenType::Or => Self::Or,
+            TokenType::Xor => Self::Xor,
             TokenType::Lsh => Self::Lsh,
             TokenType::Rsh => Self::Rsh,
             TokenType::DoubleAnd => Self::LogicalAnd,
@@ -200,9 +263,11 @@ impl Display for Op {
             Op::Multi => write!(f, "*"),
             Op::Devide => write!(f, "/"),
             Op::Not => write!(f, "!"),
+            Op::BitNot => write!(f, "~"),
             Op::Mod => write!(f, "%"),
             Op::And => write!(f, "&"),
             Op::Or => write!(f, "|"),
+            Op::Xor => write!(f, "^"),
             Op::Lsh => write!(f, "<<"),
             Op::Rsh => write!(f, ">>"),
             Op::LogicalOr => write!(f, "||"),
@@ -215,22 +280,35 @@ impl Display for Op {
 /// Used for Function call Expression
 /// * ident - name of the function
 /// * args  - function call argumnets
+/// * receiver - value before the dot in method calls
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionCall {
     pub ident: String,
     pub args: Vec<Expr>,
+    pub receiver: Option<Box<Expr>>,
 }
 
 /// Array Index
 /// Used for Array Indexing Expression
-/// * ident - name of the variable
+/// * array - expresstion of the indexed array
 /// * indexer - expresstion that indexes the array
 #[derive(Debug, PartialEq, Clone)]
 pub struct ArrayIndex {
-    pub ident: String,
+    pub array: Box<Expr>,
     pub indexer: Box<Expr>,
 }
 
+/// Used for Constructing Enum Variants
+/// * ident - name of the enum
+/// * variant - name of the variant
+/// * args - payload values of the variant
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumVariantExpr {
+    pub ident: String,
+    pub variant: String,
+    pub args: Vec<Expr>,
+}
+
 /// Compare Expr
 /// Used of iCompare Expressions with one Operation and two Expression
 /// * left: Expression in the left side of operation
@@ -302,11 +380,32 @@ pub fn expr(lexer: &mut Lexer) -> Expr {
     term_expr
 }
 
+/// Parsing Expr right before a block
+/// e.g: conditions of if, while and match or the range of for loops
+/// "name {" starts the block there instead of a struct literal
+pub fn expr_before_block(lexer: &mut Lexer) -> Expr {
+    let struct_literals = lexer.struct_literals;
+    lexer.struct_literals = false;
+    let value = expr(lexer);
+    lexer.struct_literals = struct_literals;
+    value
+}
+
+/// Parsing Expr between delimiters where struct literals are allowed again
+/// e.g: (expr), [expr], call(expr)
+fn delimited_expr(lexer: &mut Lexer) -> Expr {
+    let struct_literals = lexer.struct_literals;
+    lexer.struct_literals = true;
+    let value = expr(lexer);
+    lexer.struct_literals = struct_literals;
+    value
+}
+
 /// Parsing Expr
 /// returns second Prioraty operations
 /// e.g: Multi, Devide, Logical And
 pub fn term(lexer: &mut Lexer) -> Expr {
-    let mut left = factor(lexer);
+    let mut left = cast_expr(lexer);
     let mut cur_token = lexer.get_token_type();
     while ExprType::is_term_op(cur_token) || ExprType::is_compare_op(cur_token) {
         if ExprType::is_compare_op(cur_token) {
@@ -324,7 +423,7 @@ pub fn term(lexer: &mut Lexer) -> Expr {
         } else if ExprType::is_term_op(cur_token) {
             let op = Op::from_token_type(lexer.get_token_type());
             lexer.next_token();
-            let right = factor(lexer);
+            let right = cast_expr(lexer);
             left = Expr {
                 etype: ExprType::Binary(BinaryExpr {
                     left: Box::new(left),
@@ -339,6 +438,21 @@ pub fn term(lexer: &mut Lexer) -> Expr {
     left
 }
 
+/// Parsing explicit casts which bind tighter than binary operations
+/// e.g: a as @long * 2
+pub fn cast_expr(lexer: &mut Lexer) -> Expr {
+    let mut value = factor(lexer);
+    while lexer.get_token_type() == TokenType::As {
+        lexer.match_token(TokenType::As);
+        let loc = value.loc.clone();
+        value = Expr {
+            etype: ExprType::Cast(Box::new(value), Box::new(type_def(lexer))),
+            loc,
+        };
+    }
+    value
+}
+
 /// Parsing Expr
 /// returns first Prioraty operations
 /// e.g: Unary, Paran, Power
@@ -347,11 +461,30 @@ pub fn factor(lexer: &mut Lexer) -> Expr {
     match lexer.get_token_type() {
         TokenType::OParen => {
             lexer.match_token(TokenType::OParen);
-            let value = expr(lexer);
+            let value = delimited_expr(lexer);
+            if lexer.get_token_type() == TokenType::Comma {
+                let mut items = vec![value];
+                while lexer.get_token_type() == TokenType::Comma {
+                    lexer.match_token(TokenType::Comma);
+                    items.push(delimited_expr(lexer));
+                }
+                lexer.match_token(TokenType::CParen);
+                return Expr {
+                    etype: ExprType::Tuple(items),
+                    loc,
+                };
+            }
             lexer.match_token(TokenType::CParen);
-            value
+            postfix_expr(lexer, value)
         }
-        TokenType::Plus | TokenType::Minus | TokenType::Not | TokenType::Multi | TokenType::And => {
+        TokenType::OBracket => array_literal(lexer),
+        TokenType::If => if_expr(lexer),
+        TokenType::Plus
+        | TokenType::Minus
+        | TokenType::Not
+        | TokenType::Tilde
+        | TokenType::Multi
+        | TokenType::And => {
             let op = Op::from_token_type(lexer.get_token_type());
             lexer.next_token();
             let value = factor(lexer);
@@ -416,47 +549,80 @@ pub fn factor(lexer: &mut Lexer) -> Expr {
                 loc,
             }
         }
+        TokenType::Sizeof | TokenType::Alignof => {
+            let t_type = lexer.get_token_type();
+            lexer.next_token();
+            lexer.match_token(TokenType::OParen);
+            let vtype = Box::new(type_def(lexer));
+            lexer.match_token(TokenType::CParen);
+            let etype = if t_type == TokenType::Sizeof {
+                ExprType::SizeOf(vtype)
+            } else {
+                ExprType::AlignOf(vtype)
+            };
+            Expr { etype, loc }
+        }
         TokenType::Identifier => {
             let ident_name = lexer.get_token().literal;
-            if lexer.next_token().is_empty() {
+            let is_empty = lexer.next_token().is_empty();
+            // Constants are replaced with their value
+            if let Some(value) = lexer.constants.get(&ident_name) {
+                let base = value.to_expr(loc);
+                if is_empty {
+                    return base;
+                }
+                return postfix_expr(lexer, base);
+            }
+            if is_empty {
                 return Expr {
                     etype: ExprType::Variable(ident_name),
                     loc,
                 };
             }
-            match lexer.get_token_type() {
+            let base = match lexer.get_token_type() {
                 TokenType::OParen => {
                     let args = function_call_args(lexer);
                     Expr {
                         etype: ExprType::FunctionCall(FunctionCall {
                             ident: ident_name,
                             args,
+                            receiver: None,
                         }),
                         loc,
                     }
                 }
-                TokenType::OBracket => {
-                    let indexer = array_indexer(lexer);
-                    Expr {
-                        etype: ExprType::ArrayIndex(ArrayIndex {
+                // Blocks of statements never start with "name:"
+                TokenType::OCurly
+                    if lexer.struct_literals
+                        && lexer.peek_token(1).t_type == TokenType::Identifier
+                        && lexer.peek_token(2).t_type == TokenType::Colon =>
+                {
+                    return struct_literal(lexer, ident_name, loc);
+                }
+                TokenType::DoubleColon => {
+                    lexer.match_token(TokenType::DoubleColon);
+                    let variant = lexer.get_token().literal;
+                    lexer.match_token(TokenType::Identifier);
+                    let args = if lexer.get_token_type() == TokenType::OParen {
+                        function_call_args(lexer)
+                    } else {
+                        vec![]
+                    };
+                    return Expr {
+                        etype: ExprType::EnumVariant(EnumVariantExpr {
                             ident: ident_name,
-                            indexer: Box::new(indexer),
+                            variant,
+                            args,
                         }),
                         loc,
-                    }
-                }
-                TokenType::Dot => {
-                    let access_expr = memeber_access(lexer);
-                    Expr {
-                        etype: ExprType::Access(ident_name, Box::new(access_expr)),
-                        loc,
-                    }
+                    };
                 }
                 _ => Expr {
                     etype: ExprType::Variable(ident_name),
                     loc,
                 },
-            }
+            };
+            postfix_expr(lexer, base)
         }
         _ => {
             error(
@@ -470,23 +636,202 @@ pub fn factor(lexer: &mut Lexer) -> Expr {
     }
 }
 
+/// Parsing chains of array indexes, member accesses, calls and ?
+/// e.g: list[i].items[0].name
+pub fn postfix_expr(lexer: &mut Lexer, base: Expr) -> Expr {
+    let mut expr = base;
+    loop {
+        let loc = expr.loc.clone();
+        match lexer.get_token_type() {
+            TokenType::OBracket => {
+                let indexer = array_indexer(lexer);
+                expr = Expr {
+                    etype: ExprType::ArrayIndex(ArrayIndex {
+                        array: Box::new(expr),
+                        indexer: Box::new(indexer),
+                    }),
+                    loc,
+                };
+            }
+            TokenType::Dot => {
+                for item in memeber_access(lexer) {
+                    expr = Expr {
+                        etype: ExprType::Access(Box::new(expr), item),
+                        loc: loc.clone(),
+                    };
+                }
+            }
+            TokenType::OParen => {
+                let args = function_call_args(lexer);
+                let etype = match expr.etype {
+                    // Methods and function values in struct items are told apart by the compiler
+                    ExprType::Access(receiver, ident) => ExprType::FunctionCall(FunctionCall {
+                        ident,
+                        args,
+                        receiver: Some(receiver),
+                    }),
+                    etype => ExprType::IndirectCall(
+                        Box::new(Expr {
+                            etype,
+                            loc: loc.clone(),
+                        }),
+                        args,
+                    ),
+                };
+                expr = Expr { etype, loc };
+            }
+            // Location of ? tells the early returns apart
+            TokenType::QMark => {
+                let loc = lexer.get_token_loc();
+                lexer.match_token(TokenType::QMark);
+                expr = Expr {
+                    etype: ExprType::Try(Box::new(expr)),
+                    loc,
+                };
+            }
+            _ => return expr,
+        }
+    }
+}
+
 /// Parsing direct Member Access
-pub fn memeber_access(lexer: &mut Lexer) -> Expr {
+/// Returns the names of the accessed members, tuple items are named after their position
+pub fn memeber_access(lexer: &mut Lexer) -> Vec<String> {
     lexer.match_token(TokenType::Dot);
+    let item = lexer.get_token().literal;
+    match lexer.get_token_type() {
+        TokenType::Int(i) => {
+            lexer.match_token(TokenType::Int(i));
+            return vec![i.to_string()];
+        }
+        // Nested tuple items (t.0.1) are lexed as a float
+        TokenType::Float(f) => {
+            lexer.match_token(TokenType::Float(f));
+            return item.split('.').map(str::to_owned).collect();
+        }
+        _ => (),
+    }
     if lexer.get_token_type() != TokenType::Identifier {
         error(
             "TODO: Invalid Access Operation for struct",
             lexer.get_current_loc(),
         );
+    }
+    lexer.match_token(TokenType::Identifier);
+    vec![item]
+}
+
+/// Parsing Array Literal
+/// The count of repeated values should be a constant
+///
+/// Syntax:
+/// [expr, expr, ..] | [expr; count]
+pub fn array_literal(lexer: &mut Lexer) -> Expr {
+    let loc = lexer.get_token_loc();
+    lexer.match_token(TokenType::OBracket);
+    if lexer.get_token_type() == TokenType::CBracket {
+        error("Array literals can not be empty", loc);
+    }
+    let first = delimited_expr(lexer);
+    if lexer.get_token_type() == TokenType::SemiColon {
+        lexer.match_token(TokenType::SemiColon);
+        let count_expr = delimited_expr(lexer);
+        lexer.match_token(TokenType::CBracket);
+        let count = match eval_const(&count_expr) {
+            Ok(ConstValue::Num(n, t)) if t.is_integer() && n > 0 => n as usize,
+            Ok(value) => error(
+                format!(
+                    "Expected a positive integer count of items found ({})",
+                    value.vtype()
+                ),
+                count_expr.loc,
+            ),
+            Err(e) => error(e, count_expr.loc),
+        };
+        return Expr {
+            etype: ExprType::ArrayRepeat(Box::new(first), count),
+            loc,
+        };
+    }
+    let mut items = vec![first];
+    while lexer.get_token_type() == TokenType::Comma {
+        lexer.match_token(TokenType::Comma);
+        if lexer.get_token_type() == TokenType::CBracket {
+            break;
+        }
+        items.push(delimited_expr(lexer));
+    }
+    lexer.match_token(TokenType::CBracket);
+    Expr {
+        etype: ExprType::ArrayLit(items),
+        loc,
+    }
+}
+
+/// Parsing If Expression
+/// Both branches are required and hold a single value
+///
+/// Syntax:
+/// if cond { expr } else { expr }
+/// if cond { expr } else if cond { expr } else { expr }
+fn if_expr(lexer: &mut Lexer) -> Expr {
+    let loc = lexer.get_token_loc();
+    lexer.match_token(TokenType::If);
+    let condition = expr_before_block(lexer);
+    lexer.match_token(TokenType::OCurly);
+    let then = delimited_expr(lexer);
+    lexer.match_token(TokenType::CCurly);
+    lexer.match_token(TokenType::Else);
+    let otherwise = if lexer.get_token_type() == TokenType::If {
+        if_expr(lexer)
     } else {
-        expr(lexer)
+        lexer.match_token(TokenType::OCurly);
+        let value = delimited_expr(lexer);
+        lexer.match_token(TokenType::CCurly);
+        value
+    };
+    Expr {
+        etype: ExprType::If(Box::new(IfExpr {
+            condition,
+            then,
+            otherwise,
+        })),
+        loc,
+    }
+}
+
+/// Parsing Struct Literal
+///
+/// Syntax:
+/// Name { item: expr, item: expr, .. }
+fn struct_literal(lexer: &mut Lexer, ident: String, loc: Loc) -> Expr {
+    lexer.match_token(TokenType::OCurly);
+    let mut items = Vec::<(String, Expr)>::new();
+    while lexer.get_token_type() != TokenType::CCurly {
+        let item_loc = lexer.get_token_loc();
+        let item = lexer.get_token().literal;
+        lexer.match_token(TokenType::Identifier);
+        lexer.match_token(TokenType::Colon);
+        if items.iter().any(|(i, _)| *i == item) {
+            error(format!("Item ({item}) is given more than once"), item_loc);
+        }
+        items.push((item, delimited_expr(lexer)));
+        if lexer.get_token_type() != TokenType::Comma {
+            break;
+        }
+        lexer.match_token(TokenType::Comma);
+    }
+    lexer.match_token(TokenType::CCurly);
+    Expr {
+        etype: ExprType::StructLit(StructLiteral { ident, items }),
+        loc,
     }
 }
 
 /// Parsing Array Index
 pub fn array_indexer(lexer: &mut Lexer) -> Expr {
     lexer.match_token(TokenType::OBracket);
-    let index = expr(lexer);
+    let index = delimited_expr(lexer);
     lexer.match_token(TokenType::CBracket);
     index
 }
@@ -504,7 +849,7 @@ pub fn function_call_args(lexer: &mut Lexer) -> Vec<Expr> {
                 break;
             }
             _ => {
-                args.push(expr(lexer));
+                args.push(delimited_expr(lexer));
                 if lexer.get_token_type() == TokenType::Comma {
                     lexer.match_token(TokenType::Comma);
                 }
@@ -25,7 +25,10 @@
 use crate::{
     error_handeling::error,
     lexer::{Lexer, TokenType},
-    parser::{block::Block, types::type_def},
+    parser::{
+        block::Block,
+        types::{type_def, type_params},
+    },
 };
 
 use super::{block::BlockType, types::VariableType};
@@ -48,13 +51,14 @@ pub struct FunctionArg {
 pub struct FunctionDef {
     pub decl: FunctionDecl,
     pub block: Block,
-    pub defer_block: Block,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionDecl {
     pub is_extern: bool,
     pub ident: String,
+    /// Type parameters of a generic function
+    pub type_params: Vec<String>,
     pub args: Vec<FunctionArg>,
     pub ret_type: VariableType,
 }
@@ -76,6 +80,7 @@ pub fn parse_function_declaration(lexer: &mut Lexer) -> FunctionDecl {
         false => function_ident_token.literal,
     };
     lexer.match_token(TokenType::Identifier);
+    let type_params = type_params(lexer);
     let args = function_def_args(lexer);
     if lexer.get_token_type() == TokenType::ATSign {
         ret_type = type_def(lexer);
@@ -83,24 +88,23 @@ pub fn parse_function_declaration(lexer: &mut Lexer) -> FunctionDecl {
     FunctionDecl {
         is_extern,
         ident: fn_ident,
+        type_params,
         args,
         ret_type,
     }
 }
 
+/// Name of a method in the program name space
+pub fn method_ident(type_ident: &str, method: &str) -> String {
+    format!("{type_ident}#{method}")
+}
+
 /// Parsing Function definition
 pub fn parse_function_definition(lexer: &mut Lexer) -> FunctionDef {
     let decl = parse_function_declaration(lexer);
     let mut block = Block::new_global(decl.ident.clone(), BlockType::Function);
     block.parse_block(lexer);
-    let mut defer_block = Block::new_global(decl.ident.clone(), BlockType::Function);
-    defer_block.stmts = block.defer_stmts.clone();
-    block.defer_stmts.clear();
-    FunctionDef {
-        decl,
-        block,
-        defer_block,
-    }
+    FunctionDef { decl, block }
 }
 
 /// Parsing Function definition
@@ -117,6 +121,13 @@ pub fn function_def_args(lexer: &mut Lexer) -> Vec<FunctionArg> {
             }
             TokenType::Identifier => {
                 let ident = lexer.get_token().literal;
+                // Constants are substituted while parsing and would hide the argument
+                if lexer.constants.contains_key(&ident) {
+                    error(
+                        format!("Constant ({ident}) can not be redefined as an argument"),
+                        lexer.get_token_loc(),
+                    );
+                }
                 lexer.match_token(TokenType::Identifier);
                 let typedef = type_def(lexer);
                 if lexer.get_token_type() == TokenType::Comma {
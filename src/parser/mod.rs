@@ -24,6 +24,8 @@
 **********************************************************************************************/
 pub mod assign;
 pub mod block;
+pub mod constants;
+pub mod enums;
 pub mod expr;
 pub mod function;
 pub mod preprocessing;
@@ -8,8 +8,10 @@ use crate::{
 };
 
 use super::{
+    constants::{eval_const, ConstValue},
     expr::{expr, CompareOp, Expr, Op},
     stmt::Stmt,
+    types::VariableType,
 };
 
 pub static PLATFORMS: &[&str] = &["LINUX", "WINDOWS", "UNKNOWN"];
@@ -96,6 +98,10 @@ fn parse_pre_condition(lexer: &mut Lexer, loc: Loc, master: &String) -> Vec<Stmt
 }
 
 fn compile_pre_expr(expr: &Expr) -> bool {
+    // Conditions over constants are evaluated like constant definitions
+    if let Ok(ConstValue::Num(v, VariableType::Bool)) = eval_const(expr) {
+        return v != 0;
+    }
     match &expr.etype {
         ExprType::Bool(b) => b != &0u8,
         ExprType::Variable(v) => {
@@ -25,15 +25,20 @@ use std::collections::BTreeMap;
 *
 **********************************************************************************************/
 use crate::{
-    error_handeling::error,
+    error_handeling::{error, CompilationError, Loc},
     lexer::{Lexer, TokenType},
 };
 
 use super::{
-    function::{parse_function_declaration, parse_function_definition, FunctionDecl, FunctionDef},
+    constants::{const_def, ConstValue},
+    enums::enum_def,
+    function::{
+        method_ident, parse_function_declaration, parse_function_definition, FunctionDecl,
+        FunctionDef,
+    },
     parse_source_file,
     structs::struct_def,
-    types::StructType,
+    types::{type_alias_def, EnumType, StructType, TypeAlias},
     variable_decl::{variable_declare, VariableDeclare},
 };
 
@@ -55,21 +60,30 @@ pub struct ProgramFile {
 pub enum ProgramItem {
     /// Struct Defenition
     Struct(StructType),
+    /// Enum Defenition
+    Enum(EnumType),
     /// Function Definitions
     Func(FunctionDef),
     /// Static Variables
     StaticVar(VariableDeclare),
     /// Foregin Function interface
     FFI(String, FunctionDecl),
+    /// Compile time constants
+    Const(String, ConstValue),
+    /// Type aliases and distinct types
+    TypeAlias(TypeAlias),
 }
 
 impl ProgramItem {
     pub fn get_key(&self) -> String {
         match self {
             Self::Struct(st) => st.ident.clone(),
+            Self::Enum(en) => en.ident.clone(),
             Self::FFI(_, fun) => fun.ident.clone(),
             Self::Func(func) => func.decl.ident.clone(),
             Self::StaticVar(st) => st.ident.clone(),
+            Self::Const(ident, _) => ident.clone(),
+            Self::TypeAlias(ta) => ta.ident.clone(),
         }
     }
 }
@@ -79,13 +93,14 @@ impl ProgramItem {
 pub fn generate_ast(lexer: &mut Lexer) -> ProgramFile {
     lexer.next_token();
     let mut items = BTreeMap::<String, ProgramItem>::new();
+    let mut impl_types = Vec::<(String, Loc)>::new();
     loop {
         if lexer.get_token().is_empty() {
             break;
         }
         let loc = lexer.get_token_loc();
         match lexer.get_token_type() {
-            TokenType::Struct => {
+            TokenType::Struct | TokenType::Packed => {
                 let struct_def = struct_def(lexer);
                 let ident = struct_def.ident.clone();
                 let prv_value = items.insert(ident.clone(), ProgramItem::Struct(struct_def));
@@ -96,6 +111,14 @@ pub fn generate_ast(lexer: &mut Lexer) -> ProgramFile {
                     );
                 }
             }
+            TokenType::Enum => {
+                let enum_def = enum_def(lexer);
+                let ident = enum_def.ident.clone();
+                let prv_value = items.insert(ident.clone(), ProgramItem::Enum(enum_def));
+                if prv_value.is_some() {
+                    error(format!("Enum with the name {} already exists", ident), loc);
+                }
+            }
             TokenType::Ffi => {
                 let ffi_func = parse_ffi_function_mapping(lexer);
                 let ident = ffi_func.1.ident.clone();
@@ -119,6 +142,20 @@ pub fn generate_ast(lexer: &mut Lexer) -> ProgramFile {
                     );
                 }
             }
+            TokenType::Impl => {
+                let (type_ident, methods) = parse_impl_block(lexer);
+                impl_types.push((type_ident, loc.clone()));
+                for method in methods {
+                    let ident = method.decl.ident.clone();
+                    let prv_value = items.insert(ident.clone(), ProgramItem::Func(method));
+                    if prv_value.is_some() {
+                        error(
+                            format!("Method with the name {} already exists", ident),
+                            loc,
+                        );
+                    }
+                }
+            }
             TokenType::Static => {
                 lexer.match_token(TokenType::Static);
                 let var_decl = variable_declare(lexer);
@@ -132,6 +169,25 @@ pub fn generate_ast(lexer: &mut Lexer) -> ProgramFile {
                     );
                 }
             }
+            TokenType::Const => {
+                let (ident, value) = const_def(lexer);
+                let prv_value =
+                    items.insert(ident.clone(), ProgramItem::Const(ident.clone(), value));
+                if prv_value.is_some() {
+                    error(
+                        format!("Constant with the name {} already exists", ident),
+                        loc,
+                    );
+                }
+            }
+            TokenType::Type | TokenType::Distinct => {
+                let alias = type_alias_def(lexer);
+                let ident = alias.ident.clone();
+                let prv_value = items.insert(ident.clone(), ProgramItem::TypeAlias(alias));
+                if prv_value.is_some() {
+                    error(format!("Type with the name {} already exists", ident), loc);
+                }
+            }
             TokenType::Import => {
                 let import = parse_mod_import(lexer);
                 let mut new_path = import.0;
@@ -145,13 +201,11 @@ pub fn generate_ast(lexer: &mut Lexer) -> ProgramFile {
                     }
                 }
                 for item in new_file.items {
-                    if import.1.is_empty() {
-                        items.insert(item.get_key(), item);
-                    } else {
-                        let key = item.get_key();
-                        if import.1.contains(&key) {
-                            items.insert(item.get_key(), item);
+                    if import.1.is_empty() || import.1.contains(&item.get_key()) {
+                        if let ProgramItem::Const(ident, value) = &item {
+                            lexer.constants.insert(ident.clone(), value.clone());
                         }
+                        items.insert(item.get_key(), item);
                     }
                 }
             }
@@ -164,6 +218,15 @@ pub fn generate_ast(lexer: &mut Lexer) -> ProgramFile {
             ),
         }
     }
+    // Types may be declared after their methods
+    for (ident, loc) in impl_types {
+        if !matches!(
+            items.get(&ident),
+            Some(ProgramItem::Struct(_) | ProgramItem::Enum(_) | ProgramItem::TypeAlias(_))
+        ) {
+            error(CompilationError::UnknownType(ident), loc);
+        }
+    }
     ProgramFile {
         items: items.values().cloned().collect::<Vec<ProgramItem>>(),
     }
@@ -181,6 +244,37 @@ pub fn parse_ffi_function_mapping(lexer: &mut Lexer) -> (String, FunctionDecl) {
     (module_name, function)
 }
 
+/// Parse methods of a struct
+/// Methods are named after the struct so they never collide with free functions
+///
+/// Syntax:
+/// impl Name { func method(self @*Name, ..) { .. } .. }
+pub fn parse_impl_block(lexer: &mut Lexer) -> (String, Vec<FunctionDef>) {
+    lexer.match_token(TokenType::Impl);
+    let type_ident = lexer.get_token().literal;
+    lexer.match_token(TokenType::Identifier);
+    lexer.match_token(TokenType::OCurly);
+    let mut methods = Vec::new();
+    while lexer.get_token_type() != TokenType::CCurly {
+        let loc = lexer.get_token_loc();
+        let mut method = parse_function_definition(lexer);
+        if method.decl.args.first().map(|a| a.ident.as_str()) != Some("self") {
+            error(
+                format!(
+                    "Method ({}) should take self as its first argument",
+                    method.decl.ident
+                ),
+                loc,
+            );
+        }
+        method.decl.ident = method_ident(&type_ident, &method.decl.ident);
+        method.block.rename_master(&method.decl.ident);
+        methods.push(method);
+    }
+    lexer.match_token(TokenType::CCurly);
+    (type_ident, methods)
+}
+
 /// import Program
 /// Returns Import Program Item
 pub fn parse_mod_import(lexer: &mut Lexer) -> (String, Vec<String>) {
@@ -22,7 +22,9 @@
 *     3. This notice may not be removed or altered from any source distribution.
 *
 **********************************************************************************************/
-use crate::error_handeling::Loc;
+use std::fmt::Display;
+
+use crate::error_handeling::{error, Loc};
 use crate::lexer::{Lexer, TokenType};
 use crate::parser::block::Block;
 use crate::parser::expr::Expr;
@@ -30,7 +32,8 @@ use crate::parser::variable_decl::inline_variable_declare;
 
 use super::assign::Assign;
 use super::block::BlockType;
-use super::expr::expr;
+use super::expr::{expr, expr_before_block};
+use super::types::VariableType;
 use super::variable_decl::VariableDeclare;
 
 /// Statment
@@ -56,18 +59,26 @@ pub enum StmtType {
     Print(Expr),
     /// While loops
     While(WhileStmt),
+    /// Loops that check the condition after each iteration
+    DoWhile(WhileStmt),
+    /// Loops that only end with break or return
+    Loop(Block),
     /// For Loop
     ForLoop(ForLoop),
     /// If Stmts
     If(IFStmt),
+    /// Match Stmts
+    Match(MatchStmt),
     /// Return Stmts
     Return(Expr),
     /// Inline Assembly
     InlineAsm(Vec<String>),
-    /// Break Stmts
-    Break,
-    /// CONTINUE Stmts
-    Continue,
+    /// Break Stmts with an optional loop label
+    Break(Option<String>),
+    /// CONTINUE Stmts with an optional loop label
+    Continue(Option<String>),
+    /// Stmts that run when the block exits
+    Defer(Vec<Stmt>),
 }
 
 /// If Stmt Information
@@ -81,6 +92,40 @@ pub struct IFStmt {
     pub else_block: Box<ElseBlock>,
 }
 
+impl Stmt {
+    /// Renames ids of the blocks in the statement
+    pub fn rename_blocks(&mut self, rename: &dyn Fn(&str) -> String) {
+        match &mut self.stype {
+            StmtType::If(ifs) => ifs.rename_blocks(rename),
+            StmtType::While(w) | StmtType::DoWhile(w) => w.block.rename_blocks(rename),
+            StmtType::Loop(b) => b.rename_blocks(rename),
+            StmtType::ForLoop(f) => f.block.rename_blocks(rename),
+            StmtType::Match(m) => {
+                for arm in m.arms.iter_mut() {
+                    arm.block.rename_blocks(rename);
+                }
+            }
+            StmtType::Defer(stmts) => {
+                for stmt in stmts.iter_mut() {
+                    stmt.rename_blocks(rename);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl IFStmt {
+    fn rename_blocks(&mut self, rename: &dyn Fn(&str) -> String) {
+        self.then_block.rename_blocks(rename);
+        match self.else_block.as_mut() {
+            ElseBlock::Elif(ifs) => ifs.rename_blocks(rename),
+            ElseBlock::Else(b) => b.rename_blocks(rename),
+            ElseBlock::None => (),
+        }
+    }
+}
+
 /// Else Block Types
 #[derive(Debug, Clone)]
 pub enum ElseBlock {
@@ -94,15 +139,27 @@ pub enum ElseBlock {
 
 /// For loop statment information
 /// * variable - a variable to iterate over range and lists
-/// * end_expr - last expretion
+/// * range - values of the iterator
 /// * block - for loop body
 #[derive(Debug, Clone)]
 pub struct ForLoop {
     pub iterator: VariableDeclare,
-    pub end_expr: Expr,
+    pub range: ForRange,
     pub block: Block,
 }
 
+/// Values a for loop iterates over
+#[derive(Debug, Clone)]
+pub enum ForRange {
+    /// Integers from the initial value up to the end (exclusive)
+    /// going down when the step is negative
+    /// e.g: for i = 10 to 0 step -2
+    Range(Expr, Option<Box<Expr>>),
+    /// Items of an array or a slice
+    /// e.g: for x in list
+    Items(Expr),
+}
+
 /// While Statment Information
 /// * condition - conditional expr runs until not true
 /// * block - while loop body
@@ -112,10 +169,53 @@ pub struct WhileStmt {
     pub block: Block,
 }
 
+/// Match Statment Information
+/// * expr - the matched expression
+/// * arms - patterns and their blocks in order
+#[derive(Debug, Clone)]
+pub struct MatchStmt {
+    pub expr: Expr,
+    pub arms: Vec<MatchArm>,
+}
+
+/// Match Arm Information
+/// * pattern - pattern that is compared to the matched expression
+/// * block - runs if the pattern matches
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: MatchPattern,
+    pub block: Block,
+}
+
+/// Match Patterns
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchPattern {
+    /// Enum variant with names for its payload items
+    /// e.g: Color::Red, Shape::Rect(w, h)
+    Variant(String, String, Vec<String>),
+    /// Integer values
+    /// e.g: 10, -1
+    Int(i64),
+    /// Matches any value
+    /// e.g: _
+    Wildcard,
+}
+
+impl Display for MatchPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Variant(e, v, items) if items.is_empty() => write!(f, "{e}::{v}"),
+            Self::Variant(e, v, items) => write!(f, "{e}::{v}({})", items.join(", ")),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Wildcard => write!(f, "_"),
+        }
+    }
+}
+
 /// Parse If Stmts
 pub fn if_stmt(lexer: &mut Lexer, master: &mut Block) -> IFStmt {
     lexer.match_token(TokenType::If);
-    let condition = expr(lexer);
+    let condition = expr_before_block(lexer);
     let mut then_block = Block::new(master, BlockType::Condition);
     then_block.parse_block(lexer);
     if lexer.get_token_type() == TokenType::Else {
@@ -148,6 +248,27 @@ pub fn if_stmt(lexer: &mut Lexer, master: &mut Block) -> IFStmt {
 /// parse For Loops
 pub fn for_loop(lexer: &mut Lexer, master: &mut Block) -> ForLoop {
     lexer.match_token(TokenType::For);
+    if lexer.peek_token(1).t_type == TokenType::In {
+        let loc = lexer.get_token_loc();
+        let ident = lexer.get_token().literal;
+        lexer.match_token(TokenType::Identifier);
+        lexer.match_token(TokenType::In);
+        let items = expr_before_block(lexer);
+        let mut block = Block::new(master, BlockType::Loop);
+        block.parse_block(lexer);
+        let iterator = VariableDeclare {
+            mutable: false,
+            ident,
+            v_type: VariableType::Any,
+            init_value: None,
+            loc,
+        };
+        return ForLoop {
+            iterator,
+            range: ForRange::Items(items),
+            block,
+        };
+    }
     let mut iterator = inline_variable_declare(lexer);
     if iterator.init_value.is_none() {
         iterator.init_value = Some(Expr {
@@ -156,12 +277,19 @@ pub fn for_loop(lexer: &mut Lexer, master: &mut Block) -> ForLoop {
         });
     }
     lexer.match_token(TokenType::To);
-    let end_expr = expr(lexer);
+    let end_expr = expr_before_block(lexer);
+    let step = match lexer.get_token_type() {
+        TokenType::Step => {
+            lexer.match_token(TokenType::Step);
+            Some(Box::new(expr_before_block(lexer)))
+        }
+        _ => None,
+    };
     let mut block = Block::new(master, BlockType::Loop);
     block.parse_block(lexer);
     ForLoop {
         iterator,
-        end_expr,
+        range: ForRange::Range(end_expr, step),
         block,
     }
 }
@@ -169,8 +297,101 @@ pub fn for_loop(lexer: &mut Lexer, master: &mut Block) -> ForLoop {
 /// Parse While Stmts
 pub fn while_stmt(lexer: &mut Lexer, master: &mut Block) -> WhileStmt {
     lexer.match_token(TokenType::While);
-    let condition = expr(lexer);
+    let condition = expr_before_block(lexer);
     let mut block = Block::new(master, BlockType::Loop);
     block.parse_block(lexer);
     WhileStmt { condition, block }
 }
+
+/// Parse Do While Stmts
+///
+/// Syntax:
+/// do { ... } while cond;
+pub fn do_while_stmt(lexer: &mut Lexer, master: &mut Block) -> WhileStmt {
+    lexer.match_token(TokenType::Do);
+    let mut block = Block::new(master, BlockType::Loop);
+    block.parse_block(lexer);
+    lexer.match_token(TokenType::While);
+    let condition = expr(lexer);
+    lexer.match_token(TokenType::SemiColon);
+    WhileStmt { condition, block }
+}
+
+/// Parse the optional label after break or continue
+pub fn loop_label(lexer: &mut Lexer) -> Option<String> {
+    if lexer.get_token_type() != TokenType::Identifier {
+        return None;
+    }
+    let label = lexer.get_token().literal;
+    lexer.match_token(TokenType::Identifier);
+    Some(label)
+}
+
+/// Parse Match Stmts
+pub fn match_stmt(lexer: &mut Lexer, master: &mut Block) -> MatchStmt {
+    lexer.match_token(TokenType::Match);
+    let expr = expr_before_block(lexer);
+    lexer.match_token(TokenType::OCurly);
+    let mut arms = Vec::<MatchArm>::new();
+    while lexer.get_token_type() != TokenType::CCurly {
+        let pattern = match_pattern(lexer);
+        lexer.match_token(TokenType::FatArrow);
+        let mut block = Block::new(master, BlockType::Condition);
+        block.parse_block(lexer);
+        if lexer.get_token_type() == TokenType::Comma {
+            lexer.match_token(TokenType::Comma);
+        }
+        arms.push(MatchArm { pattern, block });
+    }
+    lexer.match_token(TokenType::CCurly);
+    MatchStmt { expr, arms }
+}
+
+/// Parse Match Patterns
+fn match_pattern(lexer: &mut Lexer) -> MatchPattern {
+    let loc = lexer.get_token_loc();
+    match lexer.get_token_type() {
+        TokenType::Identifier => {
+            let ident = lexer.get_token().literal;
+            lexer.match_token(TokenType::Identifier);
+            if ident == "_" {
+                return MatchPattern::Wildcard;
+            }
+            lexer.match_token(TokenType::DoubleColon);
+            let variant = lexer.get_token().literal;
+            lexer.match_token(TokenType::Identifier);
+            let mut items = Vec::<String>::new();
+            if lexer.get_token_type() == TokenType::OParen {
+                lexer.match_token(TokenType::OParen);
+                while lexer.get_token_type() != TokenType::CParen {
+                    items.push(lexer.get_token().literal);
+                    lexer.match_token(TokenType::Identifier);
+                    if lexer.get_token_type() != TokenType::CParen {
+                        lexer.match_token(TokenType::Comma);
+                    }
+                }
+                lexer.match_token(TokenType::CParen);
+            }
+            MatchPattern::Variant(ident, variant, items)
+        }
+        TokenType::Int(i) => {
+            lexer.next_token();
+            MatchPattern::Int(i)
+        }
+        TokenType::Minus => {
+            lexer.match_token(TokenType::Minus);
+            let TokenType::Int(i) = lexer.get_token_type() else {
+                error("Expected an integer after (-) in match pattern", loc);
+            };
+            lexer.next_token();
+            MatchPattern::Int(-i)
+        }
+        _ => error(
+            format!(
+                "Unexpected Token ({}) for match pattern",
+                lexer.get_token_type()
+            ),
+            loc,
+        ),
+    }
+}
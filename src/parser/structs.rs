@@ -1,5 +1,3 @@
-use std::collections::BTreeMap;
-
 /**********************************************************************************************
 *
 *   parser/structs: parsing strucure defenitions
@@ -24,38 +22,54 @@ use std::collections::BTreeMap;
 *     3. This notice may not be removed or altered from any source distribution.
 *
 **********************************************************************************************/
-use crate::lexer::{Lexer, TokenType};
+use crate::{
+    error_handeling::error,
+    lexer::{Lexer, TokenType},
+};
 
-use super::types::{type_def, StructItemType, StructType};
+use super::types::{type_def, type_params, StructItemType, StructType};
 
+/// Parse struct definition
+///
+/// Syntax:
+/// [packed] struct Name[<T, ..>] { field @type, ... }
 pub fn struct_def(lexer: &mut Lexer) -> StructType {
+    let packed = lexer.get_token_type() == TokenType::Packed;
+    if packed {
+        lexer.match_token(TokenType::Packed);
+    }
     lexer.match_token(TokenType::Struct);
     let struct_ident_token = lexer.get_token();
     lexer.match_token(TokenType::Identifier);
+    let type_params = type_params(lexer);
     lexer.match_token(TokenType::OCurly);
-    let mut items = BTreeMap::<String, StructItemType>::new();
-    let mut offset = 0;
+    let mut items = Vec::<StructItemType>::new();
     loop {
         if lexer.get_token_type() == TokenType::CCurly {
             lexer.match_token(TokenType::CCurly);
             break;
         }
+        let loc = lexer.get_token_loc();
         let ident = lexer.get_token().literal;
         lexer.match_token(TokenType::Identifier);
         if lexer.get_token_type() == TokenType::ATSign {
             let ttype = type_def(lexer);
-            offset += ttype.item_size();
-            items.insert(
-                ident.clone(),
-                StructItemType::new(ident.clone(), offset as i32, ttype),
-            );
+            if items.iter().any(|x| x.ident == ident) {
+                error(
+                    format!(
+                        "Struct item with the name {} already exists in struct {}",
+                        ident, struct_ident_token.literal
+                    ),
+                    loc,
+                );
+            }
+            items.push(StructItemType::new(ident, 0, ttype));
         }
         if lexer.get_token_type() != TokenType::CCurly {
             lexer.match_token(TokenType::Comma);
         }
     }
-    StructType {
-        ident: struct_ident_token.literal,
-        items,
-    }
+    let mut st = StructType::new(struct_ident_token.literal, items, packed);
+    st.type_params = type_params;
+    st
 }
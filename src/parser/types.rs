@@ -22,26 +22,184 @@
 *     3. This notice may not be removed or altered from any source distribution.
 *
 **********************************************************************************************/
-use std::{collections::BTreeMap, fmt::Display};
+use std::fmt::Display;
 
 use crate::{
     error_handeling::{error, CompilationError},
     lexer::{Lexer, TokenType},
+    parser::{
+        constants::{eval_const, ConstValue},
+        expr::expr,
+        function::{FunctionArg, FunctionDecl},
+    },
 };
 
+/// Struct type with a C compatible (SysV) memory layout
+/// Items are kept in declaration order
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct StructType {
     pub ident: String,
-    pub items: BTreeMap<String, StructItemType>,
+    pub items: Vec<StructItemType>,
+    /// Packed structs have no padding and an alignment of 1
+    pub packed: bool,
+    /// Type parameters of a generic struct
+    pub type_params: Vec<String>,
+    size: usize,
+    align: usize,
 }
 
 impl StructType {
+    pub fn new(ident: String, items: Vec<StructItemType>, packed: bool) -> Self {
+        let mut st = Self {
+            ident,
+            items,
+            packed,
+            type_params: Vec::new(),
+            size: 0,
+            align: 1,
+        };
+        st.compute_layout();
+        st
+    }
+
+    /// Tuples are structs with items named after their position
+    pub fn tuple(items: Vec<VariableType>) -> Self {
+        let names: Vec<String> = items
+            .iter()
+            .map(|t| t.to_string().trim_start_matches('@').to_owned())
+            .collect();
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(i, vtype)| StructItemType::new(i.to_string(), 0, vtype))
+            .collect();
+        Self::new(format!("({})", names.join(",")), items, false)
+    }
+
+    pub fn is_tuple(&self) -> bool {
+        self.ident.starts_with('(')
+    }
+
+    /// Name of the struct without its type arguments
+    pub fn base_ident(&self) -> &str {
+        self.ident.split('<').next().unwrap_or_default()
+    }
+
+    /// Returns the struct item with the given name
+    pub fn get(&self, ident: &str) -> Option<&StructItemType> {
+        self.items.iter().find(|x| x.ident == ident)
+    }
+
+    /// Total size of the struct including tail padding
     pub fn size(&self) -> usize {
-        let mut size = 0;
-        for item in self.items.values() {
-            size += item.offset;
+        self.size
+    }
+
+    /// Alignment of the struct (largest item alignment)
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Compute item offsets, struct size and alignment
+    /// Should be called again after user defined item types are resolved
+    pub fn compute_layout(&mut self) {
+        let mut offset = 0;
+        let mut align = 1;
+        for item in self.items.iter_mut() {
+            let item_align = if self.packed { 1 } else { item.vtype.align() };
+            offset = align_to(offset, item_align);
+            item.offset = offset as i32;
+            offset += item.vtype.size();
+            align = align.max(item_align);
         }
-        size as usize
+        self.align = align;
+        self.size = align_to(offset, align);
+    }
+}
+
+/// Round up offset to the next multiple of align
+pub fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+/// Enum type with optional payload for each variant
+/// The tag is a 4 byte integer followed by the largest payload
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct EnumType {
+    pub ident: String,
+    pub variants: Vec<EnumVariant>,
+    /// Type parameters of a generic enum
+    pub type_params: Vec<String>,
+    payload_offset: usize,
+    size: usize,
+    align: usize,
+}
+
+/// Enum variant and its payload items
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct EnumVariant {
+    pub ident: String,
+    pub payload: StructType,
+}
+
+impl EnumType {
+    pub fn new(ident: String, variants: Vec<EnumVariant>) -> Self {
+        let mut et = Self {
+            ident,
+            variants,
+            type_params: Vec::new(),
+            payload_offset: 4,
+            size: 4,
+            align: 4,
+        };
+        et.compute_layout();
+        et
+    }
+
+    /// Returns the tag and the variant with the given name
+    pub fn get(&self, ident: &str) -> Option<(i32, &EnumVariant)> {
+        self.variants
+            .iter()
+            .enumerate()
+            .find(|(_, v)| v.ident == ident)
+            .map(|(i, v)| (i as i32, v))
+    }
+
+    /// Name of the enum, instances of generic enums are named after their base enum
+    pub fn base_ident(&self) -> &str {
+        self.ident.split('<').next().unwrap_or_default()
+    }
+
+    /// Enums without any payload are plain integers
+    pub fn is_plain(&self) -> bool {
+        self.variants.iter().all(|v| v.payload.items.is_empty())
+    }
+
+    pub fn payload_offset(&self) -> usize {
+        self.payload_offset
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Compute payload layouts, enum size and alignment
+    /// Should be called again after user defined item types are resolved
+    pub fn compute_layout(&mut self) {
+        let mut align = 4;
+        let mut payload_size = 0;
+        for variant in self.variants.iter_mut() {
+            variant.payload.compute_layout();
+            align = align.max(variant.payload.align());
+            payload_size = payload_size.max(variant.payload.size());
+        }
+        self.align = align;
+        self.payload_offset = align_to(4, align);
+        self.size = align_to(self.payload_offset + payload_size, align);
     }
 }
 
@@ -62,6 +220,48 @@ impl StructItemType {
     }
 }
 
+/// Signature of a function value
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct FunctionType {
+    pub args: Vec<VariableType>,
+    pub ret_type: Box<VariableType>,
+}
+
+impl FunctionType {
+    /// Type of a function value with the given declaration
+    pub fn from_decl(decl: &FunctionDecl) -> Self {
+        Self {
+            args: decl.args.iter().map(|a| a.typedef.named()).collect(),
+            ret_type: Box::new(decl.ret_type.named()),
+        }
+    }
+
+    /// Declaration used for calling a function value
+    pub fn decl(&self, ident: &str) -> FunctionDecl {
+        FunctionDecl {
+            is_extern: false,
+            ident: ident.to_owned(),
+            type_params: Vec::new(),
+            args: self
+                .args
+                .iter()
+                .map(|t| FunctionArg {
+                    ident: String::new(),
+                    typedef: t.clone(),
+                })
+                .collect(),
+            ret_type: *self.ret_type.clone(),
+        }
+    }
+}
+
+impl Display for FunctionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args: Vec<String> = self.args.iter().map(|t| t.to_string()).collect();
+        write!(f, "@fn({})->{}", args.join(","), self.ret_type)
+    }
+}
+
 /// Type of any variable or function
 /// Expandable
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -73,10 +273,14 @@ pub enum VariableType {
     Any,
     /// 4 byte integer
     Int,
+    /// 2 byte integer
+    Short,
     /// 64bit float
     Float,
     /// 4 byte unsigned integer
     UInt,
+    /// 2 byte unsigned integer
+    UShort,
     /// 8 byte long
     Long,
     /// 8 byte unsigned long
@@ -89,12 +293,28 @@ pub enum VariableType {
     String,
     /// 8 byte Adress to a memory
     Pointer,
+    /// 8 byte Adress to a value of the given type
+    TypedPointer(Box<VariableType>),
     /// const sized array
     Array(Box<VariableType>, usize),
+    /// 16 byte pointer and length of a dynamically sized array
+    Slice(Box<VariableType>),
+    /// 8 byte Adress of a function
+    Function(FunctionType),
     /// Struct
     Struct(StructType),
+    /// Enum or tagged union
+    Enum(EnumType),
     /// user defined types
     Custom(String),
+    /// user defined generic types and their type arguments
+    Generic(String, Vec<VariableType>),
+    /// Tuple of values before its layout is computed
+    Tuple(Vec<VariableType>),
+    /// Named integer type that is never implicitly cast
+    Distinct(String, Box<VariableType>),
+    /// Another name for an integer type
+    Alias(String, Box<VariableType>),
 }
 
 impl VariableType {
@@ -104,6 +324,8 @@ impl VariableType {
             "?" => Self::Any,
             "int" | "i32" => Self::Int,
             "uint" | "u32" => Self::UInt,
+            "short" | "i16" => Self::Short,
+            "ushort" | "u16" => Self::UShort,
             "ulong" | "u64" => Self::ULong,
             "long" | "i64" => Self::Long,
             "char" | "u8" => Self::Char,
@@ -120,7 +342,8 @@ impl VariableType {
     pub fn item_size(&self) -> u8 {
         match self {
             Self::Array(a, _) => a.size() as u8,
-            Self::String => 8,
+            Self::String | Self::Slice(_) => 8,
+            Self::Enum(_) => 4,
             _ => self.size() as u8,
         }
     }
@@ -129,24 +352,102 @@ impl VariableType {
     pub fn size(&self) -> usize {
         match self {
             Self::Int | Self::UInt => 4,
-            Self::Long | Self::ULong | Self::Pointer => 8,
+            Self::Short | Self::UShort => 2,
+            Self::Long
+            | Self::ULong
+            | Self::Pointer
+            | Self::TypedPointer(_)
+            | Self::Function(_) => 8,
             Self::Bool => 1,
             Self::Char => 1,
-            Self::String => 16,
+            Self::String | Self::Slice(_) => 16,
             Self::Void => 0,
-            Self::Array(t, s) => t.item_size() as usize * s,
+            Self::Array(t, s) => t.stride() * s,
             Self::Float => 8,
-            Self::Custom(_) => 8,
-            Self::Struct(_) => 8,
+            Self::Custom(_) | Self::Generic(..) => 8,
+            Self::Struct(s) => s.size(),
+            Self::Enum(e) => e.size(),
+            Self::Tuple(items) => StructType::tuple(items.clone()).size(),
+            Self::Distinct(_, t) | Self::Alias(_, t) => t.size(),
             Self::Any => todo!(),
         }
     }
 
+    /// Distance between two items of this type in an array
+    pub fn stride(&self) -> usize {
+        match self {
+            Self::Struct(s) => s.size(),
+            Self::Enum(e) => e.size(),
+            Self::Array(_, _) => self.size(),
+            _ => self.item_size() as usize,
+        }
+    }
+
+    /// Size of the memory operand that refers to this type
+    /// Structs and slices have no operand size
+    pub fn mem_size(&self) -> u8 {
+        match self {
+            Self::Struct(_) | Self::Slice(_) | Self::Tuple(_) => 0,
+            Self::Array(t, _) => t.mem_size(),
+            _ => self.item_size(),
+        }
+    }
+
+    /// returns natural alignment of the type
+    pub fn align(&self) -> usize {
+        match self {
+            Self::String | Self::Slice(_) => 8,
+            Self::Void => 1,
+            Self::Array(t, _) => t.align(),
+            Self::Struct(s) => s.align(),
+            Self::Enum(e) => e.align(),
+            Self::Tuple(items) => StructType::tuple(items.clone()).align(),
+            _ => self.size(),
+        }
+    }
+
+    /// Types that are only copied as a whole block of memory
+    pub fn is_aggregate(&self) -> bool {
+        match self {
+            Self::Struct(_) | Self::Slice(_) | Self::Tuple(_) => true,
+            Self::Enum(e) => !e.is_plain(),
+            _ => false,
+        }
+    }
+
     /// checks if type is any
     pub fn is_any(&self) -> bool {
         matches!(self, Self::Any)
     }
 
+    /// Returns a pointer type to this type
+    /// User defined pointee types are kept by name so they can refer to themselves
+    pub fn pointer_to(&self) -> Self {
+        Self::TypedPointer(Box::new(self.named()))
+    }
+
+    pub fn named(&self) -> Self {
+        match self {
+            Self::Struct(s) if s.is_tuple() => {
+                Self::Tuple(s.items.iter().map(|i| i.vtype.named()).collect())
+            }
+            Self::Struct(s) => Self::Custom(s.ident.clone()),
+            Self::Enum(e) => Self::Custom(e.ident.clone()),
+            Self::Array(t, s) => Self::Array(Box::new(t.named()), *s),
+            Self::Slice(t) => Self::Slice(Box::new(t.named())),
+            Self::TypedPointer(t) => Self::TypedPointer(Box::new(t.named())),
+            Self::Generic(s, args) => {
+                Self::Generic(s.clone(), args.iter().map(|t| t.named()).collect())
+            }
+            Self::Tuple(items) => Self::Tuple(items.iter().map(|t| t.named()).collect()),
+            Self::Function(ft) => Self::Function(FunctionType {
+                args: ft.args.iter().map(|t| t.named()).collect(),
+                ret_type: Box::new(ft.ret_type.named()),
+            }),
+            _ => self.clone(),
+        }
+    }
+
     /// Cast two types into a single type
     pub fn cast(&self, other: &Self) -> Result<Self, CompilationError> {
         let cmp = (self, other);
@@ -159,6 +460,45 @@ impl VariableType {
         if cmp.1.is_any() {
             return Ok(self.clone());
         }
+        // Distinct types only mix with themselves
+        if matches!(cmp.0, Self::Distinct(..)) || matches!(cmp.1, Self::Distinct(..)) {
+            return Err(CompilationError::InvalidTypeCasting(
+                cmp.0.to_string(),
+                cmp.1.to_string(),
+            ));
+        }
+        // Aliases mix like the type they name and are kept by name
+        if matches!(cmp.0, Self::Alias(..)) || matches!(cmp.1, Self::Alias(..)) {
+            return match cmp.0.underlying().cast(cmp.1.underlying()) {
+                Ok(res) if res == *cmp.0.underlying() => Ok(cmp.0.clone()),
+                Ok(res) if res == *cmp.1.underlying() => Ok(cmp.1.clone()),
+                Ok(res) => Ok(res),
+                Err(_) => Err(CompilationError::InvalidTypeCasting(
+                    cmp.0.to_string(),
+                    cmp.1.to_string(),
+                )),
+            };
+        }
+        match cmp {
+            // Arrays are viewed as slices of the same item type
+            (Self::Slice(t), Self::Array(t2, _)) if t == t2 => {
+                return Ok(cmp.0.clone());
+            }
+            (Self::TypedPointer(_), Self::TypedPointer(_)) => {
+                return Err(CompilationError::InvalidTypeCasting(
+                    cmp.0.to_string(),
+                    cmp.1.to_string(),
+                ));
+            }
+            // Typed pointers only mix with values that can hold an address
+            (Self::TypedPointer(_), t) if t.holds_address() => {
+                return Ok(cmp.0.clone());
+            }
+            (t, Self::TypedPointer(_)) if t.holds_address() => {
+                return Ok(cmp.1.clone());
+            }
+            _ => (),
+        }
         if cmp.0 == &Self::Pointer || cmp.1 == &Self::Pointer {
             return Ok(Self::Pointer);
         }
@@ -179,12 +519,73 @@ impl VariableType {
         }
     }
 
+    /// Type of an arithmetic operation on two values,
+    /// unsigned integers win over signed integers of the same size
+    pub fn promote(&self, other: &Self) -> Result<Self, CompilationError> {
+        let res = self.cast(other)?;
+        if self.is_integer() && other.is_integer() && self.size() == other.size() {
+            if self.is_unsigned() {
+                return Ok(self.clone());
+            }
+            if other.is_unsigned() {
+                return Ok(other.clone());
+            }
+        }
+        Ok(res)
+    }
+
+    /// Distinct types are only used where the same type is expected
+    pub fn check_distinct(&self, other: &Self) -> Result<(), CompilationError> {
+        let distinct = matches!(self, Self::Distinct(..)) || matches!(other, Self::Distinct(..));
+        if distinct && self != other && !self.is_any() && !other.is_any() {
+            return Err(CompilationError::InvalidTypeCasting(
+                self.to_string(),
+                other.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Untyped pointers and 64-bit integers
+    fn holds_address(&self) -> bool {
+        matches!(self, Self::Pointer | Self::Long | Self::ULong)
+    }
+
+    /// Type that a distinct type or an alias wraps
+    pub fn underlying(&self) -> &Self {
+        match self {
+            Self::Distinct(_, t) | Self::Alias(_, t) => t,
+            _ => self,
+        }
+    }
+
+    /// returns true for unsigned integer types
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self.underlying(), Self::UShort | Self::UInt | Self::ULong)
+    }
+
+    /// returns true for integer types
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.underlying(),
+            Self::Int
+                | Self::Char
+                | Self::Short
+                | Self::UShort
+                | Self::UInt
+                | Self::Long
+                | Self::ULong
+        )
+    }
+
     /// returns true if types can be used mathmaticaly
     pub fn is_numeric(&self) -> bool {
         matches!(
-            self,
+            self.underlying(),
             Self::Int
                 | Self::Char
+                | Self::Short
+                | Self::UShort
                 | Self::UInt
                 | Self::Pointer
                 | Self::Long
@@ -215,35 +616,140 @@ impl Display for VariableType {
         match self {
             VariableType::Any => write!(f, "@?"),
             VariableType::Custom(s) => write!(f, "@{}", s),
+            VariableType::Generic(s, args) => write!(f, "@{}", generic_ident(s, args)),
+            VariableType::Tuple(items) => write!(f, "@{}", StructType::tuple(items.clone()).ident),
             VariableType::Array(t, s) => write!(f, "@[{},{}]", t, s),
+            VariableType::Slice(t) => write!(f, "@[{},?]", t),
+            VariableType::Function(ft) => ft.fmt(f),
             VariableType::String => write!(f, "@str"),
             VariableType::Long => write!(f, "@long"),
             VariableType::ULong => write!(f, "@ulong"),
             VariableType::Int => write!(f, "@int"),
             VariableType::Pointer => write!(f, "@ptr"),
+            VariableType::TypedPointer(t) => {
+                write!(f, "@*{}", t.to_string().trim_start_matches('@'))
+            }
             VariableType::UInt => write!(f, "@uint"),
+            VariableType::Short => write!(f, "@short"),
+            VariableType::UShort => write!(f, "@ushort"),
             VariableType::Bool => write!(f, "@bool"),
             VariableType::Char => write!(f, "@char"),
             VariableType::Void => write!(f, "@void"),
             VariableType::Float => write!(f, "@float"),
             VariableType::Struct(s) => write!(f, "@{}", s.ident),
+            VariableType::Enum(e) => write!(f, "@{}", e.ident),
+            VariableType::Distinct(s, _) | VariableType::Alias(s, _) => write!(f, "@{}", s),
+        }
+    }
+}
+
+/// Name of a generic type instantiated with the given type arguments
+pub fn generic_ident(ident: &str, args: &[VariableType]) -> String {
+    let args: Vec<String> = args
+        .iter()
+        .map(|t| t.to_string().trim_start_matches('@').to_owned())
+        .collect();
+    format!("{ident}<{}>", args.join(","))
+}
+
+/// Parse type parameters of generic functions, structs and enums
+///
+/// Syntax:
+/// <T, U, ..>
+pub fn type_params(lexer: &mut Lexer) -> Vec<String> {
+    let mut params = Vec::new();
+    if lexer.get_token_type() != TokenType::Smaller {
+        return params;
+    }
+    lexer.match_token(TokenType::Smaller);
+    loop {
+        let loc = lexer.get_token_loc();
+        let ident = lexer.get_token().literal;
+        lexer.match_token(TokenType::Identifier);
+        if params.contains(&ident) {
+            error(format!("Type parameter ({ident}) is defined twice"), loc);
+        }
+        params.push(ident);
+        if lexer.get_token_type() != TokenType::Comma {
+            break;
         }
+        lexer.match_token(TokenType::Comma);
+    }
+    lexer.match_token(TokenType::Bigger);
+    params
+}
+
+/// Type alias or distinct type defined at the top level
+/// * ident - name of the type
+/// * vtype - type that the name refers to
+/// * distinct - the type is not implicitly cast to or from its underlying type
+#[derive(Debug, Clone)]
+pub struct TypeAlias {
+    pub ident: String,
+    pub vtype: VariableType,
+    pub distinct: bool,
+}
+
+/// Parse type alias definition
+///
+/// Syntax:
+/// type Name = @type;
+/// distinct type Name = @type;
+pub fn type_alias_def(lexer: &mut Lexer) -> TypeAlias {
+    let distinct = lexer.get_token_type() == TokenType::Distinct;
+    if distinct {
+        lexer.match_token(TokenType::Distinct);
+    }
+    lexer.match_token(TokenType::Type);
+    let ident = lexer.get_token().literal;
+    lexer.match_token(TokenType::Identifier);
+    lexer.match_token(TokenType::Eq);
+    let vtype = type_def(lexer);
+    lexer.match_token(TokenType::SemiColon);
+    TypeAlias {
+        ident,
+        vtype,
+        distinct,
     }
 }
 
 /// Parse type definition
 pub fn type_def(lexer: &mut Lexer) -> VariableType {
-    let loc = lexer.get_current_loc();
     lexer.match_token(TokenType::ATSign);
+    type_body(lexer)
+}
+
+/// Parse type definition after the @ sign
+fn type_body(lexer: &mut Lexer) -> VariableType {
+    let loc = lexer.get_current_loc();
     match lexer.get_token_type() {
+        TokenType::Multi => {
+            lexer.match_token(TokenType::Multi);
+            VariableType::TypedPointer(Box::new(type_body(lexer)))
+        }
+        // Optional values are instances of the built-in Option enum
+        TokenType::QMark => {
+            lexer.match_token(TokenType::QMark);
+            VariableType::Generic("Option".to_string(), vec![type_body(lexer)])
+        }
         TokenType::Identifier => {
             let ident = lexer.get_token().literal;
             lexer.match_token(TokenType::Identifier);
-            VariableType::from_string(ident)
+            if ident == "ptr" && lexer.get_token_type() == TokenType::Smaller {
+                return pointer_type_args(lexer);
+            }
+            if ident == "fn" && lexer.get_token_type() == TokenType::OParen {
+                return function_type_args(lexer);
+            }
+            match VariableType::from_string(ident) {
+                VariableType::Custom(s) if lexer.get_token_type() == TokenType::Smaller => {
+                    VariableType::Generic(s, generic_type_args(lexer))
+                }
+                vtype => vtype,
+            }
         }
         TokenType::OBracket => {
             let var_type: VariableType;
-            let size: usize;
             lexer.match_token(TokenType::OBracket);
             let token = lexer.get_token();
             if token.is_empty() {
@@ -268,28 +774,41 @@ pub fn type_def(lexer: &mut Lexer) -> VariableType {
             if token.is_empty() {
                 error("Error: Expected a Number found EOF", loc);
             }
-            match token.t_type {
-                TokenType::Int(s) => {
-                    size = s as usize;
-                    lexer.match_token(TokenType::Int(s));
-                }
-                TokenType::QMark => {
-                    lexer.match_token(TokenType::QMark);
-                    return VariableType::Pointer;
-                }
-                _ => {
-                    error(
-                        format!(
-                            "Error: Expected Integer Number found ({})",
-                            lexer.get_token_type()
-                        ),
-                        loc,
-                    );
-                }
+            if token.t_type == TokenType::QMark {
+                lexer.match_token(TokenType::QMark);
+                lexer.match_token(TokenType::CBracket);
+                return VariableType::Slice(Box::new(var_type));
             }
+            // Sizes can be any constant expression
+            let size_expr = expr(lexer);
+            let size = match eval_const(&size_expr) {
+                Ok(ConstValue::Num(s, t)) if t.is_integer() && s >= 0 => s as usize,
+                Ok(value) => error(
+                    format!(
+                        "Error: Expected a non-negative integer array size found ({})",
+                        value.vtype()
+                    ),
+                    size_expr.loc,
+                ),
+                Err(e) => error(e, size_expr.loc),
+            };
             lexer.match_token(TokenType::CBracket);
             VariableType::Array(Box::new(var_type), size)
         }
+        TokenType::OParen => {
+            lexer.match_token(TokenType::OParen);
+            let mut items = Vec::new();
+            while lexer.get_token_type() != TokenType::CParen {
+                items.push(type_body(lexer));
+                if lexer.get_token_type() != TokenType::Comma {
+                    break;
+                }
+                lexer.match_token(TokenType::Comma);
+            }
+            lexer.match_token(TokenType::CParen);
+            VariableType::Tuple(items)
+        }
+        TokenType::ATSign => type_def(lexer),
         _ => {
             error(
                 format!("Syntax Error: Unknown Token ({})", lexer.get_token_type()),
@@ -298,3 +817,56 @@ pub fn type_def(lexer: &mut Lexer) -> VariableType {
         }
     }
 }
+
+/// Parse argument and return types of @fn(T, ..)->T
+fn function_type_args(lexer: &mut Lexer) -> VariableType {
+    lexer.match_token(TokenType::OParen);
+    let mut args = Vec::new();
+    while lexer.get_token_type() != TokenType::CParen {
+        args.push(type_body(lexer));
+        if lexer.get_token_type() != TokenType::Comma {
+            break;
+        }
+        lexer.match_token(TokenType::Comma);
+    }
+    lexer.match_token(TokenType::CParen);
+    let ret_type = if lexer.get_token_type() == TokenType::Arrow {
+        lexer.match_token(TokenType::Arrow);
+        type_body(lexer)
+    } else {
+        VariableType::Void
+    };
+    VariableType::Function(FunctionType {
+        args,
+        ret_type: Box::new(ret_type),
+    })
+}
+
+/// Parse the pointee type of @ptr<T>
+fn pointer_type_args(lexer: &mut Lexer) -> VariableType {
+    lexer.match_token(TokenType::Smaller);
+    let pointee = type_body(lexer);
+    close_type_args(lexer);
+    VariableType::TypedPointer(Box::new(pointee))
+}
+
+/// Parse the type arguments of @Name<T, ..>
+fn generic_type_args(lexer: &mut Lexer) -> Vec<VariableType> {
+    lexer.match_token(TokenType::Smaller);
+    let mut args = vec![type_body(lexer)];
+    while lexer.get_token_type() == TokenType::Comma {
+        lexer.match_token(TokenType::Comma);
+        args.push(type_body(lexer));
+    }
+    close_type_args(lexer);
+    args
+}
+
+fn close_type_args(lexer: &mut Lexer) {
+    // Closing brackets of nested type arguments are read as a shift operator
+    if lexer.get_token_type() == TokenType::Rsh {
+        lexer.token.t_type = TokenType::Bigger;
+    } else {
+        lexer.match_token(TokenType::Bigger);
+    }
+}
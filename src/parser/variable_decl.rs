@@ -29,7 +29,7 @@ use crate::{
 };
 
 use super::{
-    expr::{expr, Expr},
+    expr::{expr, Expr, ExprType},
     types::VariableType,
 };
 
@@ -51,7 +51,15 @@ pub struct VariableDeclare {
 /// parse variable declare
 pub fn inline_variable_declare(lexer: &mut Lexer) -> VariableDeclare {
     let ident_token = lexer.get_token();
-    let _loc = lexer.get_token_loc();
+    if lexer.constants.contains_key(&ident_token.literal) {
+        error(
+            format!(
+                "Constant ({}) can not be redefined as a variable",
+                ident_token.literal
+            ),
+            lexer.get_token_loc(),
+        );
+    }
     lexer.match_token(TokenType::Identifier);
     let mut is_mutable: bool = true;
     let mut v_type: VariableType = VariableType::Any;
@@ -101,3 +109,68 @@ pub fn variable_declare(lexer: &mut Lexer) -> VariableDeclare {
     lexer.match_token(TokenType::Var);
     inline_variable_declare(lexer)
 }
+
+/// Parse Variable Declarations
+/// Destructured tuples are stored in a hidden variable and each name is declared from its item
+///
+/// Syntax:
+/// var (a, b, _) = expr;
+pub fn variable_declares(lexer: &mut Lexer) -> Vec<VariableDeclare> {
+    lexer.match_token(TokenType::Var);
+    if lexer.get_token_type() != TokenType::OParen {
+        return vec![inline_variable_declare(lexer)];
+    }
+    let loc = lexer.get_token_loc();
+    lexer.match_token(TokenType::OParen);
+    let mut idents = Vec::new();
+    while lexer.get_token_type() != TokenType::CParen {
+        idents.push(lexer.get_token().literal);
+        lexer.match_token(TokenType::Identifier);
+        if lexer.get_token_type() != TokenType::Comma {
+            break;
+        }
+        lexer.match_token(TokenType::Comma);
+    }
+    lexer.match_token(TokenType::CParen);
+    let mutable = match lexer.get_token_type() {
+        TokenType::ColonEq => false,
+        TokenType::Eq => true,
+        _ => error(
+            format!(
+                "Expected \"=\" or \":=\" found ({})",
+                lexer.get_token_type()
+            ),
+            loc,
+        ),
+    };
+    lexer.next_token();
+    let value = expr(lexer);
+    let tuple_ident = format!("$tuple{}:{}", loc.line, loc.col);
+    let mut decls = vec![VariableDeclare {
+        mutable: false,
+        ident: tuple_ident.clone(),
+        v_type: VariableType::Any,
+        init_value: Some(value),
+        loc: loc.clone(),
+    }];
+    for (i, ident) in idents.into_iter().enumerate() {
+        if ident == "_" {
+            continue;
+        }
+        let tuple = Expr {
+            etype: ExprType::Variable(tuple_ident.clone()),
+            loc: loc.clone(),
+        };
+        decls.push(VariableDeclare {
+            mutable,
+            ident,
+            v_type: VariableType::Any,
+            init_value: Some(Expr {
+                etype: ExprType::Access(Box::new(tuple), i.to_string()),
+                loc: loc.clone(),
+            }),
+            loc: loc.clone(),
+        });
+    }
+    decls
+}
@@ -51,7 +51,47 @@ fn compile_and_test(input: &str, co: CompilerOptions, res_path: &str) {
         String::from_utf8(output.stdout).unwrap(),
         expectation.to_string()
     );
-    remove_file(&opath).unwrap_or_else(|_| ());
+    remove_file(&opath).unwrap_or(());
+}
+
+/// Expects the program to exit with a runtime panic message after printing the result
+fn compile_and_expect_panic(input: &str, co: CompilerOptions, res_path: &str, msg: &str) {
+    setup_compiler(input.into(), &co);
+    let opath = co
+        .output_path
+        .unwrap()
+        .with_extension("")
+        .to_string_lossy()
+        .to_string();
+    let output = Command::new(&opath)
+        .output()
+        .expect("Error Executing the program!");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().contains(msg));
+    let expectation = std::fs::read_to_string(res_path).unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        expectation.to_string()
+    );
+    remove_file(&opath).unwrap_or(());
+}
+
+/// Builds a static library from a C source file for the tests that link against it
+fn build_c_lib(src: &str, name: &str) {
+    std::fs::create_dir_all("./build").unwrap();
+    let obj = format!("./build/{name}.o");
+    let lib = format!("./build/lib{name}.a");
+    let status = Command::new("cc")
+        .args(["-c", src, "-o", &obj])
+        .status()
+        .expect("Can not compile using cc command!");
+    assert!(status.success());
+    remove_file(&lib).unwrap_or(());
+    let status = Command::new("ar")
+        .args(["rcs", &lib, &obj])
+        .status()
+        .expect("Can not archive using ar command!");
+    assert!(status.success());
 }
 
 macro_rules! test_elf {
@@ -131,6 +171,75 @@ mod asm {
         "./tests/string_expr.txt"
     );
     test_asm!(structs, "./tests/structs.nmt", "./tests/structs.txt");
+    test_asm!(
+        struct_copy,
+        "./tests/struct_copy.nmt",
+        "./tests/struct_copy.txt"
+    );
+    test_asm!(enums, "./tests/enums.nmt", "./tests/enums.txt");
+    test_asm!(pointers, "./tests/pointers.nmt", "./tests/pointers.txt");
+    test_asm!(slices, "./tests/slices.nmt", "./tests/slices.txt");
+    test_asm!(
+        byte_assign,
+        "./tests/byte_assign.nmt",
+        "./tests/byte_assign.txt"
+    );
+    test_asm!(generics, "./tests/generics.nmt", "./tests/generics.txt");
+    test_asm!(methods, "./tests/methods.nmt", "./tests/methods.txt");
+    test_asm!(tuples, "./tests/tuples.nmt", "./tests/tuples.txt");
+    test_asm!(results, "./tests/results.nmt", "./tests/results.txt");
+    test_asm!(constants, "./tests/constants.nmt", "./tests/constants.txt");
+    test_asm!(literals, "./tests/literals.nmt", "./tests/literals.txt");
+    test_asm!(
+        static_data,
+        "./tests/static_data.nmt",
+        "./tests/static_data.txt"
+    );
+    test_asm!(
+        block_defer,
+        "./tests/block_defer.nmt",
+        "./tests/block_defer.txt"
+    );
+    test_asm!(for_range, "./tests/for_range.nmt", "./tests/for_range.txt");
+    test_asm!(
+        bitwise_ops,
+        "./tests/bitwise_ops.nmt",
+        "./tests/bitwise_ops.txt"
+    );
+    test_asm!(
+        short_circuit,
+        "./tests/short_circuit.nmt",
+        "./tests/short_circuit.txt"
+    );
+    test_asm!(if_expr, "./tests/if_expr.nmt", "./tests/if_expr.txt");
+    test_asm!(
+        loop_labels,
+        "./tests/loop_labels.nmt",
+        "./tests/loop_labels.txt"
+    );
+    test_asm!(
+        type_alias,
+        "./tests/type_alias.nmt",
+        "./tests/type_alias.txt"
+    );
+    test_asm!(
+        short_ints,
+        "./tests/short_ints.nmt",
+        "./tests/short_ints.txt"
+    );
+    test_asm!(casts, "./tests/casts.nmt", "./tests/casts.txt");
+    test_asm!(integers, "./tests/integers.nmt", "./tests/integers.txt");
+    test_asm!(
+        fn_pointers,
+        "./tests/fn_pointers.nmt",
+        "./tests/fn_pointers.txt",
+        "-lc"
+    );
+    test_asm!(
+        struct_chain,
+        "./tests/struct_chain.nmt",
+        "./tests/struct_chain.txt"
+    );
     test_asm!(loops, "./tests/loops.nmt", "./tests/loops.txt");
     test_asm!(
         conditions,
@@ -141,6 +250,25 @@ mod asm {
     test_asm!(assign, "./tests/assign.nmt", "./tests/assign.txt");
     test_asm!(arrays, "./tests/arrays.nmt", "./tests/arrays.txt");
     test_asm!(defer, "./tests/defer.nmt", "./tests/defer.txt");
+    test_asm!(floats, "./tests/floats.nmt", "./tests/floats.txt");
+    test_asm!(
+        stack_args,
+        "./tests/stack_args.nmt",
+        "./tests/stack_args.txt"
+    );
+
+    #[test]
+    fn struct_layout() {
+        build_c_lib("./tests/layout.c", "__asm_layout");
+        let co = CompilerOptions {
+            output_path: Some(Path::new("./build/__asm_struct_layout").to_owned()),
+            use_nasm: true,
+            keep_obj: true,
+            linker_flags: ["-L./build", "-l__asm_layout"].map(String::from).to_vec(),
+            ..CompilerOptions::default()
+        };
+        compile_and_test("./tests/struct_layout.nmt", co, "./tests/struct_layout.txt");
+    }
 }
 
 mod elf {
@@ -180,6 +308,75 @@ mod elf {
         "./tests/string_expr.txt"
     );
     test_elf!(structs, "./tests/structs.nmt", "./tests/structs.txt");
+    test_elf!(
+        struct_copy,
+        "./tests/struct_copy.nmt",
+        "./tests/struct_copy.txt"
+    );
+    test_elf!(enums, "./tests/enums.nmt", "./tests/enums.txt");
+    test_elf!(pointers, "./tests/pointers.nmt", "./tests/pointers.txt");
+    test_elf!(slices, "./tests/slices.nmt", "./tests/slices.txt");
+    test_elf!(
+        byte_assign,
+        "./tests/byte_assign.nmt",
+        "./tests/byte_assign.txt"
+    );
+    test_elf!(generics, "./tests/generics.nmt", "./tests/generics.txt");
+    test_elf!(methods, "./tests/methods.nmt", "./tests/methods.txt");
+    test_elf!(tuples, "./tests/tuples.nmt", "./tests/tuples.txt");
+    test_elf!(results, "./tests/results.nmt", "./tests/results.txt");
+    test_elf!(constants, "./tests/constants.nmt", "./tests/constants.txt");
+    test_elf!(literals, "./tests/literals.nmt", "./tests/literals.txt");
+    test_elf!(
+        static_data,
+        "./tests/static_data.nmt",
+        "./tests/static_data.txt"
+    );
+    test_elf!(
+        block_defer,
+        "./tests/block_defer.nmt",
+        "./tests/block_defer.txt"
+    );
+    test_elf!(for_range, "./tests/for_range.nmt", "./tests/for_range.txt");
+    test_elf!(
+        bitwise_ops,
+        "./tests/bitwise_ops.nmt",
+        "./tests/bitwise_ops.txt"
+    );
+    test_elf!(
+        short_circuit,
+        "./tests/short_circuit.nmt",
+        "./tests/short_circuit.txt"
+    );
+    test_elf!(if_expr, "./tests/if_expr.nmt", "./tests/if_expr.txt");
+    test_elf!(
+        loop_labels,
+        "./tests/loop_labels.nmt",
+        "./tests/loop_labels.txt"
+    );
+    test_elf!(
+        type_alias,
+        "./tests/type_alias.nmt",
+        "./tests/type_alias.txt"
+    );
+    test_elf!(
+        short_ints,
+        "./tests/short_ints.nmt",
+        "./tests/short_ints.txt"
+    );
+    test_elf!(casts, "./tests/casts.nmt", "./tests/casts.txt");
+    test_elf!(integers, "./tests/integers.nmt", "./tests/integers.txt");
+    test_elf!(
+        fn_pointers,
+        "./tests/fn_pointers.nmt",
+        "./tests/fn_pointers.txt",
+        "-lc"
+    );
+    test_elf!(
+        struct_chain,
+        "./tests/struct_chain.nmt",
+        "./tests/struct_chain.txt"
+    );
     test_elf!(loops, "./tests/loops.nmt", "./tests/loops.txt");
     test_elf!(
         conditions,
@@ -190,4 +387,72 @@ mod elf {
     test_elf!(assign, "./tests/assign.nmt", "./tests/assign.txt");
     test_elf!(arrays, "./tests/arrays.nmt", "./tests/arrays.txt");
     test_elf!(defer, "./tests/defer.nmt", "./tests/defer.txt");
+    test_elf!(floats, "./tests/floats.nmt", "./tests/floats.txt");
+    test_elf!(
+        stack_args,
+        "./tests/stack_args.nmt",
+        "./tests/stack_args.txt"
+    );
+
+    #[test]
+    fn struct_layout() {
+        build_c_lib("./tests/layout.c", "__elf_layout");
+        let co = CompilerOptions {
+            output_path: Some(Path::new("./build/__elf_struct_layout").to_owned()),
+            keep_obj: true,
+            linker_flags: ["-L./build", "-l__elf_layout"].map(String::from).to_vec(),
+            ..CompilerOptions::default()
+        };
+        compile_and_test("./tests/struct_layout.nmt", co, "./tests/struct_layout.txt");
+    }
+
+    #[test]
+    fn bounds_check() {
+        let co = CompilerOptions {
+            output_path: Some(Path::new("./build/__elf_bounds_check").to_owned()),
+            keep_obj: true,
+            ..CompilerOptions::default()
+        };
+        compile_and_expect_panic(
+            "./tests/bounds_check.nmt",
+            co,
+            "./tests/bounds_check.txt",
+            "bounds_check.nmt:2:17: Index out of bounds",
+        );
+    }
+
+    #[test]
+    fn unwrap_panic() {
+        let co = CompilerOptions {
+            output_path: Some(Path::new("./build/__elf_unwrap_panic").to_owned()),
+            keep_obj: true,
+            ..CompilerOptions::default()
+        };
+        compile_and_expect_panic(
+            "./tests/unwrap_panic.nmt",
+            co,
+            "./tests/unwrap_panic.txt",
+            "unwrap_panic.nmt:10:17: Called unwrap on Option::None",
+        );
+    }
+
+    #[test]
+    fn shared_lib() {
+        // Statics of a shared object must be addressed relative to RIP
+        let lib = CompilerOptions {
+            output_path: Some(Path::new("./build/lib__elf_shared").to_owned()),
+            dynamic_lib: true,
+            ..CompilerOptions::default()
+        };
+        setup_compiler("./tests/shared_lib.nmt".into(), &lib);
+        let co = CompilerOptions {
+            output_path: Some(Path::new("./build/__elf_shared_main").to_owned()),
+            keep_obj: true,
+            linker_flags: ["-L./build", "-l__elf_shared", "-rpath", "./build"]
+                .map(String::from)
+                .to_vec(),
+            ..CompilerOptions::default()
+        };
+        compile_and_test("./tests/shared_main.nmt", co, "./tests/shared_main.txt");
+    }
 }
@@ -30,14 +30,13 @@ pub type IBytes = Vec<u8>;
 #[allow(unused)]
 pub fn get_program_name(path: impl ToString) -> String {
     let path = path.to_string();
-    return path
-        .split('/')
-        .last()
+    path.split('/')
+        .next_back()
         .unwrap()
         .split('.')
         .next()
         .unwrap()
-        .to_string();
+        .to_string()
 }
 
 /// padding right (like the padding_left library in js)
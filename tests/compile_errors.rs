@@ -0,0 +1,63 @@
+/**********************************************************************************************
+*
+*   compile_errors: Programs that should be rejected by the compiler
+*
+*   Each program in /tests/errors is compiled and the compiler is expected to fail
+*   with the given error message
+*
+*   LICENSE: MIT
+*
+*   Copyright (c) 2023-2024 Mahan Farzaneh (@mahanfr)
+*
+*   This software is provided "as-is", without any express or implied warranty. In no event
+*   will the authors be held liable for any damages arising from the use of this software.
+*
+*   Permission is granted to anyone to use this software for any purpose, including commercial
+*   applications, and to alter it and redistribute it freely, subject to the following restrictions:
+*
+*     1. The origin of this software must not be misrepresented; you must not claim that you
+*     wrote the original software. If you use this software in a product, an acknowledgment
+*     in the product documentation would be appreciated but is not required.
+*
+*     2. Altered source versions must be plainly marked as such, and must not be misrepresented
+*     as being the original software.
+*
+*     3. This notice may not be removed or altered from any source distribution.
+*
+**********************************************************************************************/
+use std::process::Command;
+
+fn compile_and_expect_error(input: &str, msg: &str) {
+    let name = input.rsplit('/').next().unwrap().trim_end_matches(".nmt");
+    let output = Command::new(env!("CARGO_BIN_EXE_nmet"))
+        .args([input, "-o", &format!("./build/__err_{name}")])
+        .output()
+        .expect("Error Executing the compiler!");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(msg), "{stderr}");
+}
+
+#[test]
+fn literal_overflow() {
+    compile_and_expect_error(
+        "./tests/errors/literal_overflow.nmt",
+        "literal_overflow.nmt:2:5] Constant value (300) overflows type (@char)",
+    );
+}
+
+#[test]
+fn unexpected_statement() {
+    compile_and_expect_error(
+        "./tests/errors/unexpected_statement.nmt",
+        "Unexpected Token (=>) at the start of a statement",
+    );
+}
+
+#[test]
+fn alias_mismatch() {
+    compile_and_expect_error(
+        "./tests/errors/alias_mismatch.nmt",
+        "Types (@str) and (@Fd) can not be casted to eachother for this operation",
+    );
+}
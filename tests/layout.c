@@ -0,0 +1,50 @@
+// Functions used by tests/struct_layout.nmt
+// the struct_layout tests compile it into a static library in ./build
+#include <stddef.h>
+
+struct Vec2 {
+    int x;
+    int y;
+};
+
+struct Entity {
+    _Bool alive;
+    long id;
+    struct Vec2 pos;
+    int hp;
+    struct Vec2 path[2];
+    char tag;
+};
+
+struct __attribute__((packed)) Header {
+    char kind;
+    int len;
+    long flags;
+};
+
+long entity_layout(void) {
+    return offsetof(struct Entity, id) * 1000000 + offsetof(struct Entity, pos) * 10000 +
+           offsetof(struct Entity, path) * 100 + sizeof(struct Entity);
+}
+
+void entity_fill(struct Entity *e) {
+    e->alive = 1;
+    e->id = 7;
+    e->pos.x = 1;
+    e->pos.y = 2;
+    e->hp = 100;
+    e->path[0].x = 3;
+    e->path[0].y = 4;
+    e->path[1].x = 5;
+    e->path[1].y = 6;
+    e->tag = 9;
+}
+
+long entity_sum(struct Entity *e) {
+    return e->alive + e->id + e->pos.x + e->pos.y + e->hp + e->path[0].x + e->path[0].y +
+           e->path[1].x + e->path[1].y + e->tag;
+}
+
+long header_sum(struct Header *h) {
+    return h->kind + h->len + h->flags + sizeof(struct Header);
+}